@@ -1,8 +1,8 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    attr, to_binary, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response, StdError,
-    StdResult, SubMsg, Uint128, WasmMsg,
+    attr, to_binary, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+    SubMsg, Uint128, WasmMsg,
 };
 
 use crate::state::{
@@ -11,9 +11,19 @@ use crate::state::{
 };
 use basset::airdrop::{
     ANCAirdropHandleMsg, AirdropInfo, AirdropInfoElem, AirdropInfoResponse, ConfigResponse,
-    ExecuteMsg, InstantiateMsg, MIRAirdropHandleMsg, PairHandleMsg, QueryMsg,
+    ExecuteMsg, InstantiateMsg, MIRAirdropHandleMsg, MigrateMsg, PairHandleMsg, QueryMsg,
 };
+use basset::contract_error::ContractError;
 use basset::hub::ExecuteMsg as HubHandleMsg;
+use basset::ownership::{
+    accept_ownership, cancel_ownership_proposal, propose_new_owner, query_pending_owner,
+};
+use basset::version::migrate_contract_version;
+use cw2::set_contract_version;
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:anchor-airdrop-registry";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -21,8 +31,10 @@ pub fn instantiate(
     _env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
-) -> StdResult<Response> {
-    let sndr_raw = deps.api.addr_canonicalize(&info.sender.to_string())?;
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let sndr_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
 
     let config = Config {
         owner: sndr_raw,
@@ -37,7 +49,12 @@ pub fn instantiate(
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> StdResult<Response> {
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::FabricateMIRClaim {
             stage,
@@ -50,10 +67,16 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
             proof,
         } => execute_fabricate_anchor_claim(deps, env, info, stage, amount, proof),
         ExecuteMsg::UpdateConfig {
-            owner,
             hub_contract,
             reward_contract,
-        } => execute_update_config(deps, env, info, owner, hub_contract, reward_contract),
+        } => execute_update_config(deps, env, info, hub_contract, reward_contract),
+        ExecuteMsg::ProposeNewOwner { new_owner } => {
+            execute_propose_new_owner(deps, env, info, new_owner)
+        }
+        ExecuteMsg::AcceptOwnership {} => execute_accept_ownership(deps, env, info),
+        ExecuteMsg::CancelOwnershipProposal {} => {
+            execute_cancel_ownership_proposal(deps, env, info)
+        }
         ExecuteMsg::AddAirdropInfo {
             airdrop_token,
             airdrop_info,
@@ -75,7 +98,7 @@ fn execute_fabricate_mir_claim(
     stage: u8,
     amount: Uint128,
     proof: Vec<String>,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     let config = read_config(deps.storage)?;
 
     let mut messages: Vec<SubMsg> = vec![];
@@ -113,7 +136,7 @@ fn execute_fabricate_anchor_claim(
     stage: u8,
     amount: Uint128,
     proof: Vec<String>,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     let config = read_config(deps.storage)?;
 
     let mut messages: Vec<SubMsg> = vec![];
@@ -147,21 +170,16 @@ pub fn execute_update_config(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    owner: Option<String>,
     hub_contract: Option<String>,
     reward_contract: Option<String>,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     // only owner can send this message.
     let mut config = read_config(deps.storage)?;
-    let sender_raw = deps.api.addr_canonicalize(&info.sender.to_string())?;
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
     if sender_raw != config.owner {
-        return Err(StdError::generic_err("unauthorized"));
+        return Err(ContractError::Unauthorized {});
     }
 
-    if let Some(o) = owner {
-        let owner_raw = deps.api.addr_canonicalize(&o)?;
-        config.owner = owner_raw
-    }
     if let Some(hub) = hub_contract {
         config.hub_contract = hub;
     }
@@ -174,26 +192,68 @@ pub fn execute_update_config(
     Ok(Response::new().add_attributes(vec![attr("action", "update_config")]))
 }
 
+pub fn execute_propose_new_owner(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    new_owner: String,
+) -> Result<Response, ContractError> {
+    let config = read_config(deps.storage)?;
+    propose_new_owner(deps, &info.sender, &config.owner, &new_owner)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "propose_new_owner"),
+        attr("new_owner", new_owner),
+    ]))
+}
+
+pub fn execute_accept_ownership(
+    mut deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let new_owner = accept_ownership(deps.branch(), &info.sender)?;
+    CONFIG.update(deps.storage, |mut conf| -> StdResult<Config> {
+        conf.owner = new_owner;
+        Ok(conf)
+    })?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "accept_ownership"),
+        attr("owner", info.sender),
+    ]))
+}
+
+pub fn execute_cancel_ownership_proposal(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = read_config(deps.storage)?;
+    cancel_ownership_proposal(deps, &info.sender, &config.owner)?;
+
+    Ok(Response::new().add_attributes(vec![attr("action", "cancel_ownership_proposal")]))
+}
+
 pub fn execute_add_airdrop(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
     airdrop_token: String,
     airdrop_info: AirdropInfo,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     // only owner can send this message.
     let config = read_config(deps.storage)?;
-    let sender_raw = deps.api.addr_canonicalize(&info.sender.to_string())?;
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
     if sender_raw != config.owner {
-        return Err(StdError::generic_err("unauthorized"));
+        return Err(ContractError::Unauthorized {});
     }
 
     let exists = read_airdrop_info(deps.storage, airdrop_token.clone());
     if exists.is_ok() {
-        return Err(StdError::generic_err(format!(
-            "There is a token info with this {}",
-            airdrop_token
-        )));
+        return Err(ContractError::AirdropTokenExists {
+            token: airdrop_token,
+        });
     }
 
     CONFIG.update(deps.storage, |mut conf| -> StdResult<Config> {
@@ -215,20 +275,19 @@ pub fn execute_update_airdrop(
     info: MessageInfo,
     airdrop_token: String,
     airdrop_info: AirdropInfo,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     // only owner can send this message.
     let config = read_config(deps.storage)?;
-    let sender_raw = deps.api.addr_canonicalize(&info.sender.to_string())?;
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
     if sender_raw != config.owner {
-        return Err(StdError::generic_err("unauthorized"));
+        return Err(ContractError::Unauthorized {});
     }
 
     let exists = read_airdrop_info(deps.storage, airdrop_token.clone());
     if exists.is_err() {
-        return Err(StdError::generic_err(format!(
-            "There is no token info with this {}",
-            airdrop_token
-        )));
+        return Err(ContractError::AirdropTokenNotFound {
+            token: airdrop_token,
+        });
     }
 
     update_airdrop_info(deps.storage, airdrop_token.clone(), airdrop_info)?;
@@ -243,20 +302,19 @@ pub fn execute_remove_airdrop(
     _env: Env,
     info: MessageInfo,
     airdrop_token: String,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     // only owner can send this message.
     let config = read_config(deps.storage)?;
-    let sender_raw = deps.api.addr_canonicalize(&info.sender.to_string())?;
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
     if sender_raw != config.owner {
-        return Err(StdError::generic_err("unauthorized"));
+        return Err(ContractError::Unauthorized {});
     }
 
     let exists = read_airdrop_info(deps.storage, airdrop_token.clone());
     if exists.is_err() {
-        return Err(StdError::generic_err(format!(
-            "There is no token info with this {}",
-            airdrop_token
-        )));
+        return Err(ContractError::AirdropTokenNotFound {
+            token: airdrop_token,
+        });
     }
 
     CONFIG.update(deps.storage, |mut conf| -> StdResult<Config> {
@@ -272,8 +330,18 @@ pub fn execute_remove_airdrop(
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    migrate_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "migrate"),
+        attr("version", CONTRACT_VERSION),
+    ]))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+    let res = match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
         QueryMsg::AirdropInfo {
             airdrop_token,
@@ -285,7 +353,8 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             start_after,
             limit,
         )?),
-    }
+    };
+    Ok(res?)
 }
 
 fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
@@ -294,6 +363,7 @@ fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
 
     Ok(ConfigResponse {
         owner: owner_addr.to_string(),
+        pending_owner: query_pending_owner(deps)?,
         hub_contract: config.hub_contract,
         reward_contract: config.reward_contract,
         airdrop_tokens: config.airdrop_tokens,
@@ -1,17 +1,19 @@
-use crate::contract::{execute, instantiate, query};
+use crate::contract::{execute, instantiate, migrate, query};
 use basset::airdrop::{
     ANCAirdropHandleMsg, AirdropInfoElem, AirdropInfoResponse, ConfigResponse, ExecuteMsg,
-    InstantiateMsg, MIRAirdropHandleMsg, PairHandleMsg, QueryMsg,
+    InstantiateMsg, MIRAirdropHandleMsg, MigrateMsg, PairHandleMsg, QueryMsg,
 };
 
 use basset::airdrop::AirdropInfo;
 use basset::airdrop::ExecuteMsg::UpdateConfig;
+use basset::contract_error::ContractError;
 use basset::hub::ExecuteMsg::ClaimAirdrop;
 use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
 use cosmwasm_std::{
-    attr, from_binary, to_binary, CosmosMsg, DepsMut, Env, MessageInfo, Response, StdError, SubMsg,
-    Uint128, WasmMsg,
+    attr, from_binary, to_binary, CosmosMsg, DepsMut, Env, MessageInfo, Response, SubMsg, Uint128,
+    WasmMsg,
 };
+use cw2::{get_contract_version, set_contract_version};
 
 fn do_init(deps: DepsMut, env: Env, info: MessageInfo) {
     let init_msg = InstantiateMsg {
@@ -61,6 +63,7 @@ fn proper_init() {
 
     let expected = ConfigResponse {
         owner: "owner".to_string(),
+        pending_owner: None,
         hub_contract: "hub_contract".to_string(),
         reward_contract: "reward_contract".to_string(),
         airdrop_tokens: vec![],
@@ -180,7 +183,7 @@ fn proper_add_airdrop_info() {
     let owner = "invalid";
     let invalid_info = mock_info(owner, &[]);
     let res = execute(deps.as_mut(), mock_env(), invalid_info, msg.clone());
-    assert_eq!(res.unwrap_err(), StdError::generic_err("unauthorized"));
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
 
     let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
     assert_eq!(res.messages.len(), 0);
@@ -219,6 +222,7 @@ fn proper_add_airdrop_info() {
 
     let expected = ConfigResponse {
         owner: "owner".to_string(),
+        pending_owner: None,
         hub_contract: "hub_contract".to_string(),
         reward_contract: "reward_contract".to_string(),
         airdrop_tokens: vec!["MIR".to_string()],
@@ -239,7 +243,9 @@ fn proper_add_airdrop_info() {
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
     assert_eq!(
         res,
-        StdError::generic_err("There is a token info with this MIR")
+        ContractError::AirdropTokenExists {
+            token: "MIR".to_string()
+        }
     );
 }
 
@@ -260,7 +266,7 @@ fn proper_remove_airdrop_info() {
     // only owner can send this
     let invalid_info = mock_info("invalid", &[]);
     let res = execute(deps.as_mut(), mock_env(), invalid_info, msg.clone());
-    assert_eq!(res.unwrap_err(), StdError::generic_err("unauthorized"));
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
 
     let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
     assert_eq!(res.messages.len(), 0);
@@ -277,6 +283,7 @@ fn proper_remove_airdrop_info() {
 
     let expected = ConfigResponse {
         owner: "owner".to_string(),
+        pending_owner: None,
         hub_contract: "hub_contract".to_string(),
         reward_contract: "reward_contract".to_string(),
         airdrop_tokens: vec![],
@@ -310,7 +317,9 @@ fn proper_remove_airdrop_info() {
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
     assert_eq!(
         res,
-        StdError::generic_err("There is no token info with this BUZZ")
+        ContractError::AirdropTokenNotFound {
+            token: "BUZZ".to_string()
+        }
     );
 }
 
@@ -338,7 +347,7 @@ fn proper_update_airdrop_info() {
     // only owner can send this
     let invalid_info = mock_info("invalid", &[]);
     let res = execute(deps.as_mut(), mock_env(), invalid_info, msg.clone());
-    assert_eq!(res.unwrap_err(), StdError::generic_err("unauthorized"));
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
 
     let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
     assert_eq!(res.messages.len(), 0);
@@ -423,7 +432,9 @@ fn proper_update_airdrop_info() {
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
     assert_eq!(
         res,
-        StdError::generic_err("There is no token info with this BUZZ")
+        ContractError::AirdropTokenNotFound {
+            token: "BUZZ".to_string()
+        }
     );
 }
 
@@ -442,6 +453,7 @@ pub fn proper_update_config() {
         from_binary(&query(deps.as_ref(), mock_env(), query_update_config).unwrap()).unwrap();
     let expected = ConfigResponse {
         owner: "owner".to_string(),
+        pending_owner: None,
         hub_contract: "hub_contract".to_string(),
         reward_contract: "reward_contract".to_string(),
         airdrop_tokens: vec!["MIR".to_string()],
@@ -449,18 +461,51 @@ pub fn proper_update_config() {
     assert_eq!(expected, res);
 
     let update_conf = UpdateConfig {
-        owner: Some("new_owner".to_string()),
         hub_contract: Some("new_hub_contract".to_string()),
         reward_contract: Some("new_reward_contract".to_string()),
     };
-    let res = execute(deps.as_mut(), mock_env(), info, update_conf).unwrap();
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), update_conf).unwrap();
     assert_eq!(res.messages.len(), 0);
 
+    // the ownership only moves once the new owner accepts it
+    let propose = ExecuteMsg::ProposeNewOwner {
+        new_owner: "new_owner".to_string(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("new_owner", &[]),
+        propose.clone(),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+    execute(deps.as_mut(), mock_env(), info, propose).unwrap();
+
+    let res: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(res.owner, "owner".to_string());
+    assert_eq!(res.pending_owner, Some("new_owner".to_string()));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("other", &[]),
+        ExecuteMsg::AcceptOwnership {},
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("new_owner", &[]),
+        ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap();
+
     let query_update_config = QueryMsg::Config {};
     let res: ConfigResponse =
         from_binary(&query(deps.as_ref(), mock_env(), query_update_config).unwrap()).unwrap();
     let expected = ConfigResponse {
         owner: "new_owner".to_string(),
+        pending_owner: None,
         hub_contract: "new_hub_contract".to_string(),
         reward_contract: "new_reward_contract".to_string(),
         airdrop_tokens: vec!["MIR".to_string()],
@@ -498,6 +543,7 @@ fn proper_query() {
         from_binary(&query(deps.as_ref(), mock_env(), query_update_config).unwrap()).unwrap();
     let expected = ConfigResponse {
         owner: "owner".to_string(),
+        pending_owner: None,
         hub_contract: "hub_contract".to_string(),
         reward_contract: "reward_contract".to_string(),
         airdrop_tokens: vec!["MIR".to_string(), "ANC".to_string(), "BUZZ".to_string()],
@@ -580,3 +626,30 @@ fn proper_query() {
         }
     );
 }
+
+#[test]
+fn proper_migrate() {
+    let mut deps = mock_dependencies(&[]);
+    do_init(deps.as_mut(), mock_env(), mock_info("owner", &[]));
+
+    let version = get_contract_version(&deps.storage).unwrap();
+    assert_eq!(version.contract, "crates.io:anchor-airdrop-registry");
+
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+    // downgrades are refused
+    set_contract_version(
+        &mut deps.storage,
+        "crates.io:anchor-airdrop-registry",
+        "9.0.0",
+    )
+    .unwrap();
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {});
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::CannotDowngrade {
+            stored: "9.0.0".to_string(),
+            new: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    );
+}
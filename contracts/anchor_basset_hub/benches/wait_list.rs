@@ -0,0 +1,88 @@
+//! Wait list operations with thousands of outstanding unbond requests.
+//!
+//! Run with `cargo bench --bench wait_list`.
+
+use std::time::{Duration, Instant};
+
+use anchor_basset_hub::state::{
+    get_batch_unbond_requests, get_unbond_requests, query_get_finished_amount,
+    store_unbond_history, store_unbond_wait_list, take_finished_amount,
+};
+use basset::hub::UnbondHistory;
+use cosmwasm_std::testing::MockStorage;
+use cosmwasm_std::{Decimal, Uint128};
+
+const BATCHES: u64 = 2_000;
+const RELEASED_BATCHES: u64 = 1_000;
+const USERS: u64 = 2_000;
+const ROUNDS: u32 = 20;
+
+/// One whale with a request in every batch, and many users sharing the last batch.
+fn setup() -> MockStorage {
+    let mut storage = MockStorage::new();
+    for batch_id in 1..=BATCHES {
+        store_unbond_wait_list(
+            &mut storage,
+            batch_id,
+            "whale".to_string(),
+            Uint128::new(100),
+        )
+        .unwrap();
+    }
+    for user in 0..USERS {
+        store_unbond_wait_list(
+            &mut storage,
+            BATCHES,
+            format!("user{}", user),
+            Uint128::new(100),
+        )
+        .unwrap();
+    }
+    for batch_id in 1..=RELEASED_BATCHES {
+        let history = UnbondHistory {
+            batch_id,
+            time: batch_id,
+            amount: Uint128::new(100),
+            applied_exchange_rate: Decimal::one(),
+            withdraw_rate: Decimal::one(),
+            released: true,
+        };
+        store_unbond_history(&mut storage, batch_id, history).unwrap();
+    }
+    storage
+}
+
+fn bench<F: FnMut(&mut MockStorage)>(name: &str, mut f: F) {
+    let mut total = Duration::default();
+    for _ in 0..ROUNDS {
+        let mut storage = setup();
+        let start = Instant::now();
+        f(&mut storage);
+        total += start.elapsed();
+    }
+    println!("{:<40} {:>12?}", name, total / ROUNDS);
+}
+
+fn main() {
+    bench("get_unbond_requests (page of 30)", |storage| {
+        get_unbond_requests(storage, "whale".to_string(), Some(BATCHES / 2), Some(30)).unwrap();
+    });
+    bench("get_batch_unbond_requests (page of 30)", |storage| {
+        get_batch_unbond_requests(storage, BATCHES, Some("user1000".to_string()), Some(30))
+            .unwrap();
+    });
+    bench("query_get_finished_amount", |storage| {
+        query_get_finished_amount(storage, "whale".to_string(), RELEASED_BATCHES + 1).unwrap();
+    });
+    bench("take_finished_amount", |storage| {
+        let amount = take_finished_amount(storage, "whale".to_string(), None).unwrap();
+        assert_eq!(amount, Uint128::new(100 * RELEASED_BATCHES as u128));
+    });
+    bench("take_finished_amount (single user)", |storage| {
+        take_finished_amount(storage, "user0".to_string(), None).unwrap();
+    });
+    bench("take_finished_amount (10 batches)", |storage| {
+        let batch_ids = (1..=10).map(|i| i * RELEASED_BATCHES / 10).collect();
+        take_finished_amount(storage, "whale".to_string(), Some(batch_ids)).unwrap();
+    });
+}
@@ -5,8 +5,11 @@ use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 use anchor_basset_hub::state::Parameters;
 use basset::hub::{
-    AllHistoryResponse, CurrentBatchResponse, InstantiateMsg, QueryMsg, StateResponse,
-    UnbondRequestsResponse, WhitelistedValidatorsResponse, WithdrawableUnbondedResponse,
+    AllHistoryResponse, BatchUnbondRequestsResponse, CurrentBatchResponse, ExchangeRateAtResponse,
+    ExchangeRateTwapResponse, InstantiateMsg, LiquidityBufferResponse, PositionResponse, QueryMsg,
+    SimulateBondResponse, SimulateUnbondResponse, SlashingHistoryResponse, StateResponse,
+    UnbondBatchesResponse, UnbondRequestsResponse, UnbondTicketResponse,
+    WhitelistedValidatorsResponse, WithdrawableUnbondedResponse,
 };
 use basset::hub::{Config, ExecuteMsg, State};
 
@@ -26,6 +29,16 @@ fn main() {
     export_schema(&schema_for!(WhitelistedValidatorsResponse), &out_dir);
     export_schema(&schema_for!(WithdrawableUnbondedResponse), &out_dir);
     export_schema(&schema_for!(UnbondRequestsResponse), &out_dir);
+    export_schema(&schema_for!(UnbondTicketResponse), &out_dir);
     export_schema(&schema_for!(CurrentBatchResponse), &out_dir);
+    export_schema(&schema_for!(BatchUnbondRequestsResponse), &out_dir);
     export_schema(&schema_for!(AllHistoryResponse), &out_dir);
+    export_schema(&schema_for!(UnbondBatchesResponse), &out_dir);
+    export_schema(&schema_for!(SimulateBondResponse), &out_dir);
+    export_schema(&schema_for!(SimulateUnbondResponse), &out_dir);
+    export_schema(&schema_for!(LiquidityBufferResponse), &out_dir);
+    export_schema(&schema_for!(PositionResponse), &out_dir);
+    export_schema(&schema_for!(SlashingHistoryResponse), &out_dir);
+    export_schema(&schema_for!(ExchangeRateAtResponse), &out_dir);
+    export_schema(&schema_for!(ExchangeRateTwapResponse), &out_dir);
 }
@@ -1,9 +1,15 @@
-use crate::contract::{query_total_issued, slashing};
+use crate::contract::{query_total_issued, slashed_state, slashing};
 use crate::math::decimal_division;
-use crate::state::{is_valid_validator, CONFIG, CURRENT_BATCH, PARAMETERS, STATE};
-use basset::hub::State;
+use crate::pause::assert_not_paused;
+use crate::state::{
+    increase_expected_delegation, is_valid_validator, record_exchange_rate, Parameters, CONFIG,
+    CURRENT_BATCH, PARAMETERS, STATE,
+};
+use crate::strategy::pick_bond_validator;
+use basset::contract_error::ContractError;
+use basset::hub::{PausableOperation, SimulateBondResponse, State};
 use cosmwasm_std::{
-    attr, to_binary, CosmosMsg, DepsMut, Env, MessageInfo, Response, StakingMsg, StdError,
+    attr, to_binary, Addr, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response, StakingMsg,
     StdResult, Uint128, WasmMsg,
 };
 use cw20::Cw20ExecuteMsg;
@@ -12,81 +18,78 @@ pub fn execute_bond(
     mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    validator: String,
-) -> StdResult<Response> {
+    validator: Option<String>,
+    recipient: Addr,
+) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage, PausableOperation::Bond)?;
+
     // validator must be whitelisted
-    let is_valid = is_valid_validator(deps.storage, validator.clone())?;
-    if !is_valid {
-        return Err(StdError::generic_err(
-            "The chosen validator is currently not supported",
-        ));
+    if let Some(val) = &validator {
+        let is_valid = is_valid_validator(deps.storage, val.clone())?;
+        if !is_valid {
+            return Err(ContractError::ValidatorNotWhitelisted {
+                validator: val.clone(),
+            });
+        }
     }
 
     let params = PARAMETERS.load(deps.storage)?;
-    let coin_denom = params.underlying_coin_denom;
-    let threshold = params.er_threshold;
-    let recovery_fee = params.peg_recovery_fee;
+    let coin_denom = params.underlying_coin_denom.clone();
 
     // current batch requested fee is need for accurate exchange rate computation.
     let current_batch = CURRENT_BATCH.load(deps.storage)?;
-    let requested_with_fee = current_batch.requested_with_fee;
 
     // coin must have be sent along with transaction and it should be in underlying coin denom
     if info.funds.len() > 1usize {
-        return Err(StdError::generic_err(
-            "More than one coin is sent; only one asset is supported",
-        ));
+        return Err(ContractError::MultipleAssets {});
     }
 
     let payment = info
         .funds
         .iter()
         .find(|x| x.denom == coin_denom && x.amount > Uint128::zero())
-        .ok_or_else(|| {
-            StdError::generic_err(format!("No {} assets are provided to bond", coin_denom))
+        .ok_or_else(|| ContractError::NoAssetsProvided {
+            denom: coin_denom.clone(),
         })?;
 
+    // let the delegation strategy decide if the sender has not chosen a validator
+    let validator = match validator {
+        Some(val) => val,
+        None => pick_bond_validator(deps.as_ref(), env.contract.address.clone(), payment.amount)?,
+    };
+
     // check slashing
-    slashing(&mut deps, env)?;
+    let slashing_event = slashing(&mut deps, env.clone())?;
 
-    let state = STATE.load(deps.storage)?;
+    let mut state = STATE.load(deps.storage)?;
     let sender = info.sender;
 
     // get the total supply
-    let mut total_supply = query_total_issued(deps.as_ref()).unwrap_or_default();
-
-    // peg recovery fee should be considered
-    let mint_amount = decimal_division(payment.amount, state.exchange_rate);
-    let mut mint_amount_with_fee = mint_amount;
-    if state.exchange_rate < threshold {
-        let max_peg_fee = mint_amount * recovery_fee;
-        let required_peg_fee = ((total_supply + mint_amount + current_batch.requested_with_fee)
-            .checked_sub(state.total_bond_amount + payment.amount))?;
-        let peg_fee = Uint128::min(max_peg_fee, required_peg_fee);
-        mint_amount_with_fee = (mint_amount.checked_sub(peg_fee))?;
-    }
-
-    // total supply should be updated for exchange rate calculation.
-    total_supply += mint_amount_with_fee;
+    let total_supply = query_total_issued(deps.as_ref()).unwrap_or_default();
 
     // exchange rate should be updated for future
-    STATE.update(deps.storage, |mut prev_state| -> StdResult<State> {
-        prev_state.total_bond_amount += payment.amount;
-        prev_state.update_exchange_rate(total_supply, requested_with_fee);
-        Ok(prev_state)
-    })?;
+    let (mint_amount_with_fee, _) = apply_bond(
+        &mut state,
+        &params,
+        total_supply,
+        current_batch.requested_with_fee,
+        payment.amount,
+    )?;
+    STATE.save(deps.storage, &state)?;
+    record_exchange_rate(deps.storage, env.block.time.seconds(), state.exchange_rate)?;
+    increase_expected_delegation(deps.storage, &validator, payment.amount)?;
 
     let mut messages: Vec<CosmosMsg> = vec![
         // send the delegate message
         CosmosMsg::Staking(StakingMsg::Delegate {
-            validator,
+            validator: validator.clone(),
             amount: payment.clone(),
         }),
     ];
 
-    // issue the basset token for sender
+    // issue the basset token for the recipient
     let mint_msg = Cw20ExecuteMsg::Mint {
-        recipient: sender.to_string(),
+        recipient: recipient.to_string(),
         amount: mint_amount_with_fee,
     };
 
@@ -106,10 +109,67 @@ pub fn execute_bond(
         funds: vec![],
     }));
 
-    Ok(Response::new().add_messages(messages).add_attributes(vec![
-        attr("action", "mint"),
-        attr("from", sender),
-        attr("bonded", payment.amount),
-        attr("minted", mint_amount_with_fee),
-    ]))
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            attr("action", "mint"),
+            attr("from", sender),
+            attr("validator", validator),
+            attr("bonded", payment.amount),
+            attr("minted", mint_amount_with_fee),
+        ])
+        .add_events(slashing_event))
+}
+
+/// Simulate a bond of `amount` against the state the next bond would see.
+pub fn query_simulate_bond(
+    deps: Deps,
+    env: Env,
+    amount: Uint128,
+) -> StdResult<SimulateBondResponse> {
+    let params = PARAMETERS.load(deps.storage)?;
+    let current_batch = CURRENT_BATCH.load(deps.storage)?;
+    let mut state = slashed_state(deps, &env)?;
+    let total_supply = query_total_issued(deps).unwrap_or_default();
+
+    let (minted, peg_recovery_fee) = apply_bond(
+        &mut state,
+        &params,
+        total_supply,
+        current_batch.requested_with_fee,
+        amount,
+    )?;
+
+    Ok(SimulateBondResponse {
+        minted,
+        peg_recovery_fee,
+        exchange_rate: state.exchange_rate,
+    })
+}
+
+/// Apply a bond of `amount` to the state and update its exchange rate.
+/// Return the bAsset amount to mint and the peg recovery fee deducted from it.
+pub(crate) fn apply_bond(
+    state: &mut State,
+    params: &Parameters,
+    total_supply: Uint128,
+    requested_with_fee: Uint128,
+    amount: Uint128,
+) -> StdResult<(Uint128, Uint128)> {
+    // peg recovery fee should be considered
+    let mint_amount = decimal_division(amount, state.exchange_rate);
+    let mut peg_fee = Uint128::zero();
+    if state.exchange_rate < params.er_threshold {
+        let max_peg_fee = mint_amount * params.peg_recovery_fee;
+        let required_peg_fee = ((total_supply + mint_amount + requested_with_fee)
+            .checked_sub(state.total_bond_amount + amount))?;
+        peg_fee = Uint128::min(max_peg_fee, required_peg_fee);
+    }
+    let mint_amount_with_fee = (mint_amount.checked_sub(peg_fee))?;
+
+    // total supply should be updated for exchange rate calculation.
+    state.total_bond_amount += amount;
+    state.update_exchange_rate(total_supply + mint_amount_with_fee, requested_with_fee);
+
+    Ok((mint_amount_with_fee, peg_fee))
 }
@@ -0,0 +1,256 @@
+use crate::contract::{query_total_issued, slashing};
+use crate::state::{
+    record_exchange_rate, LiquidityBuffer, BUFFER_REQUESTS, CONFIG, CURRENT_BATCH,
+    LIQUIDITY_BUFFER, PARAMETERS, STATE,
+};
+use crate::unbond::{apply_unbond, is_batch_ready, submit_batch};
+use basset::contract_error::ContractError;
+use basset::hub::LiquidityBufferResponse;
+use cosmwasm_std::{
+    attr, coins, to_binary, BankMsg, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo, Response,
+    StdResult, Storage, Uint128, WasmMsg,
+};
+use cw20::Cw20ExecuteMsg;
+use cw_storage_plus::U64Key;
+
+/// Update the fee and the utilisation cap of the liquidity buffer.
+/// Only creator/owner is allowed to execute
+pub fn execute_update_liquidity_buffer(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    fee: Option<Decimal>,
+    max_utilisation: Option<Decimal>,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info)?;
+
+    let mut buffer = LIQUIDITY_BUFFER.may_load(deps.storage)?.unwrap_or_default();
+    if let Some(fee) = fee {
+        if fee > Decimal::one() {
+            return Err(ContractError::FractionTooLarge {
+                name: "fee".to_string(),
+            });
+        }
+        buffer.fee = fee;
+    }
+    if let Some(max_utilisation) = max_utilisation {
+        if max_utilisation > Decimal::one() {
+            return Err(ContractError::FractionTooLarge {
+                name: "max_utilisation".to_string(),
+            });
+        }
+        buffer.max_utilisation = max_utilisation;
+    }
+    LIQUIDITY_BUFFER.save(deps.storage, &buffer)?;
+
+    Ok(Response::new().add_attributes(vec![attr("action", "update_liquidity_buffer")]))
+}
+
+/// Add the sent underlying coin to the liquidity buffer.
+/// Only creator/owner is allowed to execute
+pub fn execute_deposit_liquidity_buffer(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info)?;
+
+    let coin_denom = PARAMETERS.load(deps.storage)?.underlying_coin_denom;
+    if info.funds.len() > 1usize {
+        return Err(ContractError::MultipleAssets {});
+    }
+    let payment = info
+        .funds
+        .iter()
+        .find(|x| x.denom == coin_denom && x.amount > Uint128::zero())
+        .ok_or(ContractError::NoAssetsProvided { denom: coin_denom })?;
+
+    let mut buffer = LIQUIDITY_BUFFER.may_load(deps.storage)?.unwrap_or_default();
+    buffer.balance += payment.amount;
+    LIQUIDITY_BUFFER.save(deps.storage, &buffer)?;
+
+    // the deposit is not unbonded coin
+    let mut state = STATE.load(deps.storage)?;
+    state.prev_hub_balance += payment.amount;
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "deposit_liquidity_buffer"),
+        attr("amount", payment.amount),
+    ]))
+}
+
+/// Take underlying coin out of the liquidity buffer.
+/// Only creator/owner is allowed to execute
+pub fn execute_withdraw_liquidity_buffer(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info)?;
+
+    let mut buffer = LIQUIDITY_BUFFER.may_load(deps.storage)?.unwrap_or_default();
+    if amount > buffer.balance {
+        return Err(ContractError::InsufficientLiquidity {
+            available: buffer.balance,
+        });
+    }
+    buffer.balance = buffer.balance.checked_sub(amount)?;
+    LIQUIDITY_BUFFER.save(deps.storage, &buffer)?;
+
+    let mut state = STATE.load(deps.storage)?;
+    state.prev_hub_balance = state.prev_hub_balance.checked_sub(amount)?;
+    STATE.save(deps.storage, &state)?;
+
+    let coin_denom = PARAMETERS.load(deps.storage)?.underlying_coin_denom;
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: coins(amount.u128(), coin_denom),
+        })
+        .add_attributes(vec![
+            attr("action", "withdraw_liquidity_buffer"),
+            attr("amount", amount),
+        ]))
+}
+
+/// Pay the unbonded value of `amount` bAsset right away out of the liquidity buffer.
+/// The bAsset is unbonded on behalf of the buffer, which gets the coins back
+/// once the batch is released.
+/// This message must be call by receive_cw20
+pub(crate) fn execute_instant_unbond(
+    mut deps: DepsMut,
+    env: Env,
+    amount: Uint128,
+    sender: String,
+) -> Result<Response, ContractError> {
+    let params = PARAMETERS.load(deps.storage)?;
+    let mut current_batch = CURRENT_BATCH.load(deps.storage)?;
+
+    let slashing_event = slashing(&mut deps, env.clone())?;
+
+    let mut state = STATE.load(deps.storage)?;
+    let total_supply = query_total_issued(deps.as_ref()).unwrap_or_default();
+
+    let (amount_with_fee, _) = apply_unbond(
+        &mut state,
+        &params,
+        total_supply,
+        &mut current_batch,
+        amount,
+    )?;
+
+    // the buffer keeps its fee out of the value of the request
+    let mut buffer = LIQUIDITY_BUFFER.may_load(deps.storage)?.unwrap_or_default();
+    let value = amount_with_fee * state.exchange_rate;
+    let fee = value * buffer.fee;
+    let payout = value.checked_sub(fee)?;
+
+    let available = available_liquidity(&buffer);
+    if payout.is_zero() || payout > available {
+        return Err(ContractError::InsufficientLiquidity { available });
+    }
+
+    buffer.balance = buffer.balance.checked_sub(payout)?;
+    buffer.lent += payout;
+    LIQUIDITY_BUFFER.save(deps.storage, &buffer)?;
+
+    BUFFER_REQUESTS.update(
+        deps.storage,
+        U64Key::new(current_batch.id),
+        |request| -> StdResult<_> {
+            let mut request = request.unwrap_or_default();
+            request.amount += amount_with_fee;
+            request.lent += payout;
+            Ok(request)
+        },
+    )?;
+
+    // the payout leaves the hub balance
+    state.prev_hub_balance = state.prev_hub_balance.checked_sub(payout)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if is_batch_ready(&state, params.epoch_period, env.block.time.seconds()) {
+        messages = submit_batch(deps.branch(), &env, &mut state, &mut current_batch)?;
+    }
+
+    CURRENT_BATCH.save(deps.storage, &current_batch)?;
+    STATE.save(deps.storage, &state)?;
+    record_exchange_rate(deps.storage, env.block.time.seconds(), state.exchange_rate)?;
+
+    let config = CONFIG.load(deps.storage)?;
+    let token_address = deps.api.addr_humanize(
+        &config
+            .token_contract
+            .expect("the token contract must have been registered"),
+    )?;
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: token_address.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Burn { amount })?,
+        funds: vec![],
+    }));
+    messages.push(CosmosMsg::Bank(BankMsg::Send {
+        to_address: sender.clone(),
+        amount: coins(payout.u128(), params.underlying_coin_denom),
+    }));
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            attr("action", "instant_unbond"),
+            attr("from", sender),
+            attr("burnt_amount", amount),
+            attr("unbonded_amount", amount_with_fee),
+            attr("fee", fee),
+            attr("received", payout),
+        ])
+        .add_events(slashing_event))
+}
+
+/// Return the coins lent out by the buffer for a released batch to the buffer.
+/// Execute while releasing batches in process withdraw rate.
+pub(crate) fn replenish_liquidity_buffer(
+    storage: &mut dyn Storage,
+    batch_id: u64,
+    withdraw_rate: Decimal,
+) -> StdResult<()> {
+    let request = match BUFFER_REQUESTS.may_load(storage, U64Key::new(batch_id))? {
+        Some(request) => request,
+        None => return Ok(()),
+    };
+    BUFFER_REQUESTS.remove(storage, U64Key::new(batch_id));
+
+    let mut buffer = LIQUIDITY_BUFFER.load(storage)?;
+    buffer.balance += request.amount * withdraw_rate;
+    buffer.lent = buffer.lent.saturating_sub(request.lent);
+    LIQUIDITY_BUFFER.save(storage, &buffer)
+}
+
+/// Return the coins the buffer can pay out without lending more than
+/// `max_utilisation` of its coins.
+fn available_liquidity(buffer: &LiquidityBuffer) -> Uint128 {
+    let total = buffer.balance + buffer.lent;
+    let lendable = (total * buffer.max_utilisation).saturating_sub(buffer.lent);
+    Uint128::min(lendable, buffer.balance)
+}
+
+pub fn query_liquidity_buffer(deps: Deps) -> StdResult<LiquidityBufferResponse> {
+    let buffer = LIQUIDITY_BUFFER.may_load(deps.storage)?.unwrap_or_default();
+    Ok(LiquidityBufferResponse {
+        balance: buffer.balance,
+        lent: buffer.lent,
+        fee: buffer.fee,
+        max_utilisation: buffer.max_utilisation,
+        available: available_liquidity(&buffer),
+    })
+}
+
+fn assert_owner(deps: Deps, info: &MessageInfo) -> Result<(), ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if sender_raw != config.creator {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
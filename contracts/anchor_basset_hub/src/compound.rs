@@ -0,0 +1,108 @@
+use crate::contract::{query_total_issued, slashing};
+use crate::math::proportional_split;
+use crate::state::{
+    increase_expected_delegation, read_valid_validators, record_exchange_rate, Parameters,
+    CURRENT_BATCH, PARAMETERS, STATE,
+};
+use crate::strategy::{pick_bond_validator, query_delegated_amounts};
+use basset::contract_error::ContractError;
+use basset::hub::DelegationStrategy;
+use cosmwasm_std::{
+    attr, Addr, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response, StakingMsg, Uint128,
+};
+
+/// Delegate the underlying coins received since `prev_balance` and raise the
+/// exchange rate accordingly, as no bAsset is minted for them.
+/// Only the hub itself is allowed to execute, at the end of the global index update
+/// in the compound reward mode.
+pub fn execute_restake_hook(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    prev_balance: Uint128,
+) -> Result<Response, ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let params = PARAMETERS.load(deps.storage)?;
+    let balance = deps
+        .querier
+        .query_balance(
+            env.contract.address.clone(),
+            params.underlying_coin_denom.as_str(),
+        )?
+        .amount;
+    let amount = balance.saturating_sub(prev_balance);
+    if amount.is_zero() {
+        return Ok(Response::new()
+            .add_attributes(vec![attr("action", "restake"), attr("restaked", amount)]));
+    }
+
+    let slashing_event = slashing(&mut deps, env.clone())?;
+
+    let delegations =
+        pick_restake_validators(deps.as_ref(), env.contract.address.clone(), &params, amount)?;
+
+    let current_batch = CURRENT_BATCH.load(deps.storage)?;
+    let total_supply = query_total_issued(deps.as_ref()).unwrap_or_default();
+    let mut state = STATE.load(deps.storage)?;
+    state.total_bond_amount += amount;
+    state.update_exchange_rate(total_supply, current_batch.requested_with_fee);
+    STATE.save(deps.storage, &state)?;
+    record_exchange_rate(deps.storage, env.block.time.seconds(), state.exchange_rate)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for (validator, share) in delegations {
+        increase_expected_delegation(deps.storage, &validator, share)?;
+        messages.push(CosmosMsg::Staking(StakingMsg::Delegate {
+            validator,
+            amount: Coin {
+                denom: params.underlying_coin_denom.clone(),
+                amount: share,
+            },
+        }));
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            attr("action", "restake"),
+            attr("restaked", amount),
+            attr("new_exchange_rate", state.exchange_rate.to_string()),
+        ])
+        .add_events(slashing_event))
+}
+
+/// Split the restaked `amount` across validators.
+/// Without a delegation strategy the rewards follow the current delegations,
+/// otherwise the strategy picks the validator as for a bond.
+fn pick_restake_validators(
+    deps: Deps,
+    delegator: Addr,
+    params: &Parameters,
+    amount: Uint128,
+) -> Result<Vec<(String, Uint128)>, ContractError> {
+    if params.delegation_strategy != DelegationStrategy::UserChosen {
+        let validator = pick_bond_validator(deps, delegator, amount)?;
+        return Ok(vec![(validator, amount)]);
+    }
+
+    let delegated = query_delegated_amounts(deps, delegator, &params.underlying_coin_denom)?;
+    if delegated.values().all(|amount| amount.is_zero()) {
+        let validator = read_valid_validators(deps.storage)?
+            .into_iter()
+            .next()
+            .ok_or(ContractError::NoWhitelistedValidator {})?;
+        return Ok(vec![(validator, amount)]);
+    }
+
+    let weights: Vec<Uint128> = delegated.values().copied().collect();
+    let shares = proportional_split(amount, &weights)?;
+    Ok(delegated
+        .into_iter()
+        .map(|(validator, _)| validator)
+        .zip(shares)
+        .filter(|(_, share)| !share.is_zero())
+        .collect())
+}
@@ -1,11 +1,15 @@
 use crate::state::{
-    read_validators, remove_white_validators, store_white_validators, Parameters, CONFIG,
-    PARAMETERS,
+    decrease_expected_delegation, increase_expected_delegation, is_valid_validator,
+    read_validator_weights, read_validators, remove_validator_weight, remove_white_validators,
+    store_redelegation_cooldown, store_validator_weights, store_white_validators, Parameters,
+    CONFIG, PARAMETERS, UNBOND_TICKETS, UNBOND_TICKET_CONTRACT,
 };
-use basset::hub::{Config, ExecuteMsg};
+use basset::contract_error::ContractError;
+use basset::hub::{Config, DelegationStrategy, ExecuteMsg, ValidatorWeight};
+use basset::ownership::{accept_ownership, cancel_ownership_proposal, propose_new_owner};
 use cosmwasm_std::{
-    attr, to_binary, Addr, CosmosMsg, Decimal, DepsMut, DistributionMsg, Env, MessageInfo,
-    Response, StakingMsg, StdError, StdResult, WasmMsg,
+    attr, to_binary, Addr, CosmosMsg, Decimal, DepsMut, DistributionMsg, Env, MessageInfo, Order,
+    Response, StakingMsg, StdResult, Uint128, WasmMsg,
 };
 
 use rand::{Rng, SeedableRng, XorShiftRng};
@@ -21,12 +25,20 @@ pub fn execute_update_params(
     unbonding_period: Option<u64>,
     peg_recovery_fee: Option<Decimal>,
     er_threshold: Option<Decimal>,
-) -> StdResult<Response> {
+    delegation_strategy: Option<DelegationStrategy>,
+) -> Result<Response, ContractError> {
     // only owner can send this message.
     let config = CONFIG.load(deps.storage)?;
     let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
     if sender_raw != config.creator {
-        return Err(StdError::generic_err("unauthorized"));
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // the target weights strategy can not pick a validator without weights.
+    if delegation_strategy == Some(DelegationStrategy::TargetWeights)
+        && read_validator_weights(deps.storage)?.is_empty()
+    {
+        return Err(ContractError::NoTargetWeights {});
     }
 
     let params: Parameters = PARAMETERS.load(deps.storage)?;
@@ -38,6 +50,9 @@ pub fn execute_update_params(
         peg_recovery_fee: peg_recovery_fee.unwrap_or(params.peg_recovery_fee),
         er_threshold: er_threshold.unwrap_or(params.er_threshold),
         reward_denom: params.reward_denom,
+        delegation_strategy: delegation_strategy.unwrap_or(params.delegation_strategy),
+        max_rebalance_amount: params.max_rebalance_amount,
+        reward_mode: params.reward_mode,
     };
 
     PARAMETERS.save(deps.storage, &new_params)?;
@@ -45,34 +60,95 @@ pub fn execute_update_params(
     Ok(Response::new().add_attributes(vec![attr("action", "update_params")]))
 }
 
-/// Update the config. Update the owner, reward and token contracts.
+/// Set or remove the cap on the amount moved by a rebalance.
+/// Only creator/owner is allowed to execute
+pub fn execute_update_max_rebalance_amount(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    amount: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    // only owner can send this message.
+    let config = CONFIG.load(deps.storage)?;
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if sender_raw != config.creator {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    PARAMETERS.update(deps.storage, |mut params| -> StdResult<_> {
+        params.max_rebalance_amount = amount;
+        Ok(params)
+    })?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_max_rebalance_amount"),
+        attr(
+            "max_rebalance_amount",
+            amount.map_or_else(|| "none".to_string(), |amount| amount.to_string()),
+        ),
+    ]))
+}
+
+/// Replace the target weights of the whitelisted validators.
+/// Only creator/owner is allowed to execute
+pub fn execute_update_validator_weights(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    weights: Vec<ValidatorWeight>,
+) -> Result<Response, ContractError> {
+    // only owner can send this message.
+    let config = CONFIG.load(deps.storage)?;
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if sender_raw != config.creator {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    for (i, w) in weights.iter().enumerate() {
+        if !is_valid_validator(deps.storage, w.validator.clone())? {
+            return Err(ContractError::ValidatorNotWhitelisted {
+                validator: w.validator.clone(),
+            });
+        }
+        if weights[..i]
+            .iter()
+            .any(|prev| prev.validator == w.validator)
+        {
+            return Err(ContractError::DuplicateValidatorWeight {
+                validator: w.validator.clone(),
+            });
+        }
+    }
+
+    let total_weight: u64 = weights.iter().map(|w| w.weight).sum();
+    if total_weight == 0 {
+        return Err(ContractError::ZeroTotalWeight {});
+    }
+
+    store_validator_weights(deps.storage, weights)?;
+
+    Ok(Response::new().add_attributes(vec![attr("action", "update_validator_weights")]))
+}
+
+/// Update the config. Update the reward, token and airdrop registry contracts.
 /// Only creator/owner is allowed to execute
 pub fn execute_update_config(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    owner: Option<String>,
     reward_contract: Option<String>,
     token_contract: Option<String>,
     airdrop_registry_contract: Option<String>,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     // only owner must be able to send this message.
     let conf = CONFIG.load(deps.storage)?;
     let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
     if sender_raw != conf.creator {
-        return Err(StdError::generic_err("unauthorized"));
+        return Err(ContractError::Unauthorized {});
     }
 
     let mut messages: Vec<CosmosMsg> = vec![];
 
-    if let Some(o) = owner {
-        let owner_raw = deps.api.addr_canonicalize(o.as_str())?;
-
-        CONFIG.update(deps.storage, |mut last_config| -> StdResult<Config> {
-            last_config.creator = owner_raw;
-            Ok(last_config)
-        })?;
-    }
     if let Some(reward) = reward_contract {
         let reward_raw = deps.api.addr_canonicalize(reward.as_str())?;
 
@@ -109,6 +185,93 @@ pub fn execute_update_config(
         .add_attributes(vec![attr("action", "update_config")]))
 }
 
+/// Set or remove the unbond ticket contract.
+/// Tickets are redeemed through the contract that minted them,
+/// so it can not change while tickets are outstanding.
+/// Only creator/owner is allowed to execute
+pub fn execute_update_unbond_ticket_contract(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    contract: Option<String>,
+) -> Result<Response, ContractError> {
+    let conf = CONFIG.load(deps.storage)?;
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if sender_raw != conf.creator {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if UNBOND_TICKETS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .next()
+        .is_some()
+    {
+        return Err(ContractError::OutstandingUnbondTickets {});
+    }
+
+    match &contract {
+        Some(contract) => {
+            let contract_raw = deps.api.addr_canonicalize(contract)?;
+            UNBOND_TICKET_CONTRACT.save(deps.storage, &contract_raw)?;
+        }
+        None => UNBOND_TICKET_CONTRACT.remove(deps.storage),
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_unbond_ticket_contract"),
+        attr("unbond_ticket_contract", contract.unwrap_or_default()),
+    ]))
+}
+
+/// Propose a new owner. The ownership moves once the new owner accepts it.
+/// Only creator/owner is allowed to execute
+pub fn execute_propose_new_owner(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    new_owner: String,
+) -> Result<Response, ContractError> {
+    let conf = CONFIG.load(deps.storage)?;
+    propose_new_owner(deps, &info.sender, &conf.creator, &new_owner)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "propose_new_owner"),
+        attr("new_owner", new_owner),
+    ]))
+}
+
+/// Drop the pending ownership transfer.
+/// Only creator/owner is allowed to execute
+pub fn execute_cancel_ownership_proposal(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let conf = CONFIG.load(deps.storage)?;
+    cancel_ownership_proposal(deps, &info.sender, &conf.creator)?;
+
+    Ok(Response::new().add_attributes(vec![attr("action", "cancel_ownership_proposal")]))
+}
+
+/// Take over the ownership of the hub.
+/// Only the proposed owner is allowed to execute
+pub fn execute_accept_ownership(
+    mut deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let new_owner = accept_ownership(deps.branch(), &info.sender)?;
+    CONFIG.update(deps.storage, |mut last_config| -> StdResult<Config> {
+        last_config.creator = new_owner;
+        Ok(last_config)
+    })?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "accept_ownership"),
+        attr("owner", info.sender),
+    ]))
+}
+
 /// Register a white listed validator.
 /// Only creator/owner is allowed to execute
 pub fn execute_register_validator(
@@ -116,13 +279,13 @@ pub fn execute_register_validator(
     env: Env,
     info: MessageInfo,
     validator: String,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     let hub_conf = CONFIG.load(deps.storage)?;
 
     let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
     let contract_raw = deps.api.addr_canonicalize(env.contract.address.as_str())?;
     if hub_conf.creator != sender_raw && contract_raw != sender_raw {
-        return Err(StdError::generic_err("unauthorized"));
+        return Err(ContractError::Unauthorized {});
     }
 
     // given validator must be first a validator in the system.
@@ -132,9 +295,7 @@ pub fn execute_register_validator(
         .iter()
         .any(|val| val.address == validator);
     if !exists {
-        return Err(StdError::generic_err(
-            "The specified address is not a validator",
-        ));
+        return Err(ContractError::NotAValidator { address: validator });
     }
 
     store_white_validators(deps.storage, validator.clone())?;
@@ -152,22 +313,21 @@ pub fn execute_deregister_validator(
     env: Env,
     info: MessageInfo,
     validator: String,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     let token = CONFIG.load(deps.storage)?;
 
     let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
     if token.creator != sender_raw {
-        return Err(StdError::generic_err("unauthorized"));
+        return Err(ContractError::Unauthorized {});
     }
     let validators_before_remove = read_validators(deps.storage)?;
 
     if validators_before_remove.len() == 1 {
-        return Err(StdError::generic_err(
-            "Cannot remove the last whitelisted validator",
-        ));
+        return Err(ContractError::LastValidator {});
     }
 
     remove_white_validators(deps.storage, validator.to_string())?;
+    remove_validator_weight(deps.storage, validator.to_string());
 
     let query = deps
         .querier
@@ -187,6 +347,20 @@ pub fn execute_deregister_validator(
         replaced_val = Addr::unchecked(validators.get(random_index).unwrap().as_str());
 
         if let Some(delegation) = delegated_amount {
+            // the received stake can not be redelegated again until it matures.
+            let params = PARAMETERS.load(deps.storage)?;
+            store_redelegation_cooldown(
+                deps.storage,
+                replaced_val.to_string(),
+                env.block.time.seconds() + params.unbonding_period,
+            )?;
+
+            decrease_expected_delegation(deps.storage, &validator, delegation.amount.amount)?;
+            increase_expected_delegation(
+                deps.storage,
+                replaced_val.as_str(),
+                delegation.amount.amount,
+            )?;
             messages.push(CosmosMsg::Staking(StakingMsg::Redelegate {
                 src_validator: validator.to_string(),
                 dst_validator: replaced_val.to_string(),
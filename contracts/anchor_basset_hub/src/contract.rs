@@ -2,38 +2,80 @@
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
     attr, from_binary, to_binary, Addr, Binary, CosmosMsg, Decimal, Deps, DepsMut, DistributionMsg,
-    Env, MessageInfo, QueryRequest, Response, StakingMsg, StdError, StdResult, SubMsg, Uint128,
+    Env, Event, MessageInfo, Order, QueryRequest, Response, StakingMsg, StdResult, SubMsg, Uint128,
     WasmMsg, WasmQuery,
 };
+use std::collections::BTreeMap;
 
 use crate::config::{
-    execute_deregister_validator, execute_register_validator, execute_update_config,
-    execute_update_params,
+    execute_accept_ownership, execute_cancel_ownership_proposal, execute_deregister_validator,
+    execute_propose_new_owner, execute_register_validator, execute_update_config,
+    execute_update_max_rebalance_amount, execute_update_params,
+    execute_update_unbond_ticket_contract, execute_update_validator_weights,
 };
 
+use crate::pause::{assert_not_paused, execute_pause, execute_unpause, execute_update_guardian};
+use crate::position::query_position;
 use crate::state::{
-    all_unbond_history, get_unbond_requests, query_get_finished_amount, read_valid_validators,
-    CurrentBatch, Parameters, CONFIG, CURRENT_BATCH, PARAMETERS, STATE,
+    all_unbond_history, get_batch_unbond_requests, get_unbond_requests,
+    increase_expected_delegation, legacy_wait_list_pending, migrate_unbond_wait_list,
+    query_get_finished_amount, read_slashing_history, read_valid_validators,
+    read_validator_weights, record_exchange_rate, reset_expected_delegations,
+    start_wait_list_migration, store_slashing_event, CurrentBatch, Parameters, CONFIG,
+    CURRENT_BATCH, EXPECTED_DELEGATIONS, GUARDIAN, PARAMETERS, PAUSED_OPERATIONS, STATE,
+    UNBOND_TICKET_CONTRACT,
+};
+use crate::unbond::{
+    execute_cancel_unbond, execute_cancel_unbond_ticket, execute_process_batches,
+    execute_submit_batch, execute_unbond, execute_withdraw_unbonded, query_simulate_unbond,
+    query_unbond_batches, query_unbond_ticket,
 };
-use crate::unbond::{execute_unbond, execute_withdraw_unbonded};
 
-use crate::bond::execute_bond;
+use crate::bond::{execute_bond, query_simulate_bond};
+use crate::buffer::{
+    execute_deposit_liquidity_buffer, execute_instant_unbond, execute_update_liquidity_buffer,
+    execute_withdraw_liquidity_buffer, query_liquidity_buffer,
+};
+use crate::compound::execute_restake_hook;
+use crate::rate_history::{query_exchange_rate_at, query_exchange_rate_twap};
+use crate::rebalance::execute_rebalance;
+use crate::strategy::query_delegated_amounts;
+use basset::contract_error::ContractError;
 use basset::hub::ExecuteMsg::SwapHook;
 use basset::hub::{
-    AllHistoryResponse, Config, ConfigResponse, CurrentBatchResponse, Cw20HookMsg, ExecuteMsg,
-    InstantiateMsg, QueryMsg, State, StateResponse, UnbondRequestsResponse,
-    WhitelistedValidatorsResponse, WithdrawableUnbondedResponse,
+    AllHistoryResponse, BatchUnbondRequestsResponse, Config, ConfigResponse, CurrentBatchResponse,
+    Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, PausableOperation, PauseInfoResponse,
+    QueryMsg, RewardMode, SlashingEvent, SlashingHistoryResponse, State, StateResponse,
+    UnbondRequestsResponse, ValidatorWeightsResponse, WhitelistedValidatorsResponse,
+    WithdrawableUnbondedResponse,
 };
-use basset::reward::ExecuteMsg::{SwapToRewardDenom, UpdateGlobalIndex};
+use basset::ownership::query_pending_owner;
+use basset::reward::ExecuteMsg::{
+    SendUnderlying, SwapToRewardDenom, SwapToUnderlying, UpdateGlobalIndex,
+    UpdateRewardAssetIndexes,
+};
+use basset::version::{migrate_contract_version, needs_migration};
+use cw2::set_contract_version;
 use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg, TokenInfoResponse};
 
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:anchor-basset-hub";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The wait list moved from the legacy bucket layout to cw-storage-plus in this version.
+const WAIT_LIST_MIGRATION_VERSION: &str = "1.1.0";
+const DEFAULT_WAIT_LIST_MIGRATION_LIMIT: u32 = 100;
+const MAX_WAIT_LIST_MIGRATION_LIMIT: u32 = 300;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
     let sender = info.sender.clone();
     let _sndr_raw = deps.api.addr_canonicalize(sender.as_str())?;
 
@@ -41,8 +83,8 @@ pub fn instantiate(
         .funds
         .iter()
         .find(|x| x.denom == msg.underlying_coin_denom && x.amount > Uint128::zero())
-        .ok_or_else(|| {
-            StdError::generic_err(format!("No {} assets are provided to bond", "uluna"))
+        .ok_or_else(|| ContractError::NoAssetsProvided {
+            denom: msg.underlying_coin_denom.clone(),
         })?;
 
     // store config
@@ -65,6 +107,7 @@ pub fn instantiate(
     };
 
     STATE.save(deps.storage, &state)?;
+    record_exchange_rate(deps.storage, env.block.time.seconds(), state.exchange_rate)?;
 
     // instantiate parameters
     let params = Parameters {
@@ -74,6 +117,9 @@ pub fn instantiate(
         peg_recovery_fee: msg.peg_recovery_fee,
         er_threshold: msg.er_threshold,
         reward_denom: msg.reward_denom,
+        delegation_strategy: Default::default(),
+        max_rebalance_amount: None,
+        reward_mode: msg.reward_mode,
     };
 
     PARAMETERS.save(deps.storage, &params)?;
@@ -97,6 +143,7 @@ pub fn instantiate(
     })));
 
     // send the delegate message
+    increase_expected_delegation(deps.storage, &msg.validator, payment.amount)?;
     messages.push(SubMsg::new(CosmosMsg::Staking(StakingMsg::Delegate {
         validator: msg.validator.to_string(),
         amount: payment.clone(),
@@ -111,14 +158,40 @@ pub fn instantiate(
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> StdResult<Response> {
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
-        ExecuteMsg::Bond { validator } => execute_bond(deps, env, info, validator),
+        ExecuteMsg::Bond { validator } => {
+            let recipient = info.sender.clone();
+            execute_bond(deps, env, info, validator, recipient)
+        }
+        ExecuteMsg::BondFor {
+            recipient,
+            validator,
+        } => {
+            let recipient = deps.api.addr_validate(&recipient)?;
+            let res = execute_bond(deps, env, info, validator, recipient.clone())?;
+            Ok(res.add_attribute("recipient", recipient))
+        }
         ExecuteMsg::UpdateGlobalIndex { airdrop_hooks } => {
             execute_update_global(deps, env, airdrop_hooks)
         }
-        ExecuteMsg::WithdrawUnbonded {} => execute_withdraw_unbonded(deps, env, info),
+        ExecuteMsg::WithdrawUnbonded {
+            batch_ids,
+            recipient,
+            ticket_ids,
+        } => execute_withdraw_unbonded(deps, env, info, batch_ids, recipient, ticket_ids),
+        ExecuteMsg::SubmitBatch {} => execute_submit_batch(deps, env, info),
+        ExecuteMsg::ProcessBatches { limit } => execute_process_batches(deps, env, info, limit),
+        ExecuteMsg::CancelUnbond { amount } => execute_cancel_unbond(deps, env, info, amount),
+        ExecuteMsg::CancelUnbondTicket { ticket_id } => {
+            execute_cancel_unbond_ticket(deps, env, info, ticket_id)
+        }
         ExecuteMsg::RegisterValidator { validator } => {
             execute_register_validator(deps, env, info, validator)
         }
@@ -126,11 +199,14 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
             execute_deregister_validator(deps, env, info, validator)
         }
         ExecuteMsg::CheckSlashing {} => execute_slashing(deps, env),
+        ExecuteMsg::Rebalance {} => execute_rebalance(deps, env),
+        ExecuteMsg::MigrateWaitList { limit } => execute_migrate_wait_list(deps, limit),
         ExecuteMsg::UpdateParams {
             epoch_period,
             unbonding_period,
             peg_recovery_fee,
             er_threshold,
+            delegation_strategy,
         } => execute_update_params(
             deps,
             env,
@@ -139,9 +215,38 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
             unbonding_period,
             peg_recovery_fee,
             er_threshold,
+            delegation_strategy,
         ),
+        ExecuteMsg::UpdateMaxRebalanceAmount { amount } => {
+            execute_update_max_rebalance_amount(deps, env, info, amount)
+        }
+        ExecuteMsg::UpdateValidatorWeights { weights } => {
+            execute_update_validator_weights(deps, env, info, weights)
+        }
+        ExecuteMsg::ProposeNewOwner { new_owner } => {
+            execute_propose_new_owner(deps, env, info, new_owner)
+        }
+        ExecuteMsg::CancelOwnershipProposal {} => {
+            execute_cancel_ownership_proposal(deps, env, info)
+        }
+        ExecuteMsg::AcceptOwnership {} => execute_accept_ownership(deps, env, info),
+        ExecuteMsg::UpdateGuardian { guardian } => {
+            execute_update_guardian(deps, env, info, guardian)
+        }
+        ExecuteMsg::UpdateUnbondTicketContract { contract } => {
+            execute_update_unbond_ticket_contract(deps, env, info, contract)
+        }
+        ExecuteMsg::UpdateLiquidityBuffer {
+            fee,
+            max_utilisation,
+        } => execute_update_liquidity_buffer(deps, env, info, fee, max_utilisation),
+        ExecuteMsg::DepositLiquidityBuffer {} => execute_deposit_liquidity_buffer(deps, env, info),
+        ExecuteMsg::WithdrawLiquidityBuffer { amount } => {
+            execute_withdraw_liquidity_buffer(deps, env, info, amount)
+        }
+        ExecuteMsg::Pause { operations } => execute_pause(deps, env, info, operations),
+        ExecuteMsg::Unpause { operations } => execute_unpause(deps, env, info, operations),
         ExecuteMsg::UpdateConfig {
-            owner,
             reward_contract,
             token_contract,
             airdrop_registry_contract,
@@ -149,7 +254,6 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
             deps,
             env,
             info,
-            owner,
             reward_contract,
             token_contract,
             airdrop_registry_contract,
@@ -166,6 +270,9 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
             airdrop_swap_contract,
             swap_msg,
         ),
+        ExecuteMsg::RestakeHook { prev_balance } => {
+            execute_restake_hook(deps, env, info, prev_balance)
+        }
         ExecuteMsg::ClaimAirdrop {
             airdrop_token_contract,
             airdrop_contract,
@@ -191,23 +298,36 @@ pub fn receive_cw20(
     env: Env,
     info: MessageInfo,
     cw20_msg: Cw20ReceiveMsg,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     let contract_addr = info.sender.clone();
 
-    match from_binary(&cw20_msg.msg) {
-        Ok(Cw20HookMsg::Unbond {}) => {
-            // only token contract can execute this message
-            let conf = CONFIG.load(deps.storage)?;
-            if deps.api.addr_canonicalize(contract_addr.as_str())?
-                != conf
-                    .token_contract
-                    .expect("the token contract must have been registered")
-            {
-                return Err(StdError::generic_err("unauthorized"));
-            }
-            execute_unbond(deps, env, info, cw20_msg.amount, cw20_msg.sender)
+    let hook: Cw20HookMsg = from_binary(&cw20_msg.msg)?;
+    if let Cw20HookMsg::UnbondFor { recipient } = &hook {
+        deps.api.addr_validate(recipient)?;
+    }
+
+    assert_not_paused(deps.storage, PausableOperation::Unbond)?;
+
+    // only token contract can execute this message
+    let conf = CONFIG.load(deps.storage)?;
+    if deps.api.addr_canonicalize(contract_addr.as_str())?
+        != conf
+            .token_contract
+            .expect("the token contract must have been registered")
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    match hook {
+        Cw20HookMsg::Unbond {} => execute_unbond(deps, env, info, cw20_msg.amount, cw20_msg.sender),
+        // the address the unbond request belongs to
+        Cw20HookMsg::UnbondFor { recipient } => {
+            let res = execute_unbond(deps, env, info, cw20_msg.amount, recipient)?;
+            Ok(res.add_attribute("sender", cw20_msg.sender))
+        }
+        Cw20HookMsg::InstantUnbond {} => {
+            execute_instant_unbond(deps, env, cw20_msg.amount, cw20_msg.sender)
         }
-        Err(err) => Err(err),
     }
 }
 
@@ -217,7 +337,7 @@ pub fn execute_update_global(
     deps: DepsMut,
     env: Env,
     airdrop_hooks: Option<Vec<Binary>>,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     let mut messages: Vec<SubMsg> = vec![];
 
     let config = CONFIG.load(deps.storage)?;
@@ -230,11 +350,11 @@ pub fn execute_update_global(
         )?
         .to_string();
 
-    if airdrop_hooks.is_some() {
+    if let Some(hooks) = airdrop_hooks {
         let registry_addr = deps
             .api
             .addr_humanize(&config.airdrop_registry_contract.unwrap())?;
-        for msg in airdrop_hooks.unwrap() {
+        for msg in hooks {
             messages.push(SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: registry_addr.to_string(),
                 msg,
@@ -247,25 +367,71 @@ pub fn execute_update_global(
     let mut withdraw_msgs = withdraw_all_rewards(&deps, env.contract.address.clone())?;
     messages.append(&mut withdraw_msgs);
 
-    // Send Swap message to reward contract
-    let swap_msg = SwapToRewardDenom {};
-    messages.push(SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: reward_addr.clone(),
-        msg: to_binary(&swap_msg).unwrap(),
-        funds: vec![],
-    })));
+    let params = PARAMETERS.load(deps.storage)?;
+    match params.reward_mode {
+        RewardMode::Distribute => {
+            // Send Swap message to reward contract
+            let swap_msg = SwapToRewardDenom {};
+            messages.push(SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: reward_addr.clone(),
+                msg: to_binary(&swap_msg).unwrap(),
+                funds: vec![],
+            })));
 
-    messages.push(SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: reward_addr,
-        msg: to_binary(&UpdateGlobalIndex {}).unwrap(),
-        funds: vec![],
-    })));
+            messages.push(SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: reward_addr,
+                msg: to_binary(&UpdateGlobalIndex {}).unwrap(),
+                funds: vec![],
+            })));
+        }
+        RewardMode::Compound => {
+            // the rewards are swapped to the underlying denom by the reward contract,
+            // sent back, and restaked by the hook with whatever the hub received.
+            // Nothing is distributed but the reward assets: the rewards the reward contract
+            // can not swap are left in it for the next round.
+            let prev_balance = deps
+                .querier
+                .query_balance(
+                    env.contract.address.clone(),
+                    params.underlying_coin_denom.as_str(),
+                )?
+                .amount;
+            let underlying_denom = params.underlying_coin_denom;
+
+            messages.push(SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: reward_addr.clone(),
+                msg: to_binary(&SwapToUnderlying {
+                    underlying_denom: underlying_denom.clone(),
+                })?,
+                funds: vec![],
+            })));
+
+            messages.push(SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: reward_addr.clone(),
+                msg: to_binary(&SendUnderlying { underlying_denom })?,
+                funds: vec![],
+            })));
+
+            messages.push(SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: reward_addr,
+                msg: to_binary(&UpdateRewardAssetIndexes {})?,
+                funds: vec![],
+            })));
+
+            messages.push(SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: env.contract.address.to_string(),
+                msg: to_binary(&ExecuteMsg::RestakeHook { prev_balance })?,
+                funds: vec![],
+            })));
+        }
+    }
 
     //update state last modified
-    STATE.update(deps.storage, |mut last_state| -> StdResult<State> {
+    let state = STATE.update(deps.storage, |mut last_state| -> StdResult<State> {
         last_state.last_index_modification = env.block.time.seconds();
         Ok(last_state)
     })?;
+    record_exchange_rate(deps.storage, env.block.time.seconds(), state.exchange_rate)?;
 
     Ok(Response::new()
         .add_submessages(messages)
@@ -292,41 +458,110 @@ fn withdraw_all_rewards(deps: &DepsMut, delegator: Addr) -> StdResult<Vec<SubMsg
 
 /// Check whether slashing has happened
 /// This is used for checking slashing while bonding or unbonding
-pub fn slashing(deps: &mut DepsMut, env: Env) -> StdResult<()> {
-    //read params
+/// A slashing found is recorded and returned as the event to emit
+pub fn slashing(deps: &mut DepsMut, env: Env) -> StdResult<Option<Event>> {
+    let params = PARAMETERS.load(deps.storage)?;
+    let delegated = query_delegated_amounts(
+        deps.as_ref(),
+        env.contract.address.clone(),
+        &params.underlying_coin_denom,
+    )?;
+
+    let prev_state = STATE.load(deps.storage)?;
+    let state = apply_slashing(deps.as_ref(), prev_state.clone(), &delegated)?;
+    STATE.save(deps.storage, &state)?;
+    record_exchange_rate(deps.storage, env.block.time.seconds(), state.exchange_rate)?;
+
+    let mut event = None;
+    if state.total_bond_amount < prev_state.total_bond_amount {
+        // the validators that lost delegations since the last check
+        let validators = EXPECTED_DELEGATIONS
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| {
+                let (validator, expected) = item?;
+                let validator = String::from_utf8(validator)?;
+                let actual = delegated.get(&validator).copied().unwrap_or_default();
+                Ok((validator, expected.saturating_sub(actual)))
+            })
+            .filter(|item| !matches!(item, Ok((_, loss)) if loss.is_zero()))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut slashing = SlashingEvent {
+            id: 0,
+            time: env.block.time.seconds(),
+            height: env.block.height,
+            bonded_before: prev_state.total_bond_amount,
+            bonded_after: state.total_bond_amount,
+            exchange_rate_before: prev_state.exchange_rate,
+            exchange_rate_after: state.exchange_rate,
+            validators,
+        };
+        slashing.id = store_slashing_event(deps.storage, slashing.clone())?;
+
+        let mut attrs = vec![
+            attr("id", slashing.id.to_string()),
+            attr("bonded_before", slashing.bonded_before),
+            attr("bonded_after", slashing.bonded_after),
+            attr(
+                "exchange_rate_before",
+                slashing.exchange_rate_before.to_string(),
+            ),
+            attr(
+                "exchange_rate_after",
+                slashing.exchange_rate_after.to_string(),
+            ),
+        ];
+        for (validator, loss) in slashing.validators {
+            attrs.push(attr("validator", validator));
+            attrs.push(attr("validator_loss", loss));
+        }
+        event = Some(Event::new("slashing").add_attributes(attrs));
+    }
+
+    // the next check compares against the delegations seen now
+    if !delegated.is_empty() {
+        reset_expected_delegations(deps.storage, &delegated)?;
+    }
+
+    Ok(event)
+}
+
+/// Return the state with any slashing that happened since the last check applied,
+/// without storing it. Simulations use it to see the state the execution would see.
+pub(crate) fn slashed_state(deps: Deps, env: &Env) -> StdResult<State> {
     let params = PARAMETERS.load(deps.storage)?;
-    let coin_denom = params.underlying_coin_denom;
+    let delegated = query_delegated_amounts(
+        deps,
+        env.contract.address.clone(),
+        &params.underlying_coin_denom,
+    )?;
+    apply_slashing(deps, STATE.load(deps.storage)?, &delegated)
+}
 
-    // Check the amount that contract thinks is bonded
-    let state_total_bonded = STATE.load(deps.storage)?.total_bond_amount;
+/// Lower the bonded amount to the actual delegations and update the exchange rate.
+fn apply_slashing(
+    deps: Deps,
+    mut state: State,
+    delegated: &BTreeMap<String, Uint128>,
+) -> StdResult<State> {
+    if delegated.is_empty() {
+        return Ok(state);
+    }
 
     // Check the actual bonded amount
-    let delegations = deps.querier.query_all_delegations(env.contract.address)?;
-    if delegations.is_empty() {
-        Ok(())
-    } else {
-        let mut actual_total_bonded = Uint128::zero();
-        for delegation in delegations {
-            if delegation.amount.denom == coin_denom {
-                actual_total_bonded += delegation.amount.amount
-            }
-        }
+    let actual_total_bonded: Uint128 = delegated.values().copied().sum();
 
+    // Slashing happens if the expected amount is less than stored amount
+    if state.total_bond_amount > actual_total_bonded {
         // Need total issued for updating the exchange rate
-        let total_issued = query_total_issued(deps.as_ref())?;
+        let total_issued = query_total_issued(deps)?;
         let current_requested_fee = CURRENT_BATCH.load(deps.storage)?.requested_with_fee;
 
-        // Slashing happens if the expected amount is less than stored amount
-        if state_total_bonded.u128() > actual_total_bonded.u128() {
-            STATE.update(deps.storage, |mut state| -> StdResult<State> {
-                state.total_bond_amount = actual_total_bonded;
-                state.update_exchange_rate(total_issued, current_requested_fee);
-                Ok(state)
-            })?;
-        }
-
-        Ok(())
+        state.total_bond_amount = actual_total_bonded;
+        state.update_exchange_rate(total_issued, current_requested_fee);
     }
+
+    Ok(state)
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -339,19 +574,14 @@ pub fn claim_airdrop(
     airdrop_swap_contract: String,
     claim_msg: Binary,
     swap_msg: Binary,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     let conf = CONFIG.load(deps.storage)?;
 
     let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
 
     let airdrop_reg_raw = conf.airdrop_registry_contract.unwrap();
-    let airdrop_reg = deps.api.addr_humanize(&airdrop_reg_raw)?;
-
     if airdrop_reg_raw != sender_raw {
-        return Err(StdError::generic_err(format!(
-            "Sender must be {}",
-            airdrop_reg
-        )));
+        return Err(ContractError::Unauthorized {});
     }
 
     let mut messages: Vec<SubMsg> = vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -380,9 +610,9 @@ pub fn swap_hook(
     airdrop_token_contract: String,
     airdrop_swap_contract: String,
     swap_msg: Binary,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     if info.sender != env.contract.address {
-        return Err(StdError::generic_err("unauthorized"));
+        return Err(ContractError::Unauthorized {});
     }
 
     let airdrop_token_balance: BalanceResponse =
@@ -394,10 +624,9 @@ pub fn swap_hook(
         }))?;
 
     if airdrop_token_balance.balance == Uint128::new(0) {
-        return Err(StdError::generic_err(format!(
-            "There is no balance for {} in airdrop token contract {}",
-            &env.contract.address, &airdrop_token_contract
-        )));
+        return Err(ContractError::NoAirdropBalance {
+            token: airdrop_token_contract,
+        });
     }
     let messages: Vec<SubMsg> = vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
         contract_addr: airdrop_token_contract.to_string(),
@@ -418,21 +647,79 @@ pub fn swap_hook(
         ]))
 }
 
+/// Move a page of the legacy wait list to the current layout.
+/// Permissionless, so anyone can finish the migration started by `migrate`.
+pub fn execute_migrate_wait_list(
+    deps: DepsMut,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let limit = limit
+        .unwrap_or(DEFAULT_WAIT_LIST_MIGRATION_LIMIT)
+        .clamp(1, MAX_WAIT_LIST_MIGRATION_LIMIT);
+    let (migrated_requests, done) = migrate_unbond_wait_list(deps.storage, limit)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "migrate_wait_list"),
+        attr("migrated_unbond_requests", migrated_requests.to_string()),
+        attr("wait_list_migrated", done.to_string()),
+    ]))
+}
+
 /// Handler for tracking slashing
-pub fn execute_slashing(mut deps: DepsMut, env: Env) -> StdResult<Response> {
+pub fn execute_slashing(mut deps: DepsMut, env: Env) -> Result<Response, ContractError> {
     // call slashing
-    slashing(&mut deps, env)?;
+    let slashing_event = slashing(&mut deps, env)?;
     // read state for log
     let state = STATE.load(deps.storage)?;
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "check_slashing"),
+            attr("new_exchange_rate", state.exchange_rate.to_string()),
+        ])
+        .add_events(slashing_event))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored_version = migrate_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    // start the exchange rate history from the current rate
+    let state = STATE.load(deps.storage)?;
+    record_exchange_rate(deps.storage, env.block.time.seconds(), state.exchange_rate)?;
+
+    if needs_migration(&stored_version, WAIT_LIST_MIGRATION_VERSION)? {
+        start_wait_list_migration(deps.storage)?;
+    }
+
+    // the first slashing after the upgrade is attributed against the current delegations
+    let no_expected_delegations = EXPECTED_DELEGATIONS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .next()
+        .is_none();
+    if no_expected_delegations {
+        let params = PARAMETERS.load(deps.storage)?;
+        let delegated = query_delegated_amounts(
+            deps.as_ref(),
+            env.contract.address.clone(),
+            &params.underlying_coin_denom,
+        )?;
+        reset_expected_delegations(deps.storage, &delegated)?;
+    }
+    // the rest of the wait list is moved by `MigrateWaitList`
+    let (migrated_requests, done) =
+        migrate_unbond_wait_list(deps.storage, DEFAULT_WAIT_LIST_MIGRATION_LIMIT)?;
+
     Ok(Response::new().add_attributes(vec![
-        attr("action", "check_slashing"),
-        attr("new_exchange_rate", state.exchange_rate.to_string()),
+        attr("action", "migrate"),
+        attr("version", CONTRACT_VERSION),
+        attr("migrated_unbond_requests", migrated_requests.to_string()),
+        attr("wait_list_migrated", done.to_string()),
     ]))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+    let res = match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
         QueryMsg::State {} => to_binary(&query_state(deps)?),
         QueryMsg::CurrentBatch {} => to_binary(&query_current_batch(deps)?),
@@ -441,11 +728,51 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             to_binary(&query_withdrawable_unbonded(deps, address, env)?)
         }
         QueryMsg::Parameters {} => to_binary(&query_params(deps)?),
-        QueryMsg::UnbondRequests { address } => to_binary(&query_unbond_requests(deps, address)?),
+        QueryMsg::UnbondRequests {
+            address,
+            start_from,
+            limit,
+        } => to_binary(&query_unbond_requests(deps, address, start_from, limit)?),
+        QueryMsg::UnbondTicket { ticket_id } => {
+            to_binary(&query_unbond_ticket(deps, env, ticket_id)?)
+        }
+        QueryMsg::BatchUnbondRequests {
+            batch_id,
+            start_after,
+            limit,
+        } => to_binary(&query_batch_unbond_requests(
+            deps,
+            batch_id,
+            start_after,
+            limit,
+        )?),
         QueryMsg::AllHistory { start_from, limit } => {
             to_binary(&query_unbond_requests_limitation(deps, start_from, limit)?)
         }
-    }
+        QueryMsg::UnbondBatches { start_from, limit } => {
+            to_binary(&query_unbond_batches(deps, env, start_from, limit)?)
+        }
+        QueryMsg::ValidatorWeights {} => to_binary(&query_validator_weights(deps)?),
+        QueryMsg::PauseInfo {} => to_binary(&query_pause_info(deps)?),
+        QueryMsg::SimulateBond { amount } => to_binary(&query_simulate_bond(deps, env, amount)?),
+        QueryMsg::SimulateUnbond { amount } => {
+            to_binary(&query_simulate_unbond(deps, env, amount)?)
+        }
+        QueryMsg::LiquidityBuffer {} => to_binary(&query_liquidity_buffer(deps)?),
+        QueryMsg::Position {
+            address,
+            start_after,
+            limit,
+        } => to_binary(&query_position(deps, env, address, start_after, limit)?),
+        QueryMsg::SlashingHistory { start_after, limit } => {
+            to_binary(&query_slashing_history(deps, start_after, limit)?)
+        }
+        QueryMsg::ExchangeRateAt { time } => to_binary(&query_exchange_rate_at(deps, time)?),
+        QueryMsg::ExchangeRateTwap { window } => {
+            to_binary(&query_exchange_rate_twap(deps, env, window)?)
+        }
+    };
+    Ok(res?)
 }
 
 fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
@@ -453,36 +780,27 @@ fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     let mut reward: Option<String> = None;
     let mut token: Option<String> = None;
     let mut airdrop: Option<String> = None;
-    if config.reward_contract.is_some() {
-        reward = Some(
-            deps.api
-                .addr_humanize(&config.reward_contract.unwrap())
-                .unwrap()
-                .to_string(),
-        );
+    if let Some(addr) = config.reward_contract {
+        reward = Some(deps.api.addr_humanize(&addr).unwrap().to_string());
     }
-    if config.token_contract.is_some() {
-        token = Some(
-            deps.api
-                .addr_humanize(&config.token_contract.unwrap())
-                .unwrap()
-                .to_string(),
-        );
+    if let Some(addr) = config.token_contract {
+        token = Some(deps.api.addr_humanize(&addr).unwrap().to_string());
     }
-    if config.airdrop_registry_contract.is_some() {
-        airdrop = Some(
-            deps.api
-                .addr_humanize(&config.airdrop_registry_contract.unwrap())
-                .unwrap()
-                .to_string(),
-        );
+    if let Some(addr) = config.airdrop_registry_contract {
+        airdrop = Some(deps.api.addr_humanize(&addr).unwrap().to_string());
     }
+    let unbond_ticket_contract = match UNBOND_TICKET_CONTRACT.may_load(deps.storage)? {
+        Some(addr) => Some(deps.api.addr_humanize(&addr)?.to_string()),
+        None => None,
+    };
 
     Ok(ConfigResponse {
         owner: deps.api.addr_humanize(&config.creator)?.to_string(),
+        pending_owner: query_pending_owner(deps)?,
         reward_contract: reward,
         token_contract: token,
         airdrop_registry_contract: airdrop,
+        unbond_ticket_contract,
     })
 }
 
@@ -500,12 +818,39 @@ fn query_state(deps: Deps) -> StdResult<StateResponse> {
     Ok(res)
 }
 
+fn query_slashing_history(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<SlashingHistoryResponse> {
+    let history = read_slashing_history(deps.storage, start_after, limit)?;
+    Ok(SlashingHistoryResponse { history })
+}
+
 fn query_white_validators(deps: Deps) -> StdResult<WhitelistedValidatorsResponse> {
     let validators = read_valid_validators(deps.storage)?;
     let response = WhitelistedValidatorsResponse { validators };
     Ok(response)
 }
 
+fn query_validator_weights(deps: Deps) -> StdResult<ValidatorWeightsResponse> {
+    let weights = read_validator_weights(deps.storage)?;
+    Ok(ValidatorWeightsResponse { weights })
+}
+
+fn query_pause_info(deps: Deps) -> StdResult<PauseInfoResponse> {
+    let guardian = match GUARDIAN.may_load(deps.storage)? {
+        Some(guardian) => Some(deps.api.addr_humanize(&guardian)?.to_string()),
+        None => None,
+    };
+    Ok(PauseInfoResponse {
+        guardian,
+        paused_operations: PAUSED_OPERATIONS
+            .may_load(deps.storage)?
+            .unwrap_or_default(),
+    })
+}
+
 fn query_current_batch(deps: Deps) -> StdResult<CurrentBatchResponse> {
     let current_batch = CURRENT_BATCH.load(deps.storage)?;
     Ok(CurrentBatchResponse {
@@ -552,12 +897,31 @@ pub(crate) fn query_total_issued(deps: Deps) -> StdResult<Uint128> {
     Ok(token_info.total_supply)
 }
 
-fn query_unbond_requests(deps: Deps, address: String) -> StdResult<UnbondRequestsResponse> {
-    let requests = get_unbond_requests(deps.storage, address.clone())?;
+fn query_unbond_requests(
+    deps: Deps,
+    address: String,
+    start_from: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<UnbondRequestsResponse> {
+    let requests = get_unbond_requests(deps.storage, address.clone(), start_from, limit)?;
     let res = UnbondRequestsResponse { address, requests };
     Ok(res)
 }
 
+/// The legacy wait list is keyed by user, so a batch can only be listed once it is migrated.
+fn query_batch_unbond_requests(
+    deps: Deps,
+    batch_id: u64,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<BatchUnbondRequestsResponse, ContractError> {
+    if legacy_wait_list_pending(deps.storage)? {
+        return Err(ContractError::WaitListMigrationPending {});
+    }
+    let requests = get_batch_unbond_requests(deps.storage, batch_id, start_after, limit)?;
+    Ok(BatchUnbondRequestsResponse { batch_id, requests })
+}
+
 fn query_unbond_requests_limitation(
     deps: Deps,
     start: Option<u64>,
@@ -2,8 +2,15 @@ pub mod contract;
 pub mod state;
 
 mod bond;
+mod buffer;
+mod compound;
 mod config;
 mod math;
+mod pause;
+mod position;
+mod rate_history;
+mod rebalance;
+mod strategy;
 mod unbond;
 
 #[cfg(test)]
@@ -1,4 +1,5 @@
-use cosmwasm_std::{Decimal, Uint128};
+use cosmwasm_std::{Decimal, StdResult, Uint128};
+use std::cmp::Reverse;
 
 const DECIMAL_FRACTIONAL: Uint128 = Uint128::new(1_000_000_000u128);
 
@@ -8,6 +9,38 @@ pub fn decimal_division(a: Uint128, b: Decimal) -> Uint128 {
     decimal * DECIMAL_FRACTIONAL
 }
 
+/// Split `amount` proportionally to `weights`.
+/// The rounding leftover goes one unit at a time to the largest remainders,
+/// the earlier entry winning ties. If `amount` does not exceed the sum of
+/// `weights`, no share exceeds its weight.
+pub fn proportional_split(amount: Uint128, weights: &[Uint128]) -> StdResult<Vec<Uint128>> {
+    let total: Uint128 = weights.iter().sum();
+    if total.is_zero() {
+        return Ok(vec![Uint128::zero(); weights.len()]);
+    }
+
+    let mut shares: Vec<Uint128> = Vec::with_capacity(weights.len());
+    let mut remainders: Vec<(usize, Uint128)> = Vec::with_capacity(weights.len());
+    for (i, weight) in weights.iter().enumerate() {
+        let share = amount.multiply_ratio(*weight, total);
+        let remainder = amount
+            .checked_mul(*weight)?
+            .checked_sub(share.checked_mul(total)?)?;
+        shares.push(share);
+        remainders.push((i, remainder));
+    }
+
+    let distributed: Uint128 = shares.iter().sum();
+    let leftover = amount.checked_sub(distributed)?.u128() as usize;
+
+    // stable sort keeps the earlier entry first among equal remainders
+    remainders.sort_by_key(|r| Reverse(r.1));
+    for (i, _) in remainders.into_iter().take(leftover) {
+        shares[i] += Uint128::new(1);
+    }
+    Ok(shares)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -19,4 +52,64 @@ mod tests {
         let res = decimal_division(a, b);
         assert_eq!(res, Uint128::new(500));
     }
+
+    #[test]
+    fn test_proportional_split() {
+        let weights = [Uint128::new(10), Uint128::new(300), Uint128::new(200)];
+        let res = proportional_split(Uint128::new(150), &weights).unwrap();
+        assert_eq!(
+            res,
+            vec![Uint128::new(3), Uint128::new(88), Uint128::new(59)]
+        );
+
+        // exact split does not need any rounding
+        let weights = [Uint128::new(1000), Uint128::new(1500)];
+        let res = proportional_split(Uint128::new(2000), &weights).unwrap();
+        assert_eq!(res, vec![Uint128::new(800), Uint128::new(1200)]);
+
+        // equal remainders are resolved in favour of the earlier entry
+        let weights = [Uint128::new(1), Uint128::new(1), Uint128::new(1)];
+        let res = proportional_split(Uint128::new(2), &weights).unwrap();
+        assert_eq!(res, vec![Uint128::new(1), Uint128::new(1), Uint128::new(0)]);
+
+        // the whole amount never exceeds the weights
+        let res = proportional_split(Uint128::new(3), &weights).unwrap();
+        assert_eq!(res, vec![Uint128::new(1), Uint128::new(1), Uint128::new(1)]);
+    }
+
+    #[test]
+    fn test_proportional_split_keeps_distribution() {
+        let mut delegations = vec![
+            Uint128::new(1_000_000_000),
+            Uint128::new(3_000_000_000),
+            Uint128::new(6_000_000_000),
+        ];
+        let initial_total: Uint128 = delegations.iter().sum();
+        let initial_shares: Vec<Decimal> = delegations
+            .iter()
+            .map(|d| Decimal::from_ratio(*d, initial_total))
+            .collect();
+
+        for batch in 0..1000u128 {
+            let claim = Uint128::new(1_000_000 + (batch * 7_919) % 3_000_000);
+            let split = proportional_split(claim, &delegations).unwrap();
+            assert_eq!(split.iter().sum::<Uint128>(), claim);
+
+            for (d, s) in delegations.iter_mut().zip(split) {
+                *d = d.checked_sub(s).unwrap();
+            }
+
+            // every validator must stay within 2 uluna of its initial share
+            let total: Uint128 = delegations.iter().sum();
+            for (d, share) in delegations.iter().zip(initial_shares.iter()) {
+                let expected = total * *share;
+                let drift = if *d > expected {
+                    *d - expected
+                } else {
+                    expected - *d
+                };
+                assert!(drift <= Uint128::new(2));
+            }
+        }
+    }
 }
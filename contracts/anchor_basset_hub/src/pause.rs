@@ -0,0 +1,110 @@
+use crate::state::{CONFIG, GUARDIAN, PAUSED_OPERATIONS};
+use basset::contract_error::ContractError;
+use basset::hub::PausableOperation;
+use cosmwasm_std::{attr, DepsMut, Env, MessageInfo, Response, Storage};
+
+/// Set or remove the guardian.
+/// Only creator/owner is allowed to execute
+pub fn execute_update_guardian(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    guardian: Option<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if sender_raw != config.creator {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    match &guardian {
+        Some(guardian) => {
+            let guardian_raw = deps.api.addr_canonicalize(guardian)?;
+            GUARDIAN.save(deps.storage, &guardian_raw)?;
+        }
+        None => GUARDIAN.remove(deps.storage),
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_guardian"),
+        attr("guardian", guardian.unwrap_or_default()),
+    ]))
+}
+
+/// Pause operations of the hub, reward and token contracts.
+/// Only the guardian or creator/owner is allowed to execute
+pub fn execute_pause(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    operations: Vec<PausableOperation>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let is_guardian = GUARDIAN.may_load(deps.storage)? == Some(sender_raw.clone());
+    if sender_raw != config.creator && !is_guardian {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut paused = read_paused_operations(deps.storage)?;
+    for operation in operations.iter() {
+        if !paused.contains(operation) {
+            paused.push(*operation);
+        }
+    }
+    PAUSED_OPERATIONS.save(deps.storage, &paused)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "pause"),
+        attr("operations", join_operations(&operations)),
+    ]))
+}
+
+/// Resume paused operations.
+/// Only creator/owner is allowed to execute
+pub fn execute_unpause(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    operations: Vec<PausableOperation>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if sender_raw != config.creator {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut paused = read_paused_operations(deps.storage)?;
+    paused.retain(|operation| !operations.contains(operation));
+    PAUSED_OPERATIONS.save(deps.storage, &paused)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "unpause"),
+        attr("operations", join_operations(&operations)),
+    ]))
+}
+
+pub fn read_paused_operations(
+    storage: &dyn Storage,
+) -> Result<Vec<PausableOperation>, ContractError> {
+    Ok(PAUSED_OPERATIONS.may_load(storage)?.unwrap_or_default())
+}
+
+/// Fail if the given operation is paused.
+pub fn assert_not_paused(
+    storage: &dyn Storage,
+    operation: PausableOperation,
+) -> Result<(), ContractError> {
+    if read_paused_operations(storage)?.contains(&operation) {
+        return Err(ContractError::OperationPaused { operation });
+    }
+    Ok(())
+}
+
+fn join_operations(operations: &[PausableOperation]) -> String {
+    operations
+        .iter()
+        .map(|operation| operation.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
@@ -0,0 +1,107 @@
+use crate::state::{
+    get_unbond_requests, query_get_finished_amount, read_unbond_history, CONFIG, CURRENT_BATCH,
+    PARAMETERS, STATE, UNBOND_TICKETS, UNBOND_TICKET_CONTRACT,
+};
+use crate::unbond::{describe_ticket, open_batch, submitted_batch, unbond_ticket_contract};
+use basset::contract_error::ContractError;
+use basset::hub::{PositionResponse, PositionUnbondRequest};
+use basset::reward::{AccruedRewardsResponse, QueryMsg as RewardQueryMsg};
+use cosmwasm_std::{to_binary, Deps, Env, QueryRequest, Uint128, WasmQuery};
+use cw20::{BalanceResponse, Cw20QueryMsg};
+use cw721::{Cw721QueryMsg, TokensResponse};
+
+/// The most tickets the ticket contract lists in a page
+const MAX_TICKETS: u32 = 30;
+
+/// Gather the position of `address` across the hub, the token and the reward contracts.
+pub fn query_position(
+    deps: Deps,
+    env: Env,
+    address: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<PositionResponse, ContractError> {
+    let address = deps.api.addr_validate(&address)?.to_string();
+    let config = CONFIG.load(deps.storage)?;
+    let params = PARAMETERS.load(deps.storage)?;
+    let state = STATE.load(deps.storage)?;
+    let current_batch = CURRENT_BATCH.load(deps.storage)?;
+    let now = env.block.time.seconds();
+
+    let token_address = deps.api.addr_humanize(
+        &config
+            .token_contract
+            .expect("token contract must have been registered"),
+    )?;
+    let balance: BalanceResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: token_address.to_string(),
+        msg: to_binary(&Cw20QueryMsg::Balance {
+            address: address.clone(),
+        })?,
+    }))?;
+
+    let unbond_requests = get_unbond_requests(deps.storage, address.clone(), start_after, limit)?
+        .into_iter()
+        .map(|(batch_id, amount)| {
+            let batch = if batch_id == current_batch.id {
+                open_batch(&state, &params, &current_batch, now)
+            } else {
+                submitted_batch(&params, read_unbond_history(deps.storage, batch_id)?)
+            };
+            Ok(PositionUnbondRequest { amount, batch })
+        })
+        .collect::<Result<Vec<_>, ContractError>>()?;
+
+    let historical_time = now - params.unbonding_period;
+    let withdrawable = query_get_finished_amount(deps.storage, address.clone(), historical_time)?;
+
+    // the tickets are listed by the ticket contract, which knows their holders
+    let mut unbond_tickets = vec![];
+    if UNBOND_TICKET_CONTRACT.may_load(deps.storage)?.is_some() {
+        let tokens: TokensResponse = deps.querier.query_wasm_smart(
+            unbond_ticket_contract(deps)?.to_string(),
+            &Cw721QueryMsg::Tokens {
+                owner: address.clone(),
+                start_after: None,
+                limit: Some(MAX_TICKETS),
+            },
+        )?;
+        for ticket_id in tokens.tokens {
+            let ticket = UNBOND_TICKETS.load(deps.storage, &ticket_id)?;
+            unbond_tickets.push(describe_ticket(
+                deps.storage,
+                &params,
+                &state,
+                &current_batch,
+                now,
+                ticket_id,
+                ticket,
+            )?);
+        }
+    }
+
+    let accrued_rewards = match config.reward_contract {
+        Some(reward_contract) => deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: deps.api.addr_humanize(&reward_contract)?.to_string(),
+            msg: to_binary(&RewardQueryMsg::AccruedRewards {
+                address: address.clone(),
+            })?,
+        }))?,
+        None => AccruedRewardsResponse {
+            rewards: Uint128::zero(),
+            asset_rewards: vec![],
+        },
+    };
+
+    Ok(PositionResponse {
+        address,
+        balance: balance.balance,
+        exchange_rate: state.exchange_rate,
+        balance_value: balance.balance * state.exchange_rate,
+        unbond_requests,
+        withdrawable,
+        accrued_rewards: accrued_rewards.rewards,
+        asset_rewards: accrued_rewards.asset_rewards,
+        unbond_tickets,
+    })
+}
@@ -0,0 +1,98 @@
+use crate::state::{rate_checkpoint_range, read_rate_checkpoint};
+use basset::contract_error::ContractError;
+use basset::hub::{ExchangeRateAtResponse, ExchangeRateTwapResponse};
+use cosmwasm_std::{Decimal, Deps, Env, StdResult, Storage, Uint128};
+use std::cmp::{max, min};
+
+// the exchange rate is weighted at full precision
+const RATE_PRECISION: Uint128 = Uint128::new(1_000_000_000_000_000_000u128);
+
+pub fn query_exchange_rate_at(
+    deps: Deps,
+    time: u64,
+) -> Result<ExchangeRateAtResponse, ContractError> {
+    let index =
+        find_checkpoint(deps.storage, time)?.ok_or(ContractError::NoRateCheckpoint { time })?;
+    let checkpoint = read_rate_checkpoint(deps.storage, index)?;
+    Ok(ExchangeRateAtResponse {
+        exchange_rate: checkpoint.exchange_rate,
+        checkpoint_time: checkpoint.time,
+    })
+}
+
+/// Average the exchange rate over the last `window` seconds, weighted by the time each rate held.
+/// The window starts at the oldest checkpoint if the ring buffer does not reach further.
+pub fn query_exchange_rate_twap(
+    deps: Deps,
+    env: Env,
+    window: u64,
+) -> Result<ExchangeRateTwapResponse, ContractError> {
+    let end_time = env.block.time.seconds();
+    let (oldest, count) = rate_checkpoint_range(deps.storage)?;
+    if oldest == count {
+        return Err(ContractError::NoRateCheckpoint { time: end_time });
+    }
+
+    let oldest_time = read_rate_checkpoint(deps.storage, oldest)?.time;
+    let start_time = max(end_time.saturating_sub(window), oldest_time);
+    if start_time >= end_time {
+        let index = find_checkpoint(deps.storage, end_time)?.unwrap_or(oldest);
+        return Ok(ExchangeRateTwapResponse {
+            start_time: end_time,
+            end_time,
+            twap: read_rate_checkpoint(deps.storage, index)?.exchange_rate,
+        });
+    }
+
+    let mut index = find_checkpoint(deps.storage, start_time)?
+        .expect("the window does not start before the oldest checkpoint");
+    let mut checkpoint = read_rate_checkpoint(deps.storage, index)?;
+    let mut time = start_time;
+    let mut weighted_sum = Uint128::zero();
+    while time < end_time {
+        let next = if index + 1 < count {
+            Some(read_rate_checkpoint(deps.storage, index + 1)?)
+        } else {
+            None
+        };
+        let until = next
+            .as_ref()
+            .map_or(end_time, |next| min(next.time, end_time));
+
+        let rate = RATE_PRECISION * checkpoint.exchange_rate;
+        weighted_sum += rate.checked_mul(Uint128::from(until - time))?;
+        time = until;
+
+        match next {
+            Some(next) => checkpoint = next,
+            None => break,
+        }
+        index += 1;
+    }
+
+    let duration = RATE_PRECISION.checked_mul(Uint128::from(end_time - start_time))?;
+    Ok(ExchangeRateTwapResponse {
+        start_time,
+        end_time,
+        twap: Decimal::from_ratio(weighted_sum, duration),
+    })
+}
+
+/// Return the number of the last checkpoint at or before `time`,
+/// none if the ring buffer holds no checkpoint that old.
+fn find_checkpoint(storage: &dyn Storage, time: u64) -> StdResult<Option<u64>> {
+    let (oldest, count) = rate_checkpoint_range(storage)?;
+
+    // the checkpoints are ordered by time
+    let (mut low, mut high) = (oldest, count);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if read_rate_checkpoint(storage, mid)?.time <= time {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+
+    Ok(if low == oldest { None } else { Some(low - 1) })
+}
@@ -0,0 +1,116 @@
+use crate::math::proportional_split;
+use crate::state::{
+    decrease_expected_delegation, increase_expected_delegation, is_in_redelegation_cooldown,
+    read_validator_weights, store_redelegation_cooldown, PARAMETERS,
+};
+use crate::strategy::query_delegated_amounts;
+use basset::contract_error::ContractError;
+use basset::hub::ExecuteMsg;
+use cosmwasm_std::{
+    attr, to_binary, Coin, CosmosMsg, DepsMut, Env, Response, StakingMsg, Uint128, WasmMsg,
+};
+use std::cmp::{min, Reverse};
+
+/// Redelegate stake from validators above their target weight to validators below it.
+/// Anyone can execute. Validators that have received a redelegation within the
+/// unbonding period are not used as source, and at most `max_rebalance_amount`
+/// is moved per call.
+pub fn execute_rebalance(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let params = PARAMETERS.load(deps.storage)?;
+    let weights = read_validator_weights(deps.storage)?;
+    let total_weight: u64 = weights.iter().map(|w| w.weight).sum();
+    if total_weight == 0 {
+        return Err(ContractError::NoTargetWeights {});
+    }
+
+    let mut delegated = query_delegated_amounts(
+        deps.as_ref(),
+        env.contract.address.clone(),
+        &params.underlying_coin_denom,
+    )?;
+    // weighted validators without delegation are still rebalancing targets.
+    for w in weights.iter() {
+        delegated.entry(w.validator.clone()).or_default();
+    }
+    let total_delegated: Uint128 = delegated.values().copied().sum();
+
+    let validator_weights: Vec<Uint128> = delegated
+        .keys()
+        .map(|val| {
+            weights
+                .iter()
+                .find(|w| &w.validator == val)
+                .map(|w| Uint128::from(w.weight))
+                .unwrap_or_default()
+        })
+        .collect();
+    let targets = proportional_split(total_delegated, &validator_weights)?;
+
+    let block_time = env.block.time.seconds();
+    let mut sources: Vec<(String, Uint128)> = vec![];
+    let mut sinks: Vec<(String, Uint128)> = vec![];
+    for ((val, amount), target) in delegated.into_iter().zip(targets) {
+        if amount > target {
+            if !is_in_redelegation_cooldown(deps.storage, val.clone(), block_time)? {
+                sources.push((val, amount.checked_sub(target)?));
+            }
+        } else if target > amount {
+            sinks.push((val, target.checked_sub(amount)?));
+        }
+    }
+    sources.sort_by_key(|s| Reverse(s.1));
+    sinks.sort_by_key(|s| Reverse(s.1));
+
+    let mut remaining = params.max_rebalance_amount.unwrap_or(total_delegated);
+    let mut moved = Uint128::zero();
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut sink_iter = sinks.into_iter().peekable();
+    'sources: for (src, mut surplus) in sources {
+        while !surplus.is_zero() {
+            if remaining.is_zero() {
+                break 'sources;
+            }
+            let (dst, deficit) = match sink_iter.peek_mut() {
+                Some(sink) => sink,
+                None => break 'sources,
+            };
+            let amount = min(min(surplus, *deficit), remaining);
+
+            messages.push(CosmosMsg::Staking(StakingMsg::Redelegate {
+                src_validator: src.clone(),
+                dst_validator: dst.clone(),
+                amount: Coin::new(amount.u128(), &params.underlying_coin_denom),
+            }));
+            store_redelegation_cooldown(
+                deps.storage,
+                dst.clone(),
+                block_time + params.unbonding_period,
+            )?;
+            decrease_expected_delegation(deps.storage, &src, amount)?;
+            increase_expected_delegation(deps.storage, dst, amount)?;
+
+            surplus = surplus.checked_sub(amount)?;
+            *deficit = deficit.checked_sub(amount)?;
+            remaining = remaining.checked_sub(amount)?;
+            moved += amount;
+            if deficit.is_zero() {
+                sink_iter.next();
+            }
+        }
+    }
+
+    // redelegations withdraw the pending rewards, so the global index must be updated.
+    if !messages.is_empty() {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            msg: to_binary(&ExecuteMsg::UpdateGlobalIndex {
+                airdrop_hooks: None,
+            })?,
+            funds: vec![],
+        }));
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![attr("action", "rebalance"), attr("moved", moved)]))
+}
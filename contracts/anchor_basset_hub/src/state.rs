@@ -2,12 +2,21 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_std::{
-    from_slice, to_vec, Addr, Decimal, Order, StdError, StdResult, Storage, Uint128,
+    from_slice, to_vec, CanonicalAddr, Decimal, Order, StdError, StdResult, Storage, Uint128,
 };
 use cosmwasm_storage::{Bucket, PrefixedStorage, ReadonlyBucket, ReadonlyPrefixedStorage};
-use cw_storage_plus::Item;
+use cw_storage_plus::{
+    Bound, Index, IndexList, IndexedMap, Item, Map, MultiIndex, PrimaryKey, U64Key,
+};
+use std::collections::BTreeMap;
+use std::convert::TryInto;
 
-use basset::hub::{Config, State, UnbondHistory, UnbondRequest};
+use basset::contract_error::ContractError;
+use basset::hub::{
+    Config, DelegationStrategy, PausableOperation, RewardMode, SlashingEvent, State, UnbondHistory,
+    UnbondRequest, ValidatorWeight, PAUSED_OPERATIONS_KEY,
+};
+use basset::unbond_ticket::UnbondTicket;
 
 pub type LastBatch = u64;
 
@@ -24,6 +33,12 @@ pub struct Parameters {
     pub peg_recovery_fee: Decimal,
     pub er_threshold: Decimal,
     pub reward_denom: String,
+    #[serde(default)]
+    pub delegation_strategy: DelegationStrategy,
+    #[serde(default)]
+    pub max_rebalance_amount: Option<Uint128>,
+    #[serde(default)]
+    pub reward_mode: RewardMode,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -32,10 +47,132 @@ pub struct CurrentBatch {
     pub requested_with_fee: Uint128,
 }
 
+/// Progress of the processing of the matured batches waiting for their withdraw rate.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WithdrawRateCursor {
+    /// The last batch whose unbonded amount is included in `scanned_amount`
+    pub last_scanned_batch: u64,
+    /// The unbonded amount expected by the scanned batches
+    pub scanned_amount: Uint128,
+    /// The amounts shared by the scanned batches while they are being released
+    pub release: Option<ReleaseRound>,
+}
+
+/// The matured batches are released against fixed amounts, even across calls,
+/// so that splitting the work does not change the withdraw rates.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReleaseRound {
+    /// The unbonded amount expected by the batches of the round
+    pub unbonded_amount: Uint128,
+    /// The amount the hub actually received for the batches of the round
+    pub actual_amount: Uint128,
+}
+
+/// Underlying coin the owner keeps in the hub to pay instant unbonds.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct LiquidityBuffer {
+    /// The coins available to pay instant unbonds
+    pub balance: Uint128,
+    /// The coins paid out for requests that are not released yet
+    pub lent: Uint128,
+    /// The share of the unbonded value kept by the buffer
+    pub fee: Decimal,
+    /// The share of the buffer that can be lent at once
+    pub max_utilisation: Decimal,
+}
+
+/// Unbond requests of a batch made on behalf of the liquidity buffer.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct BufferRequest {
+    /// The requested amount added to the batch
+    pub amount: Uint128,
+    /// The coins paid out for the requests
+    pub lent: Uint128,
+}
+
+/// The exchange rate of the hub from `time` until the next checkpoint.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RateCheckpoint {
+    pub time: u64,
+    pub exchange_rate: Decimal,
+}
+
 pub const CONFIG: Item<Config> = Item::new("\u{0}\u{6}config");
 pub const PARAMETERS: Item<Parameters> = Item::new("\u{0}\u{b}parameteres");
 pub const CURRENT_BATCH: Item<CurrentBatch> = Item::new("\u{0}\u{d}current_batch");
 pub const STATE: Item<State> = Item::new("\u{0}\u{5}state");
+pub const VALIDATOR_WEIGHTS: Map<&str, u64> = Map::new("validator_weights");
+pub const REDELEGATION_COOLDOWNS: Map<&str, u64> = Map::new("redelegation_cooldowns");
+pub const WITHDRAW_RATE_CURSOR: Item<WithdrawRateCursor> = Item::new("withdraw_rate_cursor");
+pub const GUARDIAN: Item<CanonicalAddr> = Item::new("guardian");
+pub const UNBOND_TICKET_CONTRACT: Item<CanonicalAddr> = Item::new("unbond_ticket_contract");
+pub const LAST_TICKET_ID: Item<u64> = Item::new("last_ticket_id");
+/// Unbond requests held by tickets, ticket id -> request
+pub const UNBOND_TICKETS: Map<&str, UnbondTicket> = Map::new("unbond_tickets");
+pub const LIQUIDITY_BUFFER: Item<LiquidityBuffer> = Item::new("liquidity_buffer");
+/// Instant unbonds waiting for their batch, batch id -> request
+pub const BUFFER_REQUESTS: Map<U64Key, BufferRequest> = Map::new("buffer_requests");
+/// Delegations of the hub per validator as of the last slashing check,
+/// adjusted by the delegation messages sent since
+pub const EXPECTED_DELEGATIONS: Map<&str, Uint128> = Map::new("expected_delegations");
+pub const LAST_SLASHING_ID: Item<u64> = Item::new("last_slashing_id");
+pub const SLASHING_HISTORY: Map<U64Key, SlashingEvent> = Map::new("slashing_history");
+/// Ring buffer of the last `MAX_RATE_CHECKPOINTS` checkpoints, slot -> checkpoint
+pub const RATE_CHECKPOINTS: Map<U64Key, RateCheckpoint> = Map::new("rate_checkpoints");
+/// The number of checkpoints ever recorded
+pub const RATE_CHECKPOINT_COUNT: Item<u64> = Item::new("rate_checkpoint_count");
+pub const PAUSED_OPERATIONS: Item<Vec<PausableOperation>> = Item::new(PAUSED_OPERATIONS_KEY);
+
+/// Last legacy wait list key moved by the wait list migration, present while it is pending
+pub const WAIT_LIST_MIGRATION_CURSOR: Item<Vec<u8>> = Item::new("wait_list_migration_cursor");
+
+/// Secondary indexes of the unbond wait list.
+pub struct UnbondWaitListIndexes<'a> {
+    /// Requests of a batch, keyed by (batch id, primary key)
+    pub batch: MultiIndex<'a, (U64Key, Vec<u8>), Uint128>,
+}
+
+impl<'a> IndexList<Uint128> for UnbondWaitListIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Uint128>> + '_> {
+        let v: Vec<&dyn Index<Uint128>> = vec![&self.batch];
+        Box::new(v.into_iter())
+    }
+}
+
+/// The undelegation wait list, (user's address, batch id) -> requested amount.
+pub fn unbond_wait_list<'a>(
+) -> IndexedMap<'a, (&'a str, U64Key), Uint128, UnbondWaitListIndexes<'a>> {
+    let indexes = UnbondWaitListIndexes {
+        // the batch id is always the last 8 bytes of the primary key
+        batch: MultiIndex::new(
+            |_, pk| (U64Key::from(pk[pk.len() - 8..].to_vec()), pk),
+            "unbond_wait_list",
+            "unbond_wait_list__batch",
+        ),
+    };
+    IndexedMap::new("unbond_wait_list", indexes)
+}
+
+/// Split a primary key of the wait list into the user's address and the batch id.
+fn parse_wait_list_key(pk: &[u8]) -> StdResult<(String, u64)> {
+    let invalid = || StdError::generic_err("Invalid wait list key");
+    if pk.len() < 2 {
+        return Err(invalid());
+    }
+    let addr_len = u16::from_be_bytes([pk[0], pk[1]]) as usize;
+    if pk.len() != 2 + addr_len + 8 {
+        return Err(invalid());
+    }
+    let addr = String::from_utf8(pk[2..2 + addr_len].to_vec()).map_err(|_| invalid())?;
+    Ok((addr, parse_batch_key(&pk[2 + addr_len..])?))
+}
+
+fn parse_batch_key(key: &[u8]) -> StdResult<u64> {
+    let batch: [u8; 8] = key
+        .try_into()
+        .map_err(|_| StdError::generic_err("Invalid batch key"))?;
+    Ok(u64::from_be_bytes(batch))
+}
 
 /// Store undelegation wait list per each batch
 /// HashMap<user's address, <batch_id, requested_amount>
@@ -45,31 +182,42 @@ pub fn store_unbond_wait_list(
     sender_address: String,
     amount: Uint128,
 ) -> StdResult<()> {
-    let batch = to_vec(&batch_id)?;
-    let addr = to_vec(&sender_address)?;
-    let mut position_indexer: Bucket<Uint128> =
-        Bucket::multilevel(storage, &[PREFIX_WAIT_MAP, &addr]);
-    position_indexer.update(&batch, |asked_already| -> StdResult<Uint128> {
-        Ok(asked_already.unwrap_or_default() + amount)
-    })?;
+    migrate_user_wait_list(storage, &sender_address)?;
+    add_to_wait_list(storage, batch_id, &sender_address, amount)
+}
+
+fn add_to_wait_list(
+    storage: &mut dyn Storage,
+    batch_id: u64,
+    sender_address: &str,
+    amount: Uint128,
+) -> StdResult<()> {
+    unbond_wait_list().update(
+        storage,
+        (sender_address, U64Key::new(batch_id)),
+        |asked_already| -> StdResult<Uint128> { Ok(asked_already.unwrap_or_default() + amount) },
+    )?;
 
     Ok(())
 }
 
-/// Remove unbond batch id from user's wait list
-pub fn remove_unbond_wait_list(
+/// Decrease the requested amount of a user in a batch,
+/// the request is removed once nothing is left.
+pub fn decrease_unbond_wait_list(
     storage: &mut dyn Storage,
-    batch_id: Vec<u64>,
-    sender_address: Addr,
+    batch_id: u64,
+    sender_address: String,
+    amount: Uint128,
 ) -> StdResult<()> {
-    let addr = to_vec(&sender_address)?;
-    let mut position_indexer: Bucket<Uint128> =
-        Bucket::multilevel(storage, &[PREFIX_WAIT_MAP, &addr]);
-    for b in batch_id {
-        let batch = to_vec(&b)?;
-        position_indexer.remove(&batch);
+    migrate_user_wait_list(storage, &sender_address)?;
+    let wait_list = unbond_wait_list();
+    let key = (sender_address.as_str(), U64Key::new(batch_id));
+    let left = wait_list.load(storage, key.clone())?.checked_sub(amount)?;
+    if left.is_zero() {
+        wait_list.remove(storage, key)
+    } else {
+        wait_list.save(storage, key, &left)
     }
-    Ok(())
 }
 
 pub fn read_unbond_wait_list(
@@ -77,73 +225,144 @@ pub fn read_unbond_wait_list(
     batch_id: u64,
     sender_addr: String,
 ) -> StdResult<Uint128> {
-    let vec = to_vec(&sender_addr)?;
-    let res: ReadonlyBucket<Uint128> =
-        ReadonlyBucket::multilevel(storage, &[PREFIX_WAIT_MAP, &vec]);
-    let batch = to_vec(&batch_id)?;
-    res.load(&batch)
+    let key = (sender_addr.as_str(), U64Key::new(batch_id));
+    let legacy = read_legacy_user_wait_list(storage, &sender_addr)?
+        .into_iter()
+        .find(|(legacy_batch, _)| *legacy_batch == batch_id);
+    match legacy {
+        Some((_, amount)) => Ok(amount
+            + unbond_wait_list()
+                .may_load(storage, key)?
+                .unwrap_or_default()),
+        None => unbond_wait_list().load(storage, key),
+    }
 }
 
-pub fn get_unbond_requests(storage: &dyn Storage, sender_addr: String) -> StdResult<UnbondRequest> {
-    let vec = to_vec(&sender_addr)?;
-    let mut requests: UnbondRequest = vec![];
+type UserWaitList<'a> = Box<dyn Iterator<Item = StdResult<(u64, Uint128)>> + 'a>;
 
-    let res: ReadonlyBucket<Uint128> =
-        ReadonlyBucket::multilevel(storage, &[PREFIX_WAIT_MAP, &vec]);
-    let _un: Vec<_> = res
-        .range(None, None, Order::Ascending)
+/// Return the (batch id, requested amount) pairs of a user in ascending batch order,
+/// starting after the `start_after` batch.
+fn range_user_wait_list<'a>(
+    storage: &'a dyn Storage,
+    sender_addr: &str,
+    start_after: Option<u64>,
+) -> StdResult<UserWaitList<'a>> {
+    let start = start_after.map(|b| Bound::exclusive(U64Key::new(b)));
+    let requests = unbond_wait_list()
+        .prefix(sender_addr)
+        .range(storage, start, None, Order::Ascending)
         .map(|item| {
-            let (k, value) = item.unwrap();
-            let user_batch: u64 = from_slice(&k).unwrap();
-            requests.push((user_batch, value))
-        })
-        .collect();
-    Ok(requests)
+            let (k, v) = item?;
+            Ok((parse_batch_key(&k)?, v))
+        });
+
+    let legacy = read_legacy_user_wait_list(storage, sender_addr)?;
+    if legacy.is_empty() {
+        return Ok(Box::new(requests));
+    }
+
+    // a page of the migration may have moved only a part of the requests of the user
+    let mut merged: BTreeMap<u64, Uint128> = BTreeMap::new();
+    let legacy = legacy
+        .into_iter()
+        .filter(|(batch_id, _)| start_after.map_or(true, |start| *batch_id > start))
+        .map(Ok);
+    for item in requests.chain(legacy) {
+        let (batch_id, amount) = item?;
+        *merged.entry(batch_id).or_default() += amount;
+    }
+    Ok(Box::new(merged.into_iter().map(Ok)))
 }
 
-pub fn get_unbond_batches(storage: &dyn Storage, sender_addr: String) -> StdResult<Vec<u64>> {
-    let vec = to_vec(&sender_addr)?;
-    let mut deprecated_batches: Vec<u64> = vec![];
-    let res: ReadonlyBucket<Uint128> =
-        ReadonlyBucket::multilevel(storage, &[PREFIX_WAIT_MAP, &vec]);
-    let _un: Vec<_> = res
-        .range(None, None, Order::Ascending)
+/// Return a page of the unbond requests of a user in ascending batch order.
+pub fn get_unbond_requests(
+    storage: &dyn Storage,
+    sender_addr: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<UnbondRequest> {
+    let lim = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    range_user_wait_list(storage, &sender_addr, start_after)?
+        .take(lim)
+        .collect()
+}
+
+/// Return a page of the (user's address, requested amount) pairs of a batch
+/// in storage order. The requests still in the legacy wait list are not listed.
+pub fn get_batch_unbond_requests(
+    storage: &dyn Storage,
+    batch_id: u64,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(String, Uint128)>> {
+    let lim = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .as_ref()
+        .map(|addr| Bound::exclusive((addr.as_str(), U64Key::new(batch_id)).joined_key()));
+    let requests = unbond_wait_list()
+        .idx
+        .batch
+        .prefix(U64Key::new(batch_id))
+        .range(storage, start, None, Order::Ascending)
+        .take(lim)
         .map(|item| {
-            let (k, _) = item.unwrap();
-            let user_batch: u64 = from_slice(&k).unwrap();
-            let history = read_unbond_history(storage, user_batch);
-            if let Ok(h) = history {
-                if h.released {
-                    deprecated_batches.push(user_batch);
-                }
-            }
+            let (pk, v) = item?;
+            Ok((parse_wait_list_key(&pk)?.0, v))
         })
-        .collect();
-    Ok(deprecated_batches)
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(requests)
 }
 
-/// Return all requested unbond amount.
+/// Remove the released requests of a user from the wait list
+/// and return the amount they are worth at the withdraw rate of their batch.
+/// With `batch_ids`, only those batches are considered; the ones that are not
+/// released or hold no request of the user are skipped.
+/// Otherwise the wait list of the user is scanned once.
 /// This needs to be called after process withdraw rate function.
-/// If the batch is released, this will return user's requested
-/// amount proportional to withdraw rate.
-pub fn get_finished_amount(storage: &dyn Storage, sender_addr: String) -> StdResult<Uint128> {
-    let vec = to_vec(&sender_addr)?;
-    let mut withdrawable_amount: Uint128 = Uint128::zero();
-    let res: ReadonlyBucket<Uint128> =
-        ReadonlyBucket::multilevel(storage, &[PREFIX_WAIT_MAP, &vec]);
-    let _un: Vec<_> = res
-        .range(None, None, Order::Ascending)
-        .map(|item| {
-            let (k, v) = item.unwrap();
-            let user_batch: u64 = from_slice(&k).unwrap();
-            let history = read_unbond_history(storage, user_batch);
-            if let Ok(h) = history {
-                if h.released {
-                    withdrawable_amount += v * h.withdraw_rate;
+pub fn take_finished_amount(
+    storage: &mut dyn Storage,
+    sender_addr: String,
+    batch_ids: Option<Vec<u64>>,
+) -> StdResult<Uint128> {
+    migrate_user_wait_list(storage, &sender_addr)?;
+    let wait_list = unbond_wait_list();
+    let requests: Vec<(u64, Uint128)> = match batch_ids {
+        Some(mut batch_ids) => {
+            batch_ids.sort_unstable();
+            batch_ids.dedup();
+            let mut requests = vec![];
+            for batch_id in batch_ids {
+                let key = (sender_addr.as_str(), U64Key::new(batch_id));
+                if let Some(requested) = wait_list.may_load(storage, key)? {
+                    requests.push((batch_id, requested));
                 }
             }
-        })
-        .collect();
+            requests
+        }
+        None => range_user_wait_list(storage, &sender_addr, None)?.collect::<StdResult<_>>()?,
+    };
+
+    let mut withdrawable_amount = Uint128::zero();
+    let mut released_batches: Vec<(u64, Uint128)> = vec![];
+    for (batch_id, requested) in requests {
+        match may_read_unbond_history(storage, batch_id)? {
+            Some(h) if h.released => {
+                withdrawable_amount += requested * h.withdraw_rate;
+                released_batches.push((batch_id, requested));
+            }
+            _ => continue,
+        }
+    }
+
+    for (batch_id, requested) in released_batches {
+        // the value is known, skip the load `IndexedMap::remove` would do
+        wait_list.replace(
+            storage,
+            (sender_addr.as_str(), U64Key::new(batch_id)),
+            None,
+            Some(&requested),
+        )?;
+    }
     Ok(withdrawable_amount)
 }
 
@@ -153,24 +372,116 @@ pub fn query_get_finished_amount(
     sender_addr: String,
     block_time: u64,
 ) -> StdResult<Uint128> {
-    let vec = to_vec(&sender_addr)?;
     let mut withdrawable_amount: Uint128 = Uint128::zero();
-    let res: ReadonlyBucket<Uint128> =
-        ReadonlyBucket::multilevel(storage, &[PREFIX_WAIT_MAP, &vec]);
-    let _un: Vec<_> = res
+    for item in range_user_wait_list(storage, &sender_addr, None)? {
+        let (batch_id, requested) = item?;
+        match may_read_unbond_history(storage, batch_id)? {
+            Some(h) if h.time < block_time => {
+                withdrawable_amount += requested * h.withdraw_rate;
+            }
+            // batches are submitted in order
+            _ => break,
+        }
+    }
+    Ok(withdrawable_amount)
+}
+
+/// Start moving the wait list from the legacy `Bucket` layout to `unbond_wait_list`.
+/// The legacy layout is namespaced by `PREFIX_WAIT_MAP` and the JSON encoded
+/// user address, and keyed by the JSON encoded batch id.
+/// Until `migrate_unbond_wait_list` has emptied it, the legacy layout is read
+/// alongside `unbond_wait_list`, and the requests of a user are moved
+/// before they are updated.
+pub fn start_wait_list_migration(storage: &mut dyn Storage) -> StdResult<()> {
+    WAIT_LIST_MIGRATION_CURSOR.save(storage, &vec![])
+}
+
+/// Move up to `limit` requests of the legacy wait list to `unbond_wait_list`,
+/// resuming after the last key moved by the previous call.
+/// Return the number of moved requests and whether the migration is done.
+pub fn migrate_unbond_wait_list(storage: &mut dyn Storage, limit: u32) -> StdResult<(u64, bool)> {
+    let mut start = match WAIT_LIST_MIGRATION_CURSOR.may_load(storage)? {
+        Some(cursor) => cursor,
+        None => return Ok((0, true)),
+    };
+    if !start.is_empty() {
+        // the smallest key after the cursor
+        start.push(0);
+    }
+
+    let page: Vec<(Vec<u8>, Uint128)> = ReadonlyPrefixedStorage::new(storage, PREFIX_WAIT_MAP)
+        .range(Some(&start), None, Order::Ascending)
+        .take(limit as usize)
+        .map(|(k, v)| Ok((k, from_slice(&v)?)))
+        .collect::<StdResult<_>>()?;
+
+    for (key, amount) in &page {
+        let (sender_address, batch_id) = parse_legacy_wait_list_key(key)?;
+        add_to_wait_list(storage, batch_id, &sender_address, *amount)?;
+        PrefixedStorage::new(storage, PREFIX_WAIT_MAP).remove(key);
+    }
+
+    let done = page.len() < limit as usize;
+    if done {
+        WAIT_LIST_MIGRATION_CURSOR.remove(storage);
+    } else if let Some((key, _)) = page.last() {
+        WAIT_LIST_MIGRATION_CURSOR.save(storage, key)?;
+    }
+    Ok((page.len() as u64, done))
+}
+
+/// Whether the legacy wait list is still being moved by `migrate_unbond_wait_list`.
+pub fn legacy_wait_list_pending(storage: &dyn Storage) -> StdResult<bool> {
+    Ok(WAIT_LIST_MIGRATION_CURSOR.may_load(storage)?.is_some())
+}
+
+/// Split a key of the legacy wait list into the user's address and the batch id,
+/// the key is the length prefixed address namespace followed by the batch id.
+fn parse_legacy_wait_list_key(key: &[u8]) -> StdResult<(String, u64)> {
+    let invalid = || StdError::generic_err("Invalid legacy wait list key");
+    if key.len() < 2 {
+        return Err(invalid());
+    }
+    let addr_len = u16::from_be_bytes([key[0], key[1]]) as usize;
+    if key.len() < 2 + addr_len {
+        return Err(invalid());
+    }
+    let sender_address: String = from_slice(&key[2..2 + addr_len])?;
+    let batch_id: u64 = from_slice(&key[2 + addr_len..])?;
+    Ok((sender_address, batch_id))
+}
+
+/// Return the (batch id, requested amount) pairs of a user that are not migrated yet,
+/// in ascending batch order.
+fn read_legacy_user_wait_list(
+    storage: &dyn Storage,
+    sender_addr: &str,
+) -> StdResult<Vec<(u64, Uint128)>> {
+    if !legacy_wait_list_pending(storage)? {
+        return Ok(vec![]);
+    }
+    let addr = to_vec(&sender_addr)?;
+    let mut requests = ReadonlyBucket::<Uint128>::multilevel(storage, &[PREFIX_WAIT_MAP, &addr])
         .range(None, None, Order::Ascending)
         .map(|item| {
-            let (k, v) = item.unwrap();
-            let user_batch: u64 = from_slice(&k).unwrap();
-            let history = read_unbond_history(storage, user_batch);
-            if let Ok(h) = history {
-                if h.time < block_time {
-                    withdrawable_amount += v * h.withdraw_rate;
-                }
-            }
+            let (k, v) = item?;
+            Ok((from_slice(&k)?, v))
         })
-        .collect();
-    Ok(withdrawable_amount)
+        .collect::<StdResult<Vec<(u64, Uint128)>>>()?;
+    // the JSON encoded batch ids do not sort numerically
+    requests.sort_unstable_by_key(|(batch_id, _)| *batch_id);
+    Ok(requests)
+}
+
+/// Move the requests of a user left in the legacy wait list to `unbond_wait_list`.
+fn migrate_user_wait_list(storage: &mut dyn Storage, sender_addr: &str) -> StdResult<()> {
+    let addr = to_vec(&sender_addr)?;
+    for (batch_id, amount) in read_legacy_user_wait_list(storage, sender_addr)? {
+        add_to_wait_list(storage, batch_id, sender_addr, amount)?;
+        Bucket::<Uint128>::multilevel(storage, &[PREFIX_WAIT_MAP, &addr])
+            .remove(&to_vec(&batch_id)?);
+    }
+    Ok(())
 }
 
 /// Store valid validators
@@ -232,6 +543,58 @@ pub fn read_valid_validators(storage: &dyn Storage) -> StdResult<Vec<String>> {
     Ok(validators)
 }
 
+/// Replace the target weights of the validators
+pub fn store_validator_weights(
+    storage: &mut dyn Storage,
+    weights: Vec<ValidatorWeight>,
+) -> StdResult<()> {
+    let previous = read_validator_weights(storage)?;
+    for w in previous {
+        VALIDATOR_WEIGHTS.remove(storage, &w.validator);
+    }
+    for w in weights {
+        VALIDATOR_WEIGHTS.save(storage, &w.validator, &w.weight)?;
+    }
+    Ok(())
+}
+
+/// Remove the target weight of a validator
+pub fn remove_validator_weight(storage: &mut dyn Storage, validator_address: String) {
+    VALIDATOR_WEIGHTS.remove(storage, &validator_address);
+}
+
+/// Read the target weights of all validators
+pub fn read_validator_weights(storage: &dyn Storage) -> StdResult<Vec<ValidatorWeight>> {
+    VALIDATOR_WEIGHTS
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (k, weight) = item?;
+            let validator =
+                String::from_utf8(k).map_err(|_| StdError::generic_err("Invalid validator key"))?;
+            Ok(ValidatorWeight { validator, weight })
+        })
+        .collect()
+}
+
+/// Store the time until which a validator can not be the source of a redelegation
+pub fn store_redelegation_cooldown(
+    storage: &mut dyn Storage,
+    validator_address: String,
+    until: u64,
+) -> StdResult<()> {
+    REDELEGATION_COOLDOWNS.save(storage, &validator_address, &until)
+}
+
+/// Check whether a validator has received a redelegation that has not matured yet
+pub fn is_in_redelegation_cooldown(
+    storage: &dyn Storage,
+    validator_address: String,
+    block_time: u64,
+) -> StdResult<bool> {
+    let until = REDELEGATION_COOLDOWNS.may_load(storage, &validator_address)?;
+    Ok(until.map_or(false, |t| t > block_time))
+}
+
 /// Store unbond history map
 /// Hashmap<batch_id, <UnbondHistory>>
 pub fn store_unbond_history(
@@ -245,20 +608,145 @@ pub fn store_unbond_history(
     Ok(())
 }
 
-pub fn read_unbond_history(storage: &dyn Storage, epoc_id: u64) -> StdResult<UnbondHistory> {
+pub fn read_unbond_history(
+    storage: &dyn Storage,
+    epoc_id: u64,
+) -> Result<UnbondHistory, ContractError> {
+    may_read_unbond_history(storage, epoc_id)?
+        .ok_or(ContractError::UnbondHistoryNotFound { batch_id: epoc_id })
+}
+
+pub fn may_read_unbond_history(
+    storage: &dyn Storage,
+    epoc_id: u64,
+) -> StdResult<Option<UnbondHistory>> {
     let vec = epoc_id.to_be_bytes().to_vec();
-    let res = ReadonlyPrefixedStorage::new(storage, UNBOND_HISTORY_MAP).get(&vec);
-    match res {
-        Some(data) => from_slice(&data),
-        None => Err(StdError::generic_err(
-            "Burn requests not found for the specified time period",
-        )),
+    ReadonlyPrefixedStorage::new(storage, UNBOND_HISTORY_MAP)
+        .get(&vec)
+        .map(|data| from_slice(&data))
+        .transpose()
+}
+
+/// Record a delegation of `amount` to `validator` sent by the hub.
+pub fn increase_expected_delegation(
+    storage: &mut dyn Storage,
+    validator: &str,
+    amount: Uint128,
+) -> StdResult<()> {
+    EXPECTED_DELEGATIONS.update(storage, validator, |delegated| -> StdResult<_> {
+        Ok(delegated.unwrap_or_default() + amount)
+    })?;
+    Ok(())
+}
+
+/// Record an undelegation of `amount` from `validator` sent by the hub.
+pub fn decrease_expected_delegation(
+    storage: &mut dyn Storage,
+    validator: &str,
+    amount: Uint128,
+) -> StdResult<()> {
+    EXPECTED_DELEGATIONS.update(storage, validator, |delegated| -> StdResult<_> {
+        Ok(delegated.unwrap_or_default().saturating_sub(amount))
+    })?;
+    Ok(())
+}
+
+/// Replace the expected delegations with the actual ones,
+/// writing only the validators whose delegation changed.
+pub fn reset_expected_delegations(
+    storage: &mut dyn Storage,
+    delegated: &BTreeMap<String, Uint128>,
+) -> StdResult<()> {
+    let expected: BTreeMap<String, Uint128> = EXPECTED_DELEGATIONS
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (validator, amount) = item?;
+            Ok((String::from_utf8(validator)?, amount))
+        })
+        .collect::<StdResult<_>>()?;
+    for validator in expected.keys() {
+        if !delegated.contains_key(validator) {
+            EXPECTED_DELEGATIONS.remove(storage, validator);
+        }
+    }
+    for (validator, amount) in delegated {
+        if expected.get(validator) != Some(amount) {
+            EXPECTED_DELEGATIONS.save(storage, validator, amount)?;
+        }
     }
+    Ok(())
+}
+
+/// Store a slashing under the next id and return the id.
+pub fn store_slashing_event(storage: &mut dyn Storage, mut event: SlashingEvent) -> StdResult<u64> {
+    let id = LAST_SLASHING_ID.may_load(storage)?.unwrap_or_default() + 1;
+    LAST_SLASHING_ID.save(storage, &id)?;
+    event.id = id;
+    SLASHING_HISTORY.save(storage, U64Key::new(id), &event)?;
+    Ok(id)
+}
+
+pub fn read_slashing_history(
+    storage: &dyn Storage,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<SlashingEvent>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|id| Bound::exclusive(U64Key::new(id)));
+    SLASHING_HISTORY
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, event)| event))
+        .collect()
+}
+
+// the capacity of the exchange rate ring buffer
+pub(crate) const MAX_RATE_CHECKPOINTS: u64 = 500;
+
+/// Record the exchange rate at `time` if it differs from the last checkpoint.
+/// A change within the same block replaces the checkpoint of the block.
+pub fn record_exchange_rate(
+    storage: &mut dyn Storage,
+    time: u64,
+    exchange_rate: Decimal,
+) -> StdResult<()> {
+    let count = RATE_CHECKPOINT_COUNT.may_load(storage)?.unwrap_or_default();
+    let mut next = count;
+    if count > 0 {
+        let last = read_rate_checkpoint(storage, count - 1)?;
+        if last.exchange_rate == exchange_rate {
+            return Ok(());
+        }
+        if last.time == time {
+            next = count - 1;
+        }
+    }
+
+    RATE_CHECKPOINTS.save(
+        storage,
+        U64Key::new(next % MAX_RATE_CHECKPOINTS),
+        &RateCheckpoint {
+            time,
+            exchange_rate,
+        },
+    )?;
+    RATE_CHECKPOINT_COUNT.save(storage, &(next + 1))
+}
+
+/// Return the range of the checkpoint numbers still held by the ring buffer.
+pub fn rate_checkpoint_range(storage: &dyn Storage) -> StdResult<(u64, u64)> {
+    let count = RATE_CHECKPOINT_COUNT.may_load(storage)?.unwrap_or_default();
+    Ok((count.saturating_sub(MAX_RATE_CHECKPOINTS), count))
+}
+
+/// Read the checkpoint number `index`, which must be held by the ring buffer.
+pub fn read_rate_checkpoint(storage: &dyn Storage, index: u64) -> StdResult<RateCheckpoint> {
+    RATE_CHECKPOINTS.load(storage, U64Key::new(index % MAX_RATE_CHECKPOINTS))
 }
 
 // settings for pagination
-const MAX_LIMIT: u32 = 100;
-const DEFAULT_LIMIT: u32 = 10;
+pub(crate) const MAX_LIMIT: u32 = 100;
+pub(crate) const DEFAULT_LIMIT: u32 = 10;
 
 /// Return all unbond_history from UnbondHistory map
 #[allow(clippy::needless_lifetimes)]
@@ -295,7 +783,7 @@ mod test {
 
     use cosmwasm_std::testing::mock_dependencies;
     use cosmwasm_std::{Api, StdResult, Storage};
-    use cosmwasm_storage::{singleton, singleton_read};
+    use cosmwasm_storage::{singleton, singleton_read, Bucket};
 
     pub static STATE_KEY: &[u8] = b"state";
     pub static CURRENT_BATCH_KEY: &[u8] = b"current_batch";
@@ -391,6 +879,36 @@ mod test {
         );
     }
 
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+    pub struct LegacyParameters {
+        pub epoch_period: u64,
+        pub underlying_coin_denom: String,
+        pub unbonding_period: u64,
+        pub peg_recovery_fee: Decimal,
+        pub er_threshold: Decimal,
+        pub reward_denom: String,
+    }
+
+    #[test]
+    fn params_without_delegation_strategy() {
+        let mut deps = mock_dependencies(&[]);
+        singleton(&mut deps.storage, PARAMETERS_KEY)
+            .save(&LegacyParameters {
+                epoch_period: 10,
+                underlying_coin_denom: "uluna".to_string(),
+                unbonding_period: 1000,
+                peg_recovery_fee: Default::default(),
+                er_threshold: Default::default(),
+                reward_denom: "uusd".to_string(),
+            })
+            .unwrap();
+
+        assert_eq!(
+            PARAMETERS.load(&deps.storage).unwrap().delegation_strategy,
+            DelegationStrategy::UserChosen
+        );
+    }
+
     #[test]
     fn params_legacy_compatibility() {
         let mut deps = mock_dependencies(&[]);
@@ -403,6 +921,9 @@ mod test {
                 peg_recovery_fee: Default::default(),
                 er_threshold: Default::default(),
                 reward_denom: "uusd".to_string(),
+                delegation_strategy: Default::default(),
+                max_rebalance_amount: None,
+                reward_mode: Default::default(),
             },
         )
         .unwrap();
@@ -412,4 +933,262 @@ mod test {
             read_params(&deps.storage).unwrap()
         );
     }
+
+    /// The wait list layout used before the migration to cw-storage-plus.
+    fn store_legacy_wait_list(
+        storage: &mut dyn Storage,
+        batch_id: u64,
+        sender_address: String,
+        amount: Uint128,
+    ) {
+        let batch = to_vec(&batch_id).unwrap();
+        let addr = to_vec(&sender_address).unwrap();
+        let mut position_indexer: Bucket<Uint128> =
+            Bucket::multilevel(storage, &[PREFIX_WAIT_MAP, &addr]);
+        position_indexer.save(&batch, &amount).unwrap();
+    }
+
+    #[test]
+    fn wait_list_migration() {
+        let mut deps = mock_dependencies(&[]);
+        store_legacy_wait_list(&mut deps.storage, 9, "bob".to_string(), Uint128::new(10));
+        store_legacy_wait_list(&mut deps.storage, 10, "bob".to_string(), Uint128::new(20));
+        store_legacy_wait_list(&mut deps.storage, 10, "alice".to_string(), Uint128::new(5));
+        store_legacy_wait_list(&mut deps.storage, 10, "carol".to_string(), Uint128::new(7));
+
+        // nothing is moved before the migration is started
+        assert_eq!(
+            migrate_unbond_wait_list(&mut deps.storage, 10).unwrap(),
+            (0, true)
+        );
+        start_wait_list_migration(&mut deps.storage).unwrap();
+
+        // the first page moves bob's request of batch 10 only, in legacy key order
+        assert_eq!(
+            migrate_unbond_wait_list(&mut deps.storage, 1).unwrap(),
+            (1, false)
+        );
+        assert_eq!(
+            unbond_wait_list()
+                .range(&deps.storage, None, None, Order::Ascending)
+                .count(),
+            1
+        );
+
+        // the reads fall back to the legacy layout in the meantime
+        assert_eq!(
+            get_unbond_requests(&deps.storage, "bob".to_string(), None, None).unwrap(),
+            vec![(9, Uint128::new(10)), (10, Uint128::new(20))]
+        );
+        assert_eq!(
+            get_unbond_requests(&deps.storage, "bob".to_string(), Some(9), None).unwrap(),
+            vec![(10, Uint128::new(20))]
+        );
+        assert_eq!(
+            read_unbond_wait_list(&deps.storage, 10, "alice".to_string()).unwrap(),
+            Uint128::new(5)
+        );
+        assert!(legacy_wait_list_pending(&deps.storage).unwrap());
+
+        // an update of a user moves all of the user's requests first
+        store_unbond_wait_list(&mut deps.storage, 10, "alice".to_string(), Uint128::new(1))
+            .unwrap();
+        assert_eq!(
+            unbond_wait_list()
+                .load(&deps.storage, ("alice", U64Key::new(10)))
+                .unwrap(),
+            Uint128::new(6)
+        );
+
+        // the next page resumes after the cursor
+        assert_eq!(
+            migrate_unbond_wait_list(&mut deps.storage, 2).unwrap(),
+            (2, false)
+        );
+        assert_eq!(
+            migrate_unbond_wait_list(&mut deps.storage, 2).unwrap(),
+            (0, true)
+        );
+        assert_eq!(
+            WAIT_LIST_MIGRATION_CURSOR.may_load(&deps.storage).unwrap(),
+            None
+        );
+
+        assert_eq!(
+            get_unbond_requests(&deps.storage, "bob".to_string(), None, None).unwrap(),
+            vec![(9, Uint128::new(10)), (10, Uint128::new(20))]
+        );
+        assert_eq!(
+            read_unbond_wait_list(&deps.storage, 10, "carol".to_string()).unwrap(),
+            Uint128::new(7)
+        );
+
+        // the legacy entries are removed
+        let legacy = ReadonlyPrefixedStorage::new(&deps.storage, PREFIX_WAIT_MAP)
+            .range(None, None, Order::Ascending)
+            .count();
+        assert_eq!(legacy, 0);
+    }
+
+    #[test]
+    fn take_finished_amount_skips_unreleased_batches() {
+        let mut deps = mock_dependencies(&[]);
+        for batch_id in 1..=3u64 {
+            store_unbond_wait_list(
+                &mut deps.storage,
+                batch_id,
+                "bob".to_string(),
+                Uint128::new(10 * batch_id as u128),
+            )
+            .unwrap();
+            let history = UnbondHistory {
+                batch_id,
+                time: batch_id,
+                amount: Uint128::new(10 * batch_id as u128),
+                applied_exchange_rate: Decimal::one(),
+                withdraw_rate: Decimal::one(),
+                released: batch_id != 2,
+            };
+            store_unbond_history(&mut deps.storage, batch_id, history).unwrap();
+        }
+
+        // the second batch is not released, the third one still is paid
+        assert_eq!(
+            take_finished_amount(&mut deps.storage, "bob".to_string(), None).unwrap(),
+            Uint128::new(40)
+        );
+        assert_eq!(
+            get_unbond_requests(&deps.storage, "bob".to_string(), None, None).unwrap(),
+            vec![(2, Uint128::new(20))]
+        );
+
+        // the same goes when the batches are given
+        store_unbond_wait_list(&mut deps.storage, 3, "bob".to_string(), Uint128::new(5)).unwrap();
+        assert_eq!(
+            take_finished_amount(&mut deps.storage, "bob".to_string(), Some(vec![2, 3])).unwrap(),
+            Uint128::new(5)
+        );
+        assert_eq!(
+            get_unbond_requests(&deps.storage, "bob".to_string(), None, None).unwrap(),
+            vec![(2, Uint128::new(20))]
+        );
+    }
+
+    #[test]
+    fn wait_list_indexes() {
+        let mut deps = mock_dependencies(&[]);
+        for batch_id in 1..=3u64 {
+            for user in ["alice", "bob", "carol"] {
+                store_unbond_wait_list(
+                    &mut deps.storage,
+                    batch_id,
+                    user.to_string(),
+                    Uint128::new(10 * batch_id as u128),
+                )
+                .unwrap();
+            }
+        }
+
+        // user requests are paginated by batch id
+        assert_eq!(
+            get_unbond_requests(&deps.storage, "bob".to_string(), Some(1), Some(1)).unwrap(),
+            vec![(2, Uint128::new(20))]
+        );
+
+        // requests of a batch are paginated in storage order, shorter addresses first
+        assert_eq!(
+            get_batch_unbond_requests(&deps.storage, 2, None, Some(2)).unwrap(),
+            vec![
+                ("bob".to_string(), Uint128::new(20)),
+                ("alice".to_string(), Uint128::new(20))
+            ]
+        );
+        assert_eq!(
+            get_batch_unbond_requests(&deps.storage, 2, Some("alice".to_string()), None).unwrap(),
+            vec![("carol".to_string(), Uint128::new(20))]
+        );
+
+        // the first two batches are released
+        for batch_id in 1..=2u64 {
+            let history = UnbondHistory {
+                batch_id,
+                time: batch_id,
+                amount: Uint128::new(30 * batch_id as u128),
+                applied_exchange_rate: Decimal::one(),
+                withdraw_rate: Decimal::percent(50),
+                released: true,
+            };
+            store_unbond_history(&mut deps.storage, batch_id, history).unwrap();
+        }
+
+        assert_eq!(
+            query_get_finished_amount(&deps.storage, "bob".to_string(), 2).unwrap(),
+            Uint128::new(5)
+        );
+        assert_eq!(
+            take_finished_amount(&mut deps.storage, "bob".to_string(), None).unwrap(),
+            Uint128::new(15)
+        );
+        assert_eq!(
+            get_unbond_requests(&deps.storage, "bob".to_string(), None, None).unwrap(),
+            vec![(3, Uint128::new(30))]
+        );
+
+        // the batch index follows the removal
+        assert_eq!(
+            get_batch_unbond_requests(&deps.storage, 1, None, None).unwrap(),
+            vec![
+                ("alice".to_string(), Uint128::new(10)),
+                ("carol".to_string(), Uint128::new(10))
+            ]
+        );
+
+        decrease_unbond_wait_list(&mut deps.storage, 3, "carol".to_string(), Uint128::new(30))
+            .unwrap();
+        assert_eq!(
+            get_batch_unbond_requests(&deps.storage, 3, None, None).unwrap(),
+            vec![
+                ("bob".to_string(), Uint128::new(30)),
+                ("alice".to_string(), Uint128::new(30))
+            ]
+        );
+    }
+
+    #[test]
+    fn rate_checkpoint_ring_buffer() {
+        let mut deps = mock_dependencies(&[]);
+        let rate = |i: u64| Decimal::from_ratio(1000 + i, 1000u64);
+
+        // unchanged rates are not recorded and a block keeps its last rate
+        record_exchange_rate(&mut deps.storage, 10, Decimal::one()).unwrap();
+        record_exchange_rate(&mut deps.storage, 20, Decimal::one()).unwrap();
+        record_exchange_rate(&mut deps.storage, 30, rate(1)).unwrap();
+        record_exchange_rate(&mut deps.storage, 30, rate(2)).unwrap();
+        assert_eq!(rate_checkpoint_range(&deps.storage).unwrap(), (0, 2));
+        assert_eq!(
+            read_rate_checkpoint(&deps.storage, 1).unwrap(),
+            RateCheckpoint {
+                time: 30,
+                exchange_rate: rate(2)
+            }
+        );
+
+        // the oldest checkpoints are overwritten once the buffer is full
+        for i in 4..MAX_RATE_CHECKPOINTS + 5 {
+            record_exchange_rate(&mut deps.storage, 10 * i, rate(i)).unwrap();
+        }
+        let (oldest, count) = rate_checkpoint_range(&deps.storage).unwrap();
+        assert_eq!(count, MAX_RATE_CHECKPOINTS + 3);
+        assert_eq!(oldest, 3);
+        assert_eq!(
+            read_rate_checkpoint(&deps.storage, oldest).unwrap().time,
+            50
+        );
+        assert_eq!(
+            read_rate_checkpoint(&deps.storage, count - 1)
+                .unwrap()
+                .exchange_rate,
+            rate(MAX_RATE_CHECKPOINTS + 4)
+        );
+    }
 }
@@ -0,0 +1,132 @@
+use crate::math::proportional_split;
+use crate::state::{read_valid_validators, read_validator_weights, PARAMETERS};
+use basset::contract_error::ContractError;
+use basset::hub::DelegationStrategy;
+use cosmwasm_std::{Addr, Deps, StdResult, Uint128};
+use std::collections::BTreeMap;
+
+/// Pick the validator that receives a new bond of `amount`
+/// according to the configured delegation strategy.
+pub fn pick_bond_validator(
+    deps: Deps,
+    delegator: Addr,
+    amount: Uint128,
+) -> Result<String, ContractError> {
+    let params = PARAMETERS.load(deps.storage)?;
+    let delegated = query_delegated_amounts(deps, delegator, &params.underlying_coin_denom)?;
+
+    match params.delegation_strategy {
+        DelegationStrategy::UserChosen => Err(ContractError::ValidatorRequired {}),
+        DelegationStrategy::LeastDelegated => {
+            let validators = read_valid_validators(deps.storage)?;
+            validators
+                .into_iter()
+                .min_by_key(|val| delegated.get(val).copied().unwrap_or_default())
+                .ok_or(ContractError::NoWhitelistedValidator {})
+        }
+        DelegationStrategy::TargetWeights => {
+            let weights = read_validator_weights(deps.storage)?;
+            let total_weight: u64 = weights.iter().map(|w| w.weight).sum();
+            if total_weight == 0 {
+                return Err(ContractError::NoTargetWeights {});
+            }
+
+            let total_delegated: Uint128 = delegated.values().copied().sum();
+            let total_after_bond = total_delegated + amount;
+
+            // target - current is compared as target + (total - current) to stay unsigned.
+            let mut picked: Option<(String, Uint128)> = None;
+            for w in weights.into_iter().filter(|w| w.weight > 0) {
+                let current = delegated.get(&w.validator).copied().unwrap_or_default();
+                let target = total_after_bond.multiply_ratio(w.weight, total_weight);
+                let score = target + (total_delegated.checked_sub(current))?;
+                match &picked {
+                    Some((_, best)) if *best >= score => {}
+                    _ => picked = Some((w.validator, score)),
+                }
+            }
+            Ok(picked.expect("total weight is positive").0)
+        }
+    }
+}
+
+/// Split an undelegation of `claim` across the validators of the hub.
+/// With target weights, validators above their target after the undelegation
+/// are drained first; otherwise the claim follows the current delegation weights.
+pub fn pick_unbond_validators(
+    deps: Deps,
+    delegator: Addr,
+    claim: Uint128,
+) -> Result<Vec<(String, Uint128)>, ContractError> {
+    let params = PARAMETERS.load(deps.storage)?;
+    let delegated = query_delegated_amounts(deps, delegator, &params.underlying_coin_denom)?;
+
+    let total_delegated: Uint128 = delegated.values().copied().sum();
+    if claim > total_delegated {
+        return Err(ContractError::UndelegationExceedsDelegation {
+            amount: claim,
+            delegated: total_delegated,
+        });
+    }
+
+    let validators: Vec<String> = delegated.keys().cloned().collect();
+    let amounts: Vec<Uint128> = delegated.values().copied().collect();
+
+    let weights = read_validator_weights(deps.storage)?;
+    let total_weight: u64 = weights.iter().map(|w| w.weight).sum();
+
+    let shares =
+        if params.delegation_strategy == DelegationStrategy::TargetWeights && total_weight > 0 {
+            let remaining = total_delegated.checked_sub(claim)?;
+            let excess: Vec<Uint128> = validators
+                .iter()
+                .zip(amounts.iter())
+                .map(|(val, amount)| {
+                    let weight = weights
+                        .iter()
+                        .find(|w| &w.validator == val)
+                        .map(|w| w.weight)
+                        .unwrap_or_default();
+                    let target = remaining.multiply_ratio(weight, total_weight);
+                    amount.saturating_sub(target)
+                })
+                .collect();
+            let total_excess: Uint128 = excess.iter().sum();
+
+            if total_excess >= claim {
+                proportional_split(claim, &excess)?
+            } else {
+                // take all the excess, then the rest proportionally to what is left
+                let left: Vec<Uint128> = amounts
+                    .iter()
+                    .zip(excess.iter())
+                    .map(|(amount, ex)| amount.checked_sub(*ex))
+                    .collect::<Result<_, _>>()?;
+                let rest = proportional_split(claim.checked_sub(total_excess)?, &left)?;
+                excess.iter().zip(rest).map(|(ex, r)| *ex + r).collect()
+            }
+        } else {
+            proportional_split(claim, &amounts)?
+        };
+
+    Ok(validators
+        .into_iter()
+        .zip(shares)
+        .filter(|(_, share)| !share.is_zero())
+        .collect())
+}
+
+/// Return the amount the hub has delegated to each validator in the given denom.
+pub(crate) fn query_delegated_amounts(
+    deps: Deps,
+    delegator: Addr,
+    coin_denom: &str,
+) -> StdResult<BTreeMap<String, Uint128>> {
+    let mut delegated: BTreeMap<String, Uint128> = BTreeMap::new();
+    for delegation in deps.querier.query_all_delegations(delegator)? {
+        if delegation.amount.denom == coin_denom {
+            *delegated.entry(delegation.validator).or_default() += delegation.amount.amount;
+        }
+    }
+    Ok(delegated)
+}
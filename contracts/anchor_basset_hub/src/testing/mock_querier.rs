@@ -1,4 +1,5 @@
 use basset::hub::Config;
+use basset::reward::{AccruedRewardsResponse, QueryMsg as RewardQueryMsg};
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage};
 use cosmwasm_std::{
     from_binary, from_slice, to_binary, AllBalanceResponse, Api, BalanceResponse, BankQuery,
@@ -10,9 +11,12 @@ use cw20_legacy::state::{MinterData, TokenInfo};
 use std::collections::HashMap;
 
 use cw20::{BalanceResponse as Cw20BalanceResponse, Cw20QueryMsg};
+use cw721::{Cw721QueryMsg, OwnerOfResponse, TokensResponse};
 use terra_cosmwasm::{TaxCapResponse, TaxRateResponse, TerraQuery, TerraQueryWrapper, TerraRoute};
 
 pub const MOCK_CONTRACT_ADDR: &str = "cosmos2contract";
+pub const MOCK_TICKET_CONTRACT_ADDR: &str = "unbond_ticket";
+pub const MOCK_REWARD_CONTRACT_ADDR: &str = "reward";
 
 pub fn mock_dependencies(
     contract_balance: &[Coin],
@@ -56,6 +60,8 @@ pub struct WasmMockQuerier {
     token_querier: TokenQuerier,
     balance_querier: BalanceQuerier,
     tax_querier: TaxQuerier,
+    ticket_owners: HashMap<String, String>,
+    accrued_rewards: HashMap<String, AccruedRewardsResponse>,
 }
 
 impl Querier for WasmMockQuerier {
@@ -209,6 +215,55 @@ impl WasmMockQuerier {
                     unimplemented!()
                 }
             }
+            QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg })
+                if contract_addr == MOCK_TICKET_CONTRACT_ADDR =>
+            {
+                match from_binary(msg).unwrap() {
+                    Cw721QueryMsg::OwnerOf { token_id, .. } => {
+                        match self.ticket_owners.get(&token_id) {
+                            Some(owner) => SystemResult::Ok(ContractResult::Ok(
+                                to_binary(&OwnerOfResponse {
+                                    owner: owner.clone(),
+                                    approvals: vec![],
+                                })
+                                .unwrap(),
+                            )),
+                            None => SystemResult::Ok(ContractResult::Err(
+                                "cw721_base::state::TokenInfo not found".to_string(),
+                            )),
+                        }
+                    }
+                    Cw721QueryMsg::Tokens { owner, .. } => {
+                        let mut tokens: Vec<String> = self
+                            .ticket_owners
+                            .iter()
+                            .filter(|(_, holder)| **holder == owner)
+                            .map(|(token_id, _)| token_id.clone())
+                            .collect();
+                        tokens.sort();
+                        SystemResult::Ok(ContractResult::Ok(
+                            to_binary(&TokensResponse { tokens }).unwrap(),
+                        ))
+                    }
+                    _ => panic!("DO NOT ENTER HERE"),
+                }
+            }
+            QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg })
+                if contract_addr == MOCK_REWARD_CONTRACT_ADDR =>
+            {
+                match from_binary(msg).unwrap() {
+                    RewardQueryMsg::AccruedRewards { address } => {
+                        let rewards = self.accrued_rewards.get(&address).cloned().unwrap_or(
+                            AccruedRewardsResponse {
+                                rewards: Uint128::zero(),
+                                asset_rewards: vec![],
+                            },
+                        );
+                        SystemResult::Ok(ContractResult::Ok(to_binary(&rewards).unwrap()))
+                    }
+                    _ => panic!("DO NOT ENTER HERE"),
+                }
+            }
             QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
                 match from_binary(msg).unwrap() {
                     Cw20QueryMsg::TokenInfo {} => {
@@ -350,9 +405,27 @@ impl WasmMockQuerier {
             token_querier: TokenQuerier::default(),
             tax_querier: TaxQuerier::default(),
             balance_querier: BalanceQuerier::default(),
+            ticket_owners: HashMap::new(),
+            accrued_rewards: HashMap::new(),
         }
     }
 
+    // configure the holders of the unbond tickets
+    pub fn with_ticket_owners(&mut self, owners: &[(&str, &str)]) {
+        self.ticket_owners = owners
+            .iter()
+            .map(|(token_id, owner)| (token_id.to_string(), owner.to_string()))
+            .collect();
+    }
+
+    // configure the rewards accrued by the holders in the reward contract
+    pub fn with_accrued_rewards(&mut self, rewards: &[(&str, AccruedRewardsResponse)]) {
+        self.accrued_rewards = rewards
+            .iter()
+            .map(|(holder, rewards)| (holder.to_string(), rewards.clone()))
+            .collect();
+    }
+
     pub fn with_native_balances(&mut self, balances: &[(String, Coin)]) {
         self.balance_querier = BalanceQuerier::new(balances);
     }
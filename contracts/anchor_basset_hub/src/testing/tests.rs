@@ -5,48 +5,74 @@
 //! You can easily convert unit tests to integration tests as follows:
 //! 1. Copy them over verbatim
 //! 2. Then change
-//!      let mut deps = mock_dependencies(20, &[]);
+//!    ```text
+//!    let mut deps = mock_dependencies(20, &[]);
+//!    ```
 //!    to
-//!      let mut deps = mock_instance(WASM, &[]);
+//!    ```text
+//!    let mut deps = mock_instance(WASM, &[]);
+//!    ```
 //! 3. If you access raw storage, where ever you see something like:
-//!      deps.storage.get(CONFIG_KEY).expect("no data stored");
+//!    ```text
+//!    deps.storage.get(CONFIG_KEY).expect("no data stored");
+//!    ```
 //!    replace it with:
-//!      deps.with_storage(|store| {
-//!          let data = store.get(CONFIG_KEY).expect("no data stored");
-//!          //...
-//!      });
+//!    ```text
+//!    deps.with_storage(|store| {
+//!        let data = store.get(CONFIG_KEY).expect("no data stored");
+//!        //...
+//!    });
+//!    ```
 //! 4. Anywhere you see query(deps.as_ref(), ...) you must replace it with query(&mut deps, ...)
 use cosmwasm_std::{
-    coin, from_binary, to_binary, Addr, Api, BankMsg, Coin, CosmosMsg, Decimal, DepsMut,
-    DistributionMsg, Env, FullDelegation, MessageInfo, OwnedDeps, Querier, Response, StakingMsg,
-    StdError, Storage, SubMsg, Uint128, Validator, WasmMsg,
+    attr, coin, from_binary, to_binary, to_vec, Addr, Api, BankMsg, Coin, CosmosMsg, Decimal, Deps,
+    DepsMut, DistributionMsg, Env, Event, FullDelegation, MessageInfo, OwnedDeps, Querier,
+    Response, StakingMsg, Storage, SubMsg, Uint128, Validator, WasmMsg,
 };
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_std::testing::{mock_env, mock_info};
 
-use crate::contract::{execute, instantiate, query};
+use crate::contract::{execute, instantiate, migrate, query};
 use crate::unbond::execute_unbond;
+use basset::contract_error::ContractError;
 use basset::hub::QueryMsg;
 use basset::hub::{
-    AllHistoryResponse, ConfigResponse, CurrentBatchResponse, ExecuteMsg, InstantiateMsg,
-    StateResponse, UnbondRequestsResponse, WhitelistedValidatorsResponse,
+    AllHistoryResponse, BatchUnbondRequestsResponse, ConfigResponse, CurrentBatchResponse,
+    Cw20HookMsg, DelegationStrategy, ExchangeRateAtResponse, ExchangeRateTwapResponse, ExecuteMsg,
+    InstantiateMsg, LiquidityBufferResponse, MigrateMsg, PausableOperation, PauseInfoResponse,
+    PositionResponse, PositionUnbondRequest, RewardMode, SimulateBondResponse,
+    SimulateUnbondResponse, SlashingEvent, SlashingHistoryResponse, StateResponse, UnbondBatch,
+    UnbondBatchStatus, UnbondBatchesResponse, UnbondRequestsResponse, UnbondTicketResponse,
+    ValidatorWeight, ValidatorWeightsResponse, WhitelistedValidatorsResponse,
     WithdrawableUnbondedResponse,
 };
 
 use basset::hub::Cw20HookMsg::Unbond;
 use basset::hub::ExecuteMsg::{CheckSlashing, Receive, UpdateConfig, UpdateParams};
+use cosmwasm_storage::Bucket;
+use cw2::{get_contract_version, set_contract_version};
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
 use cw20_legacy::msg::ExecuteMsg::{Burn, Mint};
 
-use super::mock_querier::{mock_dependencies as dependencies, WasmMockQuerier};
+use super::mock_querier::{
+    mock_dependencies as dependencies, WasmMockQuerier, MOCK_REWARD_CONTRACT_ADDR,
+    MOCK_TICKET_CONTRACT_ADDR,
+};
 use crate::math::decimal_division;
-use crate::state::{read_unbond_wait_list, Parameters, CONFIG};
+use crate::state::{
+    read_unbond_history, read_unbond_wait_list, Parameters, CONFIG, EXPECTED_DELEGATIONS,
+};
 use basset::airdrop::ExecuteMsg::{FabricateANCClaim, FabricateMIRClaim};
 use basset::airdrop::PairHandleMsg;
 use basset::hub::QueryMsg::{AllHistory, UnbondRequests, WithdrawableUnbonded};
-use basset::reward::ExecuteMsg::{SwapToRewardDenom, UpdateGlobalIndex};
+use basset::reward::ExecuteMsg::{
+    SendUnderlying, SwapToRewardDenom, SwapToUnderlying, UpdateGlobalIndex,
+    UpdateRewardAssetIndexes,
+};
+use basset::reward::{AccruedRewardsResponse, RewardAsset, RewardAssetAmount};
+use basset::unbond_ticket::ExecuteMsg as UnbondTicketMsg;
 use std::borrow::BorrowMut;
 
 const DEFAULT_VALIDATOR: &str = "default-validator";
@@ -84,6 +110,24 @@ pub fn init<S: Storage, A: Api, Q: Querier>(
     reward_contract: String,
     token_contract: String,
     validator: String,
+) {
+    init_with_mode(
+        deps,
+        owner,
+        reward_contract,
+        token_contract,
+        validator,
+        RewardMode::Distribute,
+    )
+}
+
+pub fn init_with_mode<S: Storage, A: Api, Q: Querier>(
+    deps: &mut OwnedDeps<S, A, Q>,
+    owner: String,
+    reward_contract: String,
+    token_contract: String,
+    validator: String,
+    reward_mode: RewardMode,
 ) {
     let msg = InstantiateMsg {
         epoch_period: 30,
@@ -93,13 +137,13 @@ pub fn init<S: Storage, A: Api, Q: Querier>(
         er_threshold: Decimal::one(),
         reward_denom: "uusd".to_string(),
         validator,
+        reward_mode,
     };
 
     let owner_info = mock_info(owner.as_str(), &[coin(1000000, "uluna")]);
     instantiate(deps.as_mut(), mock_env(), owner_info.clone(), msg).unwrap();
 
     let register_msg = ExecuteMsg::UpdateConfig {
-        owner: None,
         reward_contract: Some(reward_contract),
         token_contract: Some(token_contract),
         airdrop_registry_contract: Some("airdrop_registry".to_string()),
@@ -123,7 +167,7 @@ pub fn do_register_validator(deps: DepsMut, validator: Validator) {
 
 pub fn do_bond(deps: DepsMut, addr: String, amount: Uint128, validator: Validator) {
     let bond = ExecuteMsg::Bond {
-        validator: validator.address,
+        validator: Some(validator.address),
     };
 
     let info = mock_info(&addr, &[coin(amount.u128(), "uluna")]);
@@ -167,6 +211,7 @@ fn proper_initialization() {
         er_threshold: Decimal::one(),
         reward_denom: "uusd".to_string(),
         validator: validator.address.clone(),
+        reward_mode: RewardMode::Distribute,
     };
 
     let _owner = "owner1";
@@ -226,9 +271,11 @@ fn proper_initialization() {
         from_binary(&query(deps.as_ref(), mock_env(), conf).unwrap()).unwrap();
     let expected_conf = ConfigResponse {
         owner: "owner1".to_string(),
+        pending_owner: None,
         reward_contract: None,
         token_contract: None,
         airdrop_registry_contract: None,
+        unbond_ticket_contract: None,
     };
 
     assert_eq!(expected_conf, query_conf);
@@ -277,7 +324,7 @@ fn proper_register_validator() {
 
     // invalid requests
     let res = execute(deps.as_mut(), mock_env(), owner_info, msg);
-    assert_eq!(res.unwrap_err(), StdError::generic_err("unauthorized"));
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
 
     //invalid validator
 
@@ -289,7 +336,9 @@ fn proper_register_validator() {
     let res = execute(deps.as_mut(), mock_env(), owner_info, msg);
     assert_eq!(
         res.unwrap_err(),
-        StdError::generic_err("The specified address is not a validator")
+        ContractError::NotAValidator {
+            address: "fake validator".to_string()
+        }
     );
 
     // successful call
@@ -305,7 +354,7 @@ fn proper_register_validator() {
     let query_validatator = QueryMsg::WhitelistedValidators {};
     let query_res: WhitelistedValidatorsResponse =
         from_binary(&query(deps.as_ref(), mock_env(), query_validatator).unwrap()).unwrap();
-    assert_eq!(query_res.validators.get(0).unwrap(), &validator.address);
+    assert_eq!(query_res.validators.first().unwrap(), &validator.address);
 
     // register another validator
     let msg = ExecuteMsg::RegisterValidator {
@@ -320,7 +369,7 @@ fn proper_register_validator() {
     let query_res: WhitelistedValidatorsResponse =
         from_binary(&query(deps.as_ref(), mock_env(), query_validatator2).unwrap()).unwrap();
     assert_eq!(query_res.validators.get(1).unwrap(), &validator2.address);
-    assert_eq!(query_res.validators.get(0).unwrap(), &validator.address);
+    assert_eq!(query_res.validators.first().unwrap(), &validator.address);
 }
 
 /// Covers if delegate message is sent to the specified validator,
@@ -369,7 +418,7 @@ fn proper_bond() {
     do_register_validator(deps.as_mut(), validator.clone());
 
     let bond_msg = ExecuteMsg::Bond {
-        validator: validator.address,
+        validator: Some(validator.address),
     };
 
     let info = mock_info(addr1.as_str(), &[coin(bond_amount.u128(), "uluna")]);
@@ -424,42 +473,48 @@ fn proper_bond() {
     let invalid_validator = "invalid";
     let bob = "bob".to_string();
     let bond = ExecuteMsg::Bond {
-        validator: invalid_validator.to_string(),
+        validator: Some(invalid_validator.to_string()),
     };
 
     let info = mock_info(&bob, &[coin(10, "uluna")]);
     let res = execute(deps.as_mut(), mock_env(), info, bond);
     assert_eq!(
         res.unwrap_err(),
-        StdError::generic_err("The chosen validator is currently not supported")
+        ContractError::ValidatorNotWhitelisted {
+            validator: "invalid".to_string()
+        }
     );
 
     // no-send funds
     let validator = sample_validator(DEFAULT_VALIDATOR.to_string());
     let bob = "bob".to_string();
     let failed_bond = ExecuteMsg::Bond {
-        validator: validator.address,
+        validator: Some(validator.address),
     };
 
     let info = mock_info(&bob, &[]);
     let res = execute(deps.as_mut(), mock_env(), info, failed_bond);
     assert_eq!(
         res.unwrap_err(),
-        StdError::generic_err("No uluna assets are provided to bond")
+        ContractError::NoAssetsProvided {
+            denom: "uluna".to_string()
+        }
     );
 
     //send other tokens than luna funds
     let validator = sample_validator(DEFAULT_VALIDATOR.to_string());
     let bob = "bob".to_string();
     let failed_bond = ExecuteMsg::Bond {
-        validator: validator.address,
+        validator: Some(validator.address),
     };
 
     let info = mock_info(&bob, &[coin(10, "ukrt")]);
     let res = execute(deps.as_mut(), mock_env(), info, failed_bond.clone());
     assert_eq!(
         res.unwrap_err(),
-        StdError::generic_err("No uluna assets are provided to bond")
+        ContractError::NoAssetsProvided {
+            denom: "uluna".to_string()
+        }
     );
 
     //bond with more than one coin is not possible
@@ -472,10 +527,222 @@ fn proper_bond() {
     );
 
     let res = execute(deps.as_mut(), mock_env(), info, failed_bond).unwrap_err();
+    assert_eq!(res, ContractError::MultipleAssets {});
+}
+
+/// Covers if the delegation strategy picks the validator of a bond
+/// that does not name one, and if the target weights are managed by the owner.
+#[test]
+fn proper_bond_with_delegation_strategy() {
+    let mut deps = dependencies(&[]);
+
+    let validator = sample_validator(DEFAULT_VALIDATOR.to_string());
+    let validator2 = sample_validator(DEFAULT_VALIDATOR2.to_string());
+    let validator3 = sample_validator(DEFAULT_VALIDATOR3.to_string());
+    set_validator_mock(&mut deps.querier);
+
+    let addr1 = "addr1000".to_string();
+    let owner = "owner1".to_string();
+    let token_contract = "token".to_string();
+    let reward_contract = "reward".to_string();
+
+    init(
+        deps.borrow_mut(),
+        owner.clone(),
+        reward_contract,
+        token_contract,
+        validator.address.clone(),
+    );
+
+    do_register_validator(deps.as_mut(), validator.clone());
+    do_register_validator(deps.as_mut(), validator2.clone());
+    do_register_validator(deps.as_mut(), validator3.clone());
+
+    let delegations: [FullDelegation; 3] = [
+        (sample_delegation(validator.address.clone(), coin(300, "uluna"))),
+        (sample_delegation(validator2.address.clone(), coin(100, "uluna"))),
+        (sample_delegation(validator3.address.clone(), coin(200, "uluna"))),
+    ];
+    let validators: [Validator; 3] = [
+        (validator.clone()),
+        (validator2.clone()),
+        (validator3.clone()),
+    ];
+    set_delegation_query(&mut deps.querier, &delegations, &validators);
+    deps.querier
+        .with_token_balances(&[(&"token".to_string(), &[(&addr1, &Uint128::new(600))])]);
+
+    let bond_msg = ExecuteMsg::Bond { validator: None };
+    let info = mock_info(&addr1, &[coin(200, "uluna")]);
+
+    // the default strategy requires the user to choose
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), bond_msg.clone());
+    assert_eq!(res.unwrap_err(), ContractError::ValidatorRequired {});
+
+    // least delegated validator
+    let owner_info = mock_info(&owner, &[]);
+    let update_params = UpdateParams {
+        epoch_period: None,
+        unbonding_period: None,
+        peg_recovery_fee: None,
+        er_threshold: None,
+        delegation_strategy: Some(DelegationStrategy::LeastDelegated),
+    };
+    execute(deps.as_mut(), mock_env(), owner_info.clone(), update_params).unwrap();
+
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), bond_msg.clone()).unwrap();
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Staking(StakingMsg::Delegate {
+            validator: validator2.address.clone(),
+            amount: coin(200, "uluna"),
+        }))
+    );
+
+    // target weights can not be used before they are configured
+    let update_params = UpdateParams {
+        epoch_period: None,
+        unbonding_period: None,
+        peg_recovery_fee: None,
+        er_threshold: None,
+        delegation_strategy: Some(DelegationStrategy::TargetWeights),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        owner_info.clone(),
+        update_params.clone(),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::NoTargetWeights {});
+
+    // only the owner can set the weights
+    let weights = vec![
+        ValidatorWeight {
+            validator: validator.address.clone(),
+            weight: 1,
+        },
+        ValidatorWeight {
+            validator: validator2.address.clone(),
+            weight: 1,
+        },
+        ValidatorWeight {
+            validator: validator3.address.clone(),
+            weight: 2,
+        },
+    ];
+    let update_weights = ExecuteMsg::UpdateValidatorWeights {
+        weights: weights.clone(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("invalid", &[]),
+        update_weights.clone(),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+
+    // weights are only accepted for whitelisted validators
+    let invalid_weights = ExecuteMsg::UpdateValidatorWeights {
+        weights: vec![ValidatorWeight {
+            validator: "invalid".to_string(),
+            weight: 1,
+        }],
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        owner_info.clone(),
+        invalid_weights,
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::ValidatorNotWhitelisted {
+            validator: "invalid".to_string()
+        }
+    );
+
+    let zero_weights = ExecuteMsg::UpdateValidatorWeights {
+        weights: vec![ValidatorWeight {
+            validator: validator.address.clone(),
+            weight: 0,
+        }],
+    };
+    let res = execute(deps.as_mut(), mock_env(), owner_info.clone(), zero_weights);
+    assert_eq!(res.unwrap_err(), ContractError::ZeroTotalWeight {});
+
+    // a validator can only be given one weight
+    let duplicate_weights = ExecuteMsg::UpdateValidatorWeights {
+        weights: vec![
+            ValidatorWeight {
+                validator: validator.address.clone(),
+                weight: 1,
+            },
+            ValidatorWeight {
+                validator: validator.address.clone(),
+                weight: 2,
+            },
+        ],
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        owner_info.clone(),
+        duplicate_weights,
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::DuplicateValidatorWeight {
+            validator: validator.address.clone()
+        }
+    );
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        owner_info.clone(),
+        update_weights,
+    )
+    .unwrap();
+    execute(deps.as_mut(), mock_env(), owner_info.clone(), update_params).unwrap();
+
+    let query_weights: ValidatorWeightsResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::ValidatorWeights {}).unwrap())
+            .unwrap();
+    assert_eq!(query_weights.weights, weights);
+
+    // targets after bond are 200, 200, 400 so validator3 is the furthest below
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), bond_msg).unwrap();
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Staking(StakingMsg::Delegate {
+            validator: validator3.address.clone(),
+            amount: coin(200, "uluna"),
+        }))
+    );
+
+    // an explicitly chosen validator is always respected
+    let bond_msg = ExecuteMsg::Bond {
+        validator: Some(validator.address.clone()),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap();
     assert_eq!(
-        res,
-        StdError::generic_err("More than one coin is sent; only one asset is supported")
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Staking(StakingMsg::Delegate {
+            validator: validator.address.clone(),
+            amount: coin(200, "uluna"),
+        }))
     );
+
+    // deregistering a validator removes its weight
+    let msg = ExecuteMsg::DeregisterValidator {
+        validator: validator3.address,
+    };
+    execute(deps.as_mut(), mock_env(), owner_info, msg).unwrap();
+
+    let query_weights: ValidatorWeightsResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::ValidatorWeights {}).unwrap())
+            .unwrap();
+    assert_eq!(query_weights.weights, weights[..2].to_vec());
 }
 
 /// Covers if the Redelegate message and UpdateGlobalIndex are sent.
@@ -533,7 +800,7 @@ fn proper_deregister() {
 
     let invalid_info = mock_info("invalid", &[]);
     let res = execute(deps.as_mut(), mock_env(), invalid_info, msg);
-    assert_eq!(res.unwrap_err(), StdError::generic_err("unauthorized"));
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
 
     let msg = ExecuteMsg::DeregisterValidator {
         validator: validator.address.clone(),
@@ -579,7 +846,7 @@ fn proper_deregister() {
     let query_validator = QueryMsg::WhitelistedValidators {};
     let query_res: WhitelistedValidatorsResponse =
         from_binary(&query(deps.as_ref(), mock_env(), query_validator).unwrap()).unwrap();
-    assert_eq!(query_res.validators.get(0).unwrap(), &validator2.address);
+    assert_eq!(query_res.validators.first().unwrap(), &validator2.address);
     assert!(!query_res.validators.contains(&validator.address));
 
     // fails if there is only one validator
@@ -589,10 +856,7 @@ fn proper_deregister() {
 
     let owner_info = mock_info(owner.as_str(), &[]);
     let res = execute(deps.as_mut(), mock_env(), owner_info, msg).unwrap_err();
-    assert_eq!(
-        res,
-        StdError::generic_err("Cannot remove the last whitelisted validator")
-    );
+    assert_eq!(res, ContractError::LastValidator {});
 }
 
 /// Covers if Withdraw message, swap message, and update global index are sent.
@@ -914,7 +1178,7 @@ pub fn proper_receive() {
 
     let invalid_info = mock_info(&invalid, &[]);
     let res = execute(deps.as_mut(), mock_env(), invalid_info, receive);
-    assert_eq!(res.unwrap_err(), StdError::generic_err("unauthorized"));
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
 
     // successful call
     let successful_unbond = Unbond {};
@@ -974,7 +1238,7 @@ pub fn proper_unbond() {
 
     let bob = "bob".to_string();
     let bond = ExecuteMsg::Bond {
-        validator: validator.address.clone(),
+        validator: Some(validator.address.clone()),
     };
 
     let info = mock_info(&bob, &[coin(10, "uluna")]);
@@ -1140,6 +1404,8 @@ pub fn proper_unbond() {
     // the last request (2) gets combined and processed with the previous requests (1, 5)
     let waitlist = QueryMsg::UnbondRequests {
         address: "bob".to_string(),
+        start_from: None,
+        limit: None,
     };
     let query_unbond: UnbondRequestsResponse =
         from_binary(&query(deps.as_ref(), mock_env(), waitlist).unwrap()).unwrap();
@@ -1158,8 +1424,8 @@ pub fn proper_unbond() {
     assert_eq!(res.history[0].batch_id, 1);
 }
 
-/// Covers if the pick_validator function sends different Undelegate messages
-/// to different validators, when a validator does not have enough delegation.
+/// Covers if the pick_validator function spreads the Undelegate messages
+/// over the validators proportionally to their delegations.
 #[test]
 pub fn proper_pick_validator() {
     let mut deps = dependencies(&[]);
@@ -1276,56 +1542,30 @@ pub fn proper_pick_validator() {
         ],
     )]);
 
-    //check if the undelegate message is send two more than one validator.
-    if res.messages.len() > 2 {
-        match &res.messages[0].msg {
-            CosmosMsg::Staking(StakingMsg::Undelegate {
-                validator: val,
-                amount,
-            }) => {
-                if val == &validator.address {
-                    assert_eq!(amount.amount, Uint128::new(10))
-                }
-                if val == &validator2.address {
-                    assert_eq!(amount.amount, Uint128::new(150))
-                }
-                if val == &validator3.address {
-                    assert_eq!(amount.amount, Uint128::new(150))
-                }
-            }
-            _ => panic!("Unexpected message: {:?}", &res.messages[1]),
-        }
-
-        match &res.messages[1].msg {
-            CosmosMsg::Staking(StakingMsg::Undelegate {
-                validator: val,
-                amount,
-            }) => {
-                if val == &validator2.address {
-                    assert_eq!(amount.amount, Uint128::new(140))
-                }
-                if val == &validator3.address {
-                    assert_eq!(amount.amount, Uint128::new(140))
-                }
-            }
-            _ => panic!("Unexpected message: {:?}", &res.messages[2]),
-        }
-    } else {
-        match &res.messages[1].msg {
-            CosmosMsg::Staking(StakingMsg::Undelegate {
-                validator: val,
-                amount,
-            }) => {
-                if val == &validator2.address {
-                    assert_eq!(amount.amount, Uint128::new(150))
-                }
-                if val == &validator3.address {
-                    assert_eq!(amount.amount, Uint128::new(150))
-                }
-            }
-            _ => panic!("Unexpected message: {:?}", &res.messages[1]),
-        }
-    }
+    // the claim of 150 is spread proportionally to the delegations (10, 300, 200)
+    // and the rounding leftover goes to the largest remainders.
+    assert_eq!(res.messages.len(), 4);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Staking(StakingMsg::Undelegate {
+            validator: validator.address,
+            amount: coin(3, "uluna"),
+        }))
+    );
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(CosmosMsg::Staking(StakingMsg::Undelegate {
+            validator: validator2.address,
+            amount: coin(88, "uluna"),
+        }))
+    );
+    assert_eq!(
+        res.messages[2],
+        SubMsg::new(CosmosMsg::Staking(StakingMsg::Undelegate {
+            validator: validator3.address,
+            amount: coin(59, "uluna"),
+        }))
+    );
 }
 
 /// Covers if the pick_validator function sends different Undelegate messages
@@ -1402,38 +1642,122 @@ pub fn proper_pick_validator_respect_distributed_delegation() {
     );
     assert_eq!(res.messages.len(), 3);
 
-    //check if the undelegate message is send two more than one validator.
-    if res.messages.len() > 2 {
-        match &res.messages[0].msg {
-            CosmosMsg::Staking(StakingMsg::Undelegate {
-                validator: val,
-                amount,
-            }) => {
-                if val == &validator.address {
-                    assert_eq!(amount.amount, Uint128::new(1000))
-                }
-                if val == &validator2.address {
-                    assert_eq!(amount.amount, Uint128::new(1500))
-                }
-            }
-            _ => panic!("Unexpected message: {:?}", &res.messages[1]),
-        }
+    // the claim of 2000 is split 2:3 like the delegations
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Staking(StakingMsg::Undelegate {
+            validator: validator.address,
+            amount: coin(800, "uluna"),
+        }))
+    );
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(CosmosMsg::Staking(StakingMsg::Undelegate {
+            validator: validator2.address,
+            amount: coin(1200, "uluna"),
+        }))
+    );
+}
 
-        match &res.messages[1].msg {
-            CosmosMsg::Staking(StakingMsg::Undelegate {
-                validator: val,
-                amount,
-            }) => {
-                if val == &validator.address {
-                    assert_eq!(amount.amount, Uint128::new(500))
-                }
-                if val == &validator2.address {
-                    assert_eq!(amount.amount, Uint128::new(1000))
-                }
-            }
-            _ => panic!("Unexpected message: {:?}", &res.messages[2]),
-        }
-    }
+/// Covers if the pick_validator function drains the validators
+/// that are above their target weight first.
+#[test]
+pub fn proper_pick_validator_respect_target_weights() {
+    let mut deps = dependencies(&[]);
+
+    let addr1 = "addr1000".to_string();
+
+    let validator = sample_validator(DEFAULT_VALIDATOR.to_string());
+    let validator2 = sample_validator(DEFAULT_VALIDATOR2.to_string());
+    let validator3 = sample_validator(DEFAULT_VALIDATOR3.to_string());
+    set_validator_mock(&mut deps.querier);
+
+    let owner = "owner1".to_string();
+    let token_contract = "token".to_string();
+    let reward_contract = "reward".to_string();
+
+    init(
+        deps.borrow_mut(),
+        owner.clone(),
+        reward_contract,
+        token_contract.clone(),
+        validator.address.clone(),
+    );
+
+    do_register_validator(deps.as_mut(), validator.clone());
+    do_register_validator(deps.as_mut(), validator2.clone());
+    do_register_validator(deps.as_mut(), validator3.clone());
+
+    let owner_info = mock_info(&owner, &[]);
+    let weights = ExecuteMsg::UpdateValidatorWeights {
+        weights: vec![
+            ValidatorWeight {
+                validator: validator.address.clone(),
+                weight: 1,
+            },
+            ValidatorWeight {
+                validator: validator2.address.clone(),
+                weight: 1,
+            },
+            ValidatorWeight {
+                validator: validator3.address.clone(),
+                weight: 1,
+            },
+        ],
+    };
+    execute(deps.as_mut(), mock_env(), owner_info.clone(), weights).unwrap();
+    let update_params = UpdateParams {
+        epoch_period: None,
+        unbonding_period: None,
+        peg_recovery_fee: None,
+        er_threshold: None,
+        delegation_strategy: Some(DelegationStrategy::TargetWeights),
+    };
+    execute(deps.as_mut(), mock_env(), owner_info, update_params).unwrap();
+
+    let delegations: [FullDelegation; 3] = [
+        (sample_delegation(validator.address.clone(), coin(300, "uluna"))),
+        (sample_delegation(validator2.address.clone(), coin(100, "uluna"))),
+        (sample_delegation(validator3.address.clone(), coin(200, "uluna"))),
+    ];
+    let validators: [Validator; 3] = [
+        (validator.clone()),
+        (validator2.clone()),
+        (validator3.clone()),
+    ];
+    set_delegation_query(&mut deps.querier, &delegations, &validators);
+    deps.querier
+        .with_token_balances(&[(&"token".to_string(), &[(&addr1, &Uint128::new(600))])]);
+
+    let token_info = mock_info(&token_contract, &[]);
+    let mut token_env = mock_env();
+    token_env.block.time = token_env.block.time.plus_seconds(40);
+
+    let res = do_unbond(
+        deps.as_mut(),
+        addr1,
+        token_env,
+        token_info,
+        Uint128::new(150),
+    );
+
+    // every validator targets 150 after the undelegation, so only the
+    // excess of validator (150) and validator3 (50) is undelegated.
+    assert_eq!(res.messages.len(), 3);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Staking(StakingMsg::Undelegate {
+            validator: validator.address,
+            amount: coin(113, "uluna"),
+        }))
+    );
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(CosmosMsg::Staking(StakingMsg::Undelegate {
+            validator: validator3.address,
+            amount: coin(37, "uluna"),
+        }))
+    );
 }
 
 /// Covers the effect of slashing of bond, unbond, and withdraw_unbonded
@@ -1487,7 +1811,7 @@ pub fn proper_slashing() {
 
     //bond again to see the update exchange rate
     let second_bond = ExecuteMsg::Bond {
-        validator: validator.address.clone(),
+        validator: Some(validator.address.clone()),
     };
 
     let info = mock_info(&addr1, &[coin(1000, "uluna")]);
@@ -1585,7 +1909,11 @@ pub fn proper_slashing() {
 
     env.block.time = env.block.time.plus_seconds(90);
     //check withdrawUnbonded message
-    let withdraw_unbond_msg = ExecuteMsg::WithdrawUnbonded {};
+    let withdraw_unbond_msg = ExecuteMsg::WithdrawUnbonded {
+        batch_ids: None,
+        recipient: None,
+        ticket_ids: None,
+    };
     let wdraw_unbonded_res = execute(deps.as_mut(), env, info, withdraw_unbond_msg).unwrap();
     assert_eq!(wdraw_unbonded_res.messages.len(), 1);
 
@@ -1632,7 +1960,7 @@ pub fn proper_withdraw_unbonded() {
 
     let bob = "bob".to_string();
     let bond_msg = ExecuteMsg::Bond {
-        validator: validator.address.clone(),
+        validator: Some(validator.address.clone()),
     };
 
     let info = mock_info(&bob, &[coin(100, "uluna")]);
@@ -1681,7 +2009,11 @@ pub fn proper_withdraw_unbonded() {
     //set the block time 30 seconds from now.
     env.block.time = env.block.time.plus_seconds(31);
 
-    let wdraw_unbonded_msg = ExecuteMsg::WithdrawUnbonded {};
+    let wdraw_unbonded_msg = ExecuteMsg::WithdrawUnbonded {
+        batch_ids: None,
+        recipient: None,
+        ticket_ids: None,
+    };
     let wdraw_unbonded_res = execute(
         deps.as_mut(),
         env.clone(),
@@ -1693,7 +2025,9 @@ pub fn proper_withdraw_unbonded() {
     assert!(wdraw_unbonded_res.is_err());
     assert_eq!(
         wdraw_unbonded_res.unwrap_err(),
-        StdError::generic_err("No withdrawable uluna assets are available yet")
+        ContractError::NothingToWithdraw {
+            denom: "uluna".to_string()
+        }
     );
 
     let res = execute_unbond(
@@ -1729,6 +2063,8 @@ pub fn proper_withdraw_unbonded() {
     //first query AllUnbondedRequests
     let all_unbonded = UnbondRequests {
         address: bob.clone(),
+        start_from: None,
+        limit: None,
     };
     let query_unbonded = query(deps.as_ref(), mock_env(), all_unbonded).unwrap();
     let res: UnbondRequestsResponse = from_binary(&query_unbonded).unwrap();
@@ -1779,6 +2115,8 @@ pub fn proper_withdraw_unbonded() {
 
     let waitlist = UnbondRequests {
         address: bob.clone(),
+        start_from: None,
+        limit: None,
     };
     let query_unbond: UnbondRequestsResponse =
         from_binary(&query(deps.as_ref(), mock_env(), waitlist).unwrap()).unwrap();
@@ -1826,7 +2164,7 @@ pub fn proper_withdraw_unbonded_respect_slashing() {
 
     let bob = "bob".to_string();
     let bond_msg = ExecuteMsg::Bond {
-        validator: validator.address.clone(),
+        validator: Some(validator.address.clone()),
     };
 
     let info = mock_info(&bob, &[coin(bond_amount.u128(), "uluna")]);
@@ -1868,7 +2206,11 @@ pub fn proper_withdraw_unbonded_respect_slashing() {
 
     env.block.time = env.block.time.plus_seconds(31);
 
-    let wdraw_unbonded_msg = ExecuteMsg::WithdrawUnbonded {};
+    let wdraw_unbonded_msg = ExecuteMsg::WithdrawUnbonded {
+        batch_ids: None,
+        recipient: None,
+        ticket_ids: None,
+    };
     let wdraw_unbonded_res = execute(
         deps.as_mut(),
         env.clone(),
@@ -1878,7 +2220,9 @@ pub fn proper_withdraw_unbonded_respect_slashing() {
     assert!(wdraw_unbonded_res.is_err());
     assert_eq!(
         wdraw_unbonded_res.unwrap_err(),
-        StdError::generic_err("No withdrawable uluna assets are available yet")
+        ContractError::NothingToWithdraw {
+            denom: "uluna".to_string()
+        }
     );
 
     // trigger undelegation message
@@ -1916,6 +2260,8 @@ pub fn proper_withdraw_unbonded_respect_slashing() {
     //first query AllUnbondedRequests
     let all_unbonded = UnbondRequests {
         address: bob.clone(),
+        start_from: None,
+        limit: None,
     };
     let query_unbonded = query(deps.as_ref(), mock_env(), all_unbonded).unwrap();
     let res: UnbondRequestsResponse = from_binary(&query_unbonded).unwrap();
@@ -1983,7 +2329,7 @@ pub fn proper_withdraw_unbonded_respect_inactivity_slashing() {
 
     let bob = "bob".to_string();
     let bond_msg = ExecuteMsg::Bond {
-        validator: validator.address.clone(),
+        validator: Some(validator.address.clone()),
     };
 
     let info = mock_info(&bob, &[coin(bond_amount.u128(), "uluna")]);
@@ -2031,7 +2377,11 @@ pub fn proper_withdraw_unbonded_respect_inactivity_slashing() {
     assert_eq!(query_batch.requested_with_fee, unbond_amount);
 
     env.block.time = env.block.time.plus_seconds(1000);
-    let wdraw_unbonded_msg = ExecuteMsg::WithdrawUnbonded {};
+    let wdraw_unbonded_msg = ExecuteMsg::WithdrawUnbonded {
+        batch_ids: None,
+        recipient: None,
+        ticket_ids: None,
+    };
     let wdraw_unbonded_res = execute(
         deps.as_mut(),
         env.clone(),
@@ -2041,7 +2391,9 @@ pub fn proper_withdraw_unbonded_respect_inactivity_slashing() {
     assert!(wdraw_unbonded_res.is_err());
     assert_eq!(
         wdraw_unbonded_res.unwrap_err(),
-        StdError::generic_err("No withdrawable uluna assets are available yet")
+        ContractError::NothingToWithdraw {
+            denom: "uluna".to_string()
+        }
     );
 
     // trigger undelegation message
@@ -2095,6 +2447,8 @@ pub fn proper_withdraw_unbonded_respect_inactivity_slashing() {
     //first query AllUnbondedRequests
     let all_unbonded = UnbondRequests {
         address: bob.clone(),
+        start_from: None,
+        limit: None,
     };
     let query_unbonded = query(deps.as_ref(), env.clone(), all_unbonded).unwrap();
     let res: UnbondRequestsResponse = from_binary(&query_unbonded).unwrap();
@@ -2175,7 +2529,7 @@ pub fn proper_withdraw_unbond_with_dummies() {
 
     let bob = "bob".to_string();
     let bond_msg = ExecuteMsg::Bond {
-        validator: validator.address.clone(),
+        validator: Some(validator.address.clone()),
     };
 
     let info = mock_info(&bob, &[coin(bond_amount.u128(), "uluna")]);
@@ -2271,7 +2625,11 @@ pub fn proper_withdraw_unbond_with_dummies() {
     )]);
 
     env.block.time = env.block.time.plus_seconds(120);
-    let wdraw_unbonded_msg = ExecuteMsg::WithdrawUnbonded {};
+    let wdraw_unbonded_msg = ExecuteMsg::WithdrawUnbonded {
+        batch_ids: None,
+        recipient: None,
+        ticket_ids: None,
+    };
     let success_res = execute(deps.as_mut(), env, info, wdraw_unbonded_msg).unwrap();
 
     assert_eq!(success_res.messages.len(), 1);
@@ -2326,6 +2684,7 @@ pub fn test_update_params() {
         unbonding_period: None,
         peg_recovery_fee: None,
         er_threshold: None,
+        delegation_strategy: None,
     };
     let owner = "owner1".to_string();
     let token_contract = "token".to_string();
@@ -2346,7 +2705,7 @@ pub fn test_update_params() {
         invalid_info,
         update_prams.clone(),
     );
-    assert_eq!(res.unwrap_err(), StdError::generic_err("unauthorized"));
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
     let creator_info = mock_info("owner1", &[]);
     let res = execute(deps.as_mut(), mock_env(), creator_info, update_prams).unwrap();
     assert_eq!(res.messages.len(), 0);
@@ -2366,6 +2725,7 @@ pub fn test_update_params() {
         unbonding_period: Some(3),
         peg_recovery_fee: Some(Decimal::one()),
         er_threshold: Some(Decimal::zero()),
+        delegation_strategy: None,
     };
 
     //the result must be 1
@@ -2396,6 +2756,7 @@ pub fn proper_recovery_fee() {
         unbonding_period: None,
         peg_recovery_fee: Some(Decimal::from_ratio(Uint128::new(1), Uint128::new(1000))),
         er_threshold: Some(Decimal::from_ratio(Uint128::new(99), Uint128::new(100))),
+        delegation_strategy: None,
     };
     let owner = "owner1".to_string();
     let token_contract = "token".to_string();
@@ -2430,7 +2791,7 @@ pub fn proper_recovery_fee() {
 
     let bob = "bob".to_string();
     let bond_msg = ExecuteMsg::Bond {
-        validator: validator.address.clone(),
+        validator: Some(validator.address.clone()),
     };
 
     //this will set the balance of the user in token contract
@@ -2456,7 +2817,7 @@ pub fn proper_recovery_fee() {
     //Bond again to see the applied result
     let bob = "bob".to_string();
     let bond_msg = ExecuteMsg::Bond {
-        validator: validator.address.clone(),
+        validator: Some(validator.address.clone()),
     };
 
     deps.querier
@@ -2572,7 +2933,11 @@ pub fn proper_recovery_fee() {
 
     token_env.block.time = token_env.block.time.plus_seconds(90);
     //check withdrawUnbonded message
-    let withdraw_unbond_msg = ExecuteMsg::WithdrawUnbonded {};
+    let withdraw_unbond_msg = ExecuteMsg::WithdrawUnbonded {
+        batch_ids: None,
+        recipient: None,
+        ticket_ids: None,
+    };
     let wdraw_unbonded_res =
         execute(deps.as_mut(), token_env, token_info, withdraw_unbond_msg).unwrap();
     assert_eq!(wdraw_unbonded_res.messages.len(), 1);
@@ -2647,31 +3012,83 @@ pub fn proper_update_config() {
     assert_eq!(&config_query.reward_contract.unwrap(), &reward_contract);
     assert_eq!(&config_query.owner, &owner);
 
-    // only the owner can call this message
-    let update_config = UpdateConfig {
-        owner: Some(new_owner.clone()),
-        reward_contract: None,
-        token_contract: None,
-        airdrop_registry_contract: None,
+    // only the owner can propose a new owner
+    let propose = ExecuteMsg::ProposeNewOwner {
+        new_owner: new_owner.clone(),
     };
     let info = mock_info(&invalid_owner, &[]);
-    let res = execute(deps.as_mut(), mock_env(), info, update_config);
-    assert_eq!(res.unwrap_err(), StdError::generic_err("unauthorized"));
+    let res = execute(deps.as_mut(), mock_env(), info, propose.clone());
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
 
-    // change the owner
-    let update_config = UpdateConfig {
-        owner: Some(new_owner.clone()),
-        reward_contract: None,
-        token_contract: None,
-        airdrop_registry_contract: None,
-    };
+    // nothing to accept before a proposal
+    let info = mock_info(&new_owner, &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::AcceptOwnership {},
+    );
+    assert_eq!(res.unwrap_err(), ContractError::NoPendingOwner {});
+
+    // propose and cancel
     let info = mock_info(&owner, &[]);
-    let res = execute(deps.as_mut(), mock_env(), info, update_config).unwrap();
+    execute(deps.as_mut(), mock_env(), info.clone(), propose.clone()).unwrap();
+    let config_query: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config_query.pending_owner, Some(new_owner.clone()));
+    assert_eq!(config_query.owner, owner);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::CancelOwnershipProposal {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![attr("action", "cancel_ownership_proposal")]
+    );
+    let config_query: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config_query.pending_owner, None);
+
+    // change the owner
+    execute(deps.as_mut(), mock_env(), info, propose).unwrap();
+
+    // only the pending owner can accept
+    let info = mock_info(&invalid_owner, &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::AcceptOwnership {},
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+
+    let info = mock_info(&new_owner, &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap();
     assert_eq!(res.messages.len(), 0);
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "accept_ownership"),
+            attr("owner", new_owner.clone())
+        ]
+    );
 
     let config = CONFIG.load(&deps.storage).unwrap();
     let new_owner_raw = deps.api.addr_canonicalize(&new_owner).unwrap();
     assert_eq!(new_owner_raw, config.creator);
+    let config_query: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config_query.pending_owner, None);
 
     // new owner can send the owner related messages
     let update_prams = UpdateParams {
@@ -2679,6 +3096,7 @@ pub fn proper_update_config() {
         unbonding_period: None,
         peg_recovery_fee: None,
         er_threshold: None,
+        delegation_strategy: None,
     };
 
     let new_owner_info = mock_info(&new_owner, &[]);
@@ -2691,14 +3109,14 @@ pub fn proper_update_config() {
         unbonding_period: None,
         peg_recovery_fee: None,
         er_threshold: None,
+        delegation_strategy: None,
     };
 
     let new_owner_info = mock_info(&owner, &[]);
     let res = execute(deps.as_mut(), mock_env(), new_owner_info, update_prams);
-    assert_eq!(res.unwrap_err(), StdError::generic_err("unauthorized"));
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
 
     let update_config = UpdateConfig {
-        owner: None,
         reward_contract: Some("new reward".to_string()),
         token_contract: None,
         airdrop_registry_contract: None,
@@ -2723,7 +3141,6 @@ pub fn proper_update_config() {
     );
 
     let update_config = UpdateConfig {
-        owner: None,
         reward_contract: None,
         token_contract: Some("new token".to_string()),
         airdrop_registry_contract: None,
@@ -2748,7 +3165,6 @@ pub fn proper_update_config() {
     assert_eq!(config_query.owner, new_owner);
 
     let update_config = UpdateConfig {
-        owner: None,
         reward_contract: None,
         token_contract: None,
         airdrop_registry_contract: Some("new airdrop".to_string()),
@@ -2797,10 +3213,7 @@ fn proper_claim_airdrop() {
     //invalid sender
     let info = mock_info(&owner, &[]);
     let res = execute(deps.as_mut(), mock_env(), info, claim_msg.clone()).unwrap_err();
-    assert_eq!(
-        res,
-        StdError::generic_err(format!("Sender must be {}", &airdrop_registry))
-    );
+    assert_eq!(res, ContractError::Unauthorized {});
 
     let valid_info = mock_info(&airdrop_registry, &[]);
     let res = execute(deps.as_mut(), mock_env(), valid_info, claim_msg).unwrap();
@@ -2863,9 +3276,13 @@ fn proper_swap_hook() {
     let info = mock_info(&owner, &[]);
     let env = mock_env();
     let res = execute(deps.as_mut(), mock_env(), info, swap_msg.clone()).unwrap_err();
-    assert_eq!(res, StdError::generic_err("unauthorized"));
+    assert_eq!(res, ContractError::Unauthorized {});
 
     // no balance for hub
+    deps.querier.with_token_balances(&[(
+        &"airdrop_token".to_string(),
+        &[(&env.contract.address.to_string(), &Uint128::zero())],
+    )]);
     let contract_info = mock_info(env.contract.address.as_str(), &[]);
     let res = execute(
         deps.as_mut(),
@@ -2873,8 +3290,12 @@ fn proper_swap_hook() {
         contract_info.clone(),
         swap_msg.clone(),
     );
-
-    assert!(res.is_err());
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::NoAirdropBalance {
+            token: "airdrop_token".to_string()
+        }
+    );
 
     deps.querier.with_token_balances(&[(
         &"airdrop_token".to_string(),
@@ -2986,8 +3407,11 @@ fn proper_update_global_index_with_airdrop() {
 fn set_delegation(querier: &mut WasmMockQuerier, validator: Validator, amount: u128, denom: &str) {
     querier.update_staking(
         "uluna",
-        &[validator.clone()],
-        &[sample_delegation(validator.address, coin(amount, denom))],
+        std::slice::from_ref(&validator),
+        &[sample_delegation(
+            validator.address.clone(),
+            coin(amount, denom),
+        )],
     );
 }
 
@@ -3017,3 +3441,2474 @@ fn sample_delegation(addr: String, amount: Coin) -> FullDelegation {
 pub enum MIRMsg {
     MIRClaim {},
 }
+
+#[test]
+pub fn proper_rebalance() {
+    let mut deps = dependencies(&[]);
+
+    let validator = sample_validator(DEFAULT_VALIDATOR.to_string());
+    let validator2 = sample_validator(DEFAULT_VALIDATOR2.to_string());
+    let validator3 = sample_validator(DEFAULT_VALIDATOR3.to_string());
+    set_validator_mock(&mut deps.querier);
+
+    let owner = "owner1".to_string();
+    let token_contract = "token".to_string();
+    let reward_contract = "reward".to_string();
+
+    init(
+        deps.borrow_mut(),
+        owner.clone(),
+        reward_contract,
+        token_contract,
+        validator.address.clone(),
+    );
+
+    do_register_validator(deps.as_mut(), validator.clone());
+    do_register_validator(deps.as_mut(), validator2.clone());
+    do_register_validator(deps.as_mut(), validator3.clone());
+
+    // rebalancing needs target weights
+    let anyone = mock_info("anyone", &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        anyone.clone(),
+        ExecuteMsg::Rebalance {},
+    );
+    assert_eq!(res.unwrap_err(), ContractError::NoTargetWeights {});
+
+    let owner_info = mock_info(&owner, &[]);
+    let weights = ExecuteMsg::UpdateValidatorWeights {
+        weights: vec![
+            ValidatorWeight {
+                validator: validator.address.clone(),
+                weight: 1,
+            },
+            ValidatorWeight {
+                validator: validator2.address.clone(),
+                weight: 1,
+            },
+            ValidatorWeight {
+                validator: validator3.address.clone(),
+                weight: 1,
+            },
+        ],
+    };
+    execute(deps.as_mut(), mock_env(), owner_info.clone(), weights).unwrap();
+    let update_max_rebalance_amount = ExecuteMsg::UpdateMaxRebalanceAmount {
+        amount: Some(Uint128::new(60)),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        anyone.clone(),
+        update_max_rebalance_amount.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        owner_info,
+        update_max_rebalance_amount,
+    )
+    .unwrap();
+    assert_eq!(res.attributes[1], attr("max_rebalance_amount", "60"));
+
+    let validators: [Validator; 3] = [
+        (validator.clone()),
+        (validator2.clone()),
+        (validator3.clone()),
+    ];
+    let delegations: [FullDelegation; 3] = [
+        (sample_delegation(validator.address.clone(), coin(300, "uluna"))),
+        (sample_delegation(validator2.address.clone(), coin(100, "uluna"))),
+        (sample_delegation(validator3.address.clone(), coin(200, "uluna"))),
+    ];
+    set_delegation_query(&mut deps.querier, &delegations, &validators);
+
+    // the move is capped by max_rebalance_amount
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        anyone.clone(),
+        ExecuteMsg::Rebalance {},
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Staking(StakingMsg::Redelegate {
+            src_validator: validator.address.clone(),
+            dst_validator: validator2.address.clone(),
+            amount: coin(60, "uluna"),
+        }))
+    );
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+            msg: to_binary(&ExecuteMsg::UpdateGlobalIndex {
+                airdrop_hooks: None
+            })
+            .unwrap(),
+            funds: vec![],
+        }))
+    );
+    assert_eq!(res.attributes[1], attr("moved", "60"));
+
+    // validator2 has just received a redelegation, so it can not be a source
+    let delegations: [FullDelegation; 3] = [
+        (sample_delegation(validator.address.clone(), coin(200, "uluna"))),
+        (sample_delegation(validator2.address.clone(), coin(300, "uluna"))),
+        (sample_delegation(validator3.address.clone(), coin(100, "uluna"))),
+    ];
+    set_delegation_query(&mut deps.querier, &delegations, &validators);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        anyone.clone(),
+        ExecuteMsg::Rebalance {},
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 0);
+    assert_eq!(res.attributes[1], attr("moved", "0"));
+
+    // once the unbonding period has passed it can be rebalanced again
+    let query_params: Parameters =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Parameters {}).unwrap()).unwrap();
+    let mut env = mock_env();
+    env.block.time = env
+        .block
+        .time
+        .plus_seconds(query_params.unbonding_period + 1);
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        anyone.clone(),
+        ExecuteMsg::Rebalance {},
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Staking(StakingMsg::Redelegate {
+            src_validator: validator2.address.clone(),
+            dst_validator: validator3.address.clone(),
+            amount: coin(60, "uluna"),
+        }))
+    );
+
+    // once the cap is removed the whole excess is moved
+    let update_max_rebalance_amount = ExecuteMsg::UpdateMaxRebalanceAmount { amount: None };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(&owner, &[]),
+        update_max_rebalance_amount,
+    )
+    .unwrap();
+    let query_params: Parameters =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Parameters {}).unwrap()).unwrap();
+    assert_eq!(query_params.max_rebalance_amount, None);
+
+    let res = execute(deps.as_mut(), env, anyone, ExecuteMsg::Rebalance {}).unwrap();
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Staking(StakingMsg::Redelegate {
+            src_validator: validator2.address,
+            dst_validator: validator3.address,
+            amount: coin(100, "uluna"),
+        }))
+    );
+}
+
+#[test]
+pub fn proper_migrate() {
+    let mut deps = dependencies(&[]);
+
+    let validator = sample_validator(DEFAULT_VALIDATOR.to_string());
+    set_validator_mock(&mut deps.querier);
+
+    let owner = "owner1".to_string();
+    init(
+        deps.borrow_mut(),
+        owner,
+        "reward".to_string(),
+        "token".to_string(),
+        validator.address.clone(),
+    );
+
+    let version = get_contract_version(&deps.storage).unwrap();
+    assert_eq!(version.contract, "crates.io:anchor-basset-hub");
+    assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+
+    // a hub deployed before cw2 has no version and the wait list in the legacy layout
+    deps.storage.remove(b"contract_info");
+    let mut legacy_wait_list: Bucket<Uint128> = Bucket::multilevel(
+        &mut deps.storage,
+        &[b"wait", &to_vec(&"bob".to_string()).unwrap()],
+    );
+    legacy_wait_list
+        .save(&to_vec(&1u64).unwrap(), &Uint128::new(100))
+        .unwrap();
+    // more requests than the migration moves at once
+    for i in 0..100 {
+        let mut legacy_wait_list: Bucket<Uint128> = Bucket::multilevel(
+            &mut deps.storage,
+            &[b"wait", &to_vec(&format!("user{:03}", i)).unwrap()],
+        );
+        legacy_wait_list
+            .save(&to_vec(&1u64).unwrap(), &Uint128::new(10))
+            .unwrap();
+    }
+    // nor the expected delegations the slashings are attributed against
+    EXPECTED_DELEGATIONS.remove(&mut deps.storage, DEFAULT_VALIDATOR);
+    set_delegation(&mut deps.querier, validator, 1000100, "uluna");
+
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(res.attributes[2], attr("migrated_unbond_requests", "100"));
+    assert_eq!(res.attributes[3], attr("wait_list_migrated", "false"));
+    assert_eq!(
+        EXPECTED_DELEGATIONS
+            .load(&deps.storage, DEFAULT_VALIDATOR)
+            .unwrap(),
+        Uint128::new(1000100)
+    );
+
+    let query_unbond: UnbondRequestsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            UnbondRequests {
+                address: "bob".to_string(),
+                start_from: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(query_unbond.requests, vec![(1u64, Uint128::new(100))]);
+
+    // the requests of a batch are listed once the whole wait list is migrated
+    let batch_requests = QueryMsg::BatchUnbondRequests {
+        batch_id: 1,
+        start_after: None,
+        limit: Some(2),
+    };
+    let res = query(deps.as_ref(), mock_env(), batch_requests.clone());
+    assert_eq!(res.unwrap_err(), ContractError::WaitListMigrationPending {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::MigrateWaitList { limit: None },
+    )
+    .unwrap();
+    assert_eq!(res.attributes[1], attr("migrated_unbond_requests", "1"));
+    assert_eq!(res.attributes[2], attr("wait_list_migrated", "true"));
+
+    let res: BatchUnbondRequestsResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), batch_requests).unwrap()).unwrap();
+    assert_eq!(
+        res.requests,
+        vec![
+            ("bob".to_string(), Uint128::new(100)),
+            ("user000".to_string(), Uint128::new(10)),
+        ]
+    );
+
+    // migrating again is a no-op
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(res.attributes[2], attr("migrated_unbond_requests", "0"));
+
+    // so is moving a page of the wait list, which anyone can do
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::MigrateWaitList { limit: Some(10) },
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "migrate_wait_list"),
+            attr("migrated_unbond_requests", "0"),
+            attr("wait_list_migrated", "true"),
+        ]
+    );
+
+    // downgrades are refused
+    set_contract_version(&mut deps.storage, "crates.io:anchor-basset-hub", "9.0.0").unwrap();
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {});
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::CannotDowngrade {
+            stored: "9.0.0".to_string(),
+            new: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    );
+}
+
+#[test]
+pub fn proper_pause() {
+    let mut deps = dependencies(&[]);
+
+    let validator = sample_validator(DEFAULT_VALIDATOR.to_string());
+    set_validator_mock(&mut deps.querier);
+
+    let owner = "owner1".to_string();
+    let token_contract = "token".to_string();
+    init(
+        deps.borrow_mut(),
+        owner.clone(),
+        "reward".to_string(),
+        token_contract.clone(),
+        validator.address.clone(),
+    );
+    do_register_validator(deps.as_mut(), validator.clone());
+
+    // only the owner sets the guardian
+    let guardian_info = mock_info("guardian", &[]);
+    let update_guardian = ExecuteMsg::UpdateGuardian {
+        guardian: Some("guardian".to_string()),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        guardian_info.clone(),
+        update_guardian.clone(),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+    let owner_info = mock_info(&owner, &[]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        owner_info.clone(),
+        update_guardian,
+    )
+    .unwrap();
+
+    // only the guardian or the owner can pause
+    let pause = ExecuteMsg::Pause {
+        operations: vec![PausableOperation::Bond, PausableOperation::Unbond],
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        pause.clone(),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+    execute(deps.as_mut(), mock_env(), guardian_info.clone(), pause).unwrap();
+
+    let pause_info: PauseInfoResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PauseInfo {}).unwrap()).unwrap();
+    assert_eq!(
+        pause_info,
+        PauseInfoResponse {
+            guardian: Some("guardian".to_string()),
+            paused_operations: vec![PausableOperation::Bond, PausableOperation::Unbond],
+        }
+    );
+
+    let bond = ExecuteMsg::Bond {
+        validator: Some(validator.address.clone()),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bob", &[coin(10, "uluna")]),
+        bond.clone(),
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::OperationPaused {
+            operation: PausableOperation::Bond
+        }
+    );
+
+    let receive = Receive(Cw20ReceiveMsg {
+        sender: "bob".to_string(),
+        amount: Uint128::new(1),
+        msg: to_binary(&Unbond {}).unwrap(),
+    });
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(&token_contract, &[]),
+        receive,
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::OperationPaused {
+            operation: PausableOperation::Unbond
+        }
+    );
+
+    // the guardian can not unpause
+    let unpause = ExecuteMsg::Unpause {
+        operations: vec![PausableOperation::Bond],
+    };
+    let res = execute(deps.as_mut(), mock_env(), guardian_info, unpause.clone());
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+    execute(deps.as_mut(), mock_env(), owner_info, unpause).unwrap();
+
+    let pause_info: PauseInfoResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PauseInfo {}).unwrap()).unwrap();
+    assert_eq!(
+        pause_info.paused_operations,
+        vec![PausableOperation::Unbond]
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bob", &[coin(10, "uluna")]),
+        bond,
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 2);
+}
+
+#[test]
+pub fn proper_simulate() {
+    let mut deps = dependencies(&[]);
+    let validator = sample_validator(DEFAULT_VALIDATOR.to_string());
+    set_validator_mock(&mut deps.querier);
+
+    let owner = "owner1".to_string();
+    let token_contract = "token".to_string();
+    init(
+        &mut deps,
+        owner.clone(),
+        "reward".to_string(),
+        token_contract.clone(),
+        validator.address.clone(),
+    );
+    do_register_validator(deps.as_mut(), validator.clone());
+
+    let update_prams = UpdateParams {
+        epoch_period: None,
+        unbonding_period: None,
+        peg_recovery_fee: Some(Decimal::from_ratio(Uint128::new(1), Uint128::new(1000))),
+        er_threshold: Some(Decimal::from_ratio(Uint128::new(99), Uint128::new(100))),
+        delegation_strategy: None,
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(&owner, &[]),
+        update_prams,
+    )
+    .unwrap();
+
+    let bob = "bob".to_string();
+    let bond_amount = Uint128::new(1000000u128);
+    do_bond(deps.as_mut(), bob.clone(), bond_amount, validator.clone());
+    deps.querier
+        .with_token_balances(&[(&token_contract, &[(&bob, &bond_amount)])]);
+
+    // the simulation must see the slashing the execution would apply
+    set_delegation(&mut deps.querier, validator.clone(), 900000, "uluna");
+
+    let simulation: SimulateBondResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateBond {
+                amount: bond_amount,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(!simulation.peg_recovery_fee.is_zero());
+
+    let info = mock_info(&bob, &[coin(bond_amount.u128(), "uluna")]);
+    let bond_msg = ExecuteMsg::Bond {
+        validator: Some(validator.address.clone()),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, bond_msg).unwrap();
+    assert_eq!(
+        res.attributes[4],
+        attr("minted", simulation.minted.to_string())
+    );
+    let state: StateResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap()).unwrap();
+    assert_eq!(state.exchange_rate, simulation.exchange_rate);
+
+    let total_supply = bond_amount + simulation.minted;
+    deps.querier
+        .with_token_balances(&[(&token_contract, &[(&bob, &total_supply)])]);
+    set_delegation(&mut deps.querier, validator, 1900000, "uluna");
+
+    let unbond_amount = Uint128::new(100000u128);
+    let simulation: SimulateUnbondResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateUnbond {
+                amount: unbond_amount,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(simulation.burned, unbond_amount);
+    assert_eq!(
+        simulation.unbonded + simulation.peg_recovery_fee,
+        unbond_amount
+    );
+    assert_eq!(
+        simulation.estimated_amount,
+        simulation.unbonded * simulation.exchange_rate
+    );
+    // the batch is submitted once the epoch period has passed
+    let params: Parameters =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Parameters {}).unwrap()).unwrap();
+    assert_eq!(
+        simulation.estimated_release_time,
+        mock_env().block.time.seconds() + params.epoch_period + 1 + params.unbonding_period
+    );
+
+    let res = do_unbond(
+        deps.as_mut(),
+        bob,
+        mock_env(),
+        mock_info(&token_contract, &[]),
+        unbond_amount,
+    );
+    assert_eq!(
+        res.attributes[3],
+        attr("unbonded_amount", simulation.unbonded.to_string())
+    );
+    let state: StateResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap()).unwrap();
+    assert_eq!(state.exchange_rate, simulation.exchange_rate);
+}
+
+#[test]
+pub fn proper_cancel_unbond() {
+    let mut deps = dependencies(&[]);
+    let validator = sample_validator(DEFAULT_VALIDATOR.to_string());
+    set_validator_mock(&mut deps.querier);
+
+    let token_contract = "token".to_string();
+    init(
+        deps.borrow_mut(),
+        "owner1".to_string(),
+        "reward".to_string(),
+        token_contract.clone(),
+        validator.address.clone(),
+    );
+    do_register_validator(deps.as_mut(), validator.clone());
+
+    let bob = "bob".to_string();
+    let bond_amount = Uint128::new(1000u128);
+    do_bond(deps.as_mut(), bob.clone(), bond_amount, validator.clone());
+    set_delegation(&mut deps.querier, validator, 1000, "uluna");
+    deps.querier
+        .with_token_balances(&[(&token_contract, &[(&bob, &bond_amount)])]);
+
+    do_unbond(
+        deps.as_mut(),
+        bob.clone(),
+        mock_env(),
+        mock_info(&token_contract, &[]),
+        Uint128::new(100),
+    );
+    deps.querier
+        .with_token_balances(&[(&token_contract, &[(&bob, &Uint128::new(900))])]);
+    let state_before: StateResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap()).unwrap();
+
+    // can not cancel more than requested
+    let cancel = ExecuteMsg::CancelUnbond {
+        amount: Uint128::new(101),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info(&bob, &[]), cancel);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::CancelExceedsRequest {
+            requested: Uint128::new(100)
+        }
+    );
+
+    let cancel = ExecuteMsg::CancelUnbond {
+        amount: Uint128::new(40),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        cancel.clone(),
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::CancelExceedsRequest {
+            requested: Uint128::zero()
+        }
+    );
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info(&bob, &[]), cancel).unwrap();
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: token_contract.clone(),
+            msg: to_binary(&Mint {
+                recipient: bob.clone(),
+                amount: Uint128::new(40),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+    deps.querier
+        .with_token_balances(&[(&token_contract, &[(&bob, &Uint128::new(940))])]);
+
+    let query_batch: CurrentBatchResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::CurrentBatch {}).unwrap()).unwrap();
+    assert_eq!(query_batch.id, 1);
+    assert_eq!(query_batch.requested_with_fee, Uint128::new(60));
+    assert_eq!(
+        read_unbond_wait_list(&deps.storage, 1, bob.clone()).unwrap(),
+        Uint128::new(60)
+    );
+
+    // moving the request back to the supply keeps the exchange rate
+    let state: StateResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap()).unwrap();
+    assert_eq!(state.exchange_rate, state_before.exchange_rate);
+
+    // cancelling the rest removes the request
+    let cancel = ExecuteMsg::CancelUnbond {
+        amount: Uint128::new(60),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(&bob, &[]), cancel).unwrap();
+    let requests: UnbondRequestsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::UnbondRequests {
+                address: bob.clone(),
+                start_from: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(requests.requests.is_empty());
+
+    // a submitted batch can not be cancelled
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(31);
+    do_unbond(
+        deps.as_mut(),
+        bob.clone(),
+        env.clone(),
+        mock_info(&token_contract, &[]),
+        Uint128::new(100),
+    );
+    let cancel = ExecuteMsg::CancelUnbond {
+        amount: Uint128::new(100),
+    };
+    let res = execute(deps.as_mut(), env, mock_info(&bob, &[]), cancel);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::CancelExceedsRequest {
+            requested: Uint128::zero()
+        }
+    );
+}
+
+#[test]
+pub fn proper_submit_batch() {
+    let mut deps = dependencies(&[]);
+    let validator = sample_validator(DEFAULT_VALIDATOR.to_string());
+    set_validator_mock(&mut deps.querier);
+
+    let token_contract = "token".to_string();
+    init(
+        deps.borrow_mut(),
+        "owner1".to_string(),
+        "reward".to_string(),
+        token_contract.clone(),
+        validator.address.clone(),
+    );
+    do_register_validator(deps.as_mut(), validator.clone());
+
+    let bob = "bob".to_string();
+    let bond_amount = Uint128::new(1000u128);
+    do_bond(deps.as_mut(), bob.clone(), bond_amount, validator.clone());
+    set_delegation(&mut deps.querier, validator.clone(), 1000, "uluna");
+    deps.querier
+        .with_token_balances(&[(&token_contract, &[(&bob, &bond_amount)])]);
+
+    let res = do_unbond(
+        deps.as_mut(),
+        bob.clone(),
+        mock_env(),
+        mock_info(&token_contract, &[]),
+        Uint128::new(100),
+    );
+    assert_eq!(res.messages.len(), 1);
+    deps.querier
+        .with_token_balances(&[(&token_contract, &[(&bob, &Uint128::new(900))])]);
+
+    // the epoch period has not passed yet
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::SubmitBatch {},
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::BatchNotReady {
+            ready_at: mock_env().block.time.seconds() + 31
+        }
+    );
+
+    // anyone can submit the batch without unbonding
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(31);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::SubmitBatch {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Staking(StakingMsg::Undelegate {
+            validator: validator.address,
+            amount: coin(100, "uluna"),
+        }))]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![attr("action", "submit_batch"), attr("batch_id", "1")]
+    );
+
+    let history = read_unbond_history(&deps.storage, 1).unwrap();
+    assert_eq!(history.amount, Uint128::new(100));
+    assert_eq!(history.time, env.block.time.seconds());
+    let query_batch: CurrentBatchResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::CurrentBatch {}).unwrap()).unwrap();
+    assert_eq!(query_batch.id, 2);
+    assert_eq!(query_batch.requested_with_fee, Uint128::zero());
+    let state: StateResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap()).unwrap();
+    assert_eq!(state.last_unbonded_time, env.block.time.seconds());
+
+    // an empty batch is not submitted
+    env.block.time = env.block.time.plus_seconds(31);
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("anyone", &[]),
+        ExecuteMsg::SubmitBatch {},
+    );
+    assert_eq!(res.unwrap_err(), ContractError::EmptyBatch {});
+}
+
+#[test]
+pub fn proper_process_batches() {
+    let setup = || {
+        let mut deps = dependencies(&[]);
+        let validator = sample_validator(DEFAULT_VALIDATOR.to_string());
+        set_validator_mock(&mut deps.querier);
+
+        let token_contract = "token".to_string();
+        init(
+            deps.borrow_mut(),
+            "owner1".to_string(),
+            "reward".to_string(),
+            token_contract.clone(),
+            validator.address.clone(),
+        );
+        do_register_validator(deps.as_mut(), validator.clone());
+
+        let bob = "bob".to_string();
+        let bond_amount = Uint128::new(1000u128);
+        do_bond(deps.as_mut(), bob.clone(), bond_amount, validator.clone());
+        set_delegation(&mut deps.querier, validator, 1001000, "uluna");
+
+        // each unbond submits its own batch
+        let mut env = mock_env();
+        let owner_balance = Uint128::new(1000000u128);
+        for i in 0..3u128 {
+            let bob_balance = bond_amount - Uint128::new(100 * i);
+            deps.querier.with_token_balances(&[(
+                &token_contract,
+                &[
+                    (&bob, &bob_balance),
+                    (&"owner1".to_string(), &owner_balance),
+                ],
+            )]);
+            env.block.time = env.block.time.plus_seconds(31);
+            do_unbond(
+                deps.as_mut(),
+                bob.clone(),
+                env.clone(),
+                mock_info(&token_contract, &[]),
+                Uint128::new(100),
+            );
+        }
+
+        // the batches got slashed while unbonding
+        deps.querier.with_native_balances(&[(
+            MOCK_CONTRACT_ADDR.to_string(),
+            Coin {
+                denom: "uluna".to_string(),
+                amount: Uint128::new(290),
+            },
+        )]);
+        env.block.time = env.block.time.plus_seconds(3);
+        (deps, env)
+    };
+    let all_history = |deps: Deps| {
+        let history: AllHistoryResponse = from_binary(
+            &query(
+                deps,
+                mock_env(),
+                QueryMsg::AllHistory {
+                    start_from: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        history.history
+    };
+
+    // a single call processes the whole backlog
+    let (mut deps, env) = setup();
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("keeper", &[]),
+        ExecuteMsg::ProcessBatches { limit: None },
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "process_batches"),
+            attr("released_batches", "3")
+        ]
+    );
+    let expected = all_history(deps.as_ref());
+    assert!(expected.iter().all(|h| h.released));
+    assert!(expected[0].withdraw_rate < Decimal::one());
+
+    // the same result one batch per call
+    let (mut deps, env) = setup();
+    let process = ExecuteMsg::ProcessBatches { limit: Some(1) };
+    let mut released = vec![];
+    for _ in 0..6 {
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("keeper", &[]),
+            process.clone(),
+        )
+        .unwrap();
+        released.push(res.attributes[1].value.clone());
+    }
+    // all the matured batches are scanned before any is released
+    assert_eq!(released, vec!["0", "0", "1", "1", "1", "0"]);
+    assert_eq!(all_history(deps.as_ref()), expected);
+
+    let state: StateResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap()).unwrap();
+    assert_eq!(state.last_processed_batch, 3);
+    assert_eq!(state.actual_unbonded_amount, Uint128::zero());
+    assert_eq!(state.prev_hub_balance, Uint128::new(290));
+}
+
+/// Covers withdrawing the released requests of given batches to a recipient.
+#[test]
+pub fn proper_withdraw_unbonded_batches() {
+    let mut deps = dependencies(&[]);
+    let validator = sample_validator(DEFAULT_VALIDATOR.to_string());
+    set_validator_mock(&mut deps.querier);
+
+    let token_contract = "token".to_string();
+    init(
+        deps.borrow_mut(),
+        "owner1".to_string(),
+        "reward".to_string(),
+        token_contract.clone(),
+        validator.address.clone(),
+    );
+    do_register_validator(deps.as_mut(), validator.clone());
+
+    let bob = "bob".to_string();
+    let bond_amount = Uint128::new(1000u128);
+    do_bond(deps.as_mut(), bob.clone(), bond_amount, validator.clone());
+    set_delegation(&mut deps.querier, validator, 1001000, "uluna");
+
+    // each unbond submits its own batch
+    let mut env = mock_env();
+    let owner_balance = Uint128::new(1000000u128);
+    for i in 0..3u128 {
+        let bob_balance = bond_amount - Uint128::new(100 * i);
+        deps.querier.with_token_balances(&[(
+            &token_contract,
+            &[
+                (&bob, &bob_balance),
+                (&"owner1".to_string(), &owner_balance),
+            ],
+        )]);
+        env.block.time = env.block.time.plus_seconds(31);
+        do_unbond(
+            deps.as_mut(),
+            bob.clone(),
+            env.clone(),
+            mock_info(&token_contract, &[]),
+            Uint128::new(100),
+        );
+    }
+
+    deps.querier.with_native_balances(&[(
+        MOCK_CONTRACT_ADDR.to_string(),
+        Coin {
+            denom: "uluna".to_string(),
+            amount: Uint128::new(300),
+        },
+    )]);
+    env.block.time = env.block.time.plus_seconds(3);
+
+    // only the second batch is withdrawn, to the vault
+    let withdraw = ExecuteMsg::WithdrawUnbonded {
+        batch_ids: Some(vec![2]),
+        recipient: Some("vault".to_string()),
+        ticket_ids: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(&bob, &[]),
+        withdraw.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "vault".to_string(),
+            amount: vec![coin(100, "uluna")],
+        })
+    );
+    assert_eq!(res.attributes[2], attr("recipient", "vault"));
+
+    let requests: UnbondRequestsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            UnbondRequests {
+                address: bob.clone(),
+                start_from: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        requests.requests,
+        vec![(1, Uint128::new(100)), (3, Uint128::new(100))]
+    );
+
+    // the batch is already withdrawn
+    deps.querier.with_native_balances(&[(
+        MOCK_CONTRACT_ADDR.to_string(),
+        Coin {
+            denom: "uluna".to_string(),
+            amount: Uint128::new(200),
+        },
+    )]);
+    let err = execute(deps.as_mut(), env.clone(), mock_info(&bob, &[]), withdraw).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::NothingToWithdraw {
+            denom: "uluna".to_string()
+        }
+    );
+
+    // the rest goes to bob
+    let withdraw = ExecuteMsg::WithdrawUnbonded {
+        batch_ids: None,
+        recipient: None,
+        ticket_ids: None,
+    };
+    let res = execute(deps.as_mut(), env, mock_info(&bob, &[]), withdraw).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: bob.clone(),
+            amount: vec![coin(200, "uluna")],
+        })
+    );
+
+    let state: StateResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap()).unwrap();
+    assert_eq!(state.prev_hub_balance, Uint128::zero());
+}
+
+/// Covers unbond requests recorded for the recipient named in the hook.
+#[test]
+pub fn proper_unbond_for() {
+    let mut deps = dependencies(&[]);
+    let validator = sample_validator(DEFAULT_VALIDATOR.to_string());
+    set_validator_mock(&mut deps.querier);
+
+    let token_contract = "token".to_string();
+    init(
+        deps.borrow_mut(),
+        "owner1".to_string(),
+        "reward".to_string(),
+        token_contract.clone(),
+        validator.address.clone(),
+    );
+    do_register_validator(deps.as_mut(), validator.clone());
+
+    let vault = "vault".to_string();
+    let bond_amount = Uint128::new(1000u128);
+    do_bond(deps.as_mut(), vault.clone(), bond_amount, validator.clone());
+    set_delegation(&mut deps.querier, validator, bond_amount.u128(), "uluna");
+    deps.querier
+        .with_token_balances(&[(&token_contract, &[(&vault, &bond_amount)])]);
+
+    // the recipient must be a valid address
+    let receive = Receive(Cw20ReceiveMsg {
+        sender: vault.clone(),
+        amount: Uint128::new(10),
+        msg: to_binary(&Cw20HookMsg::UnbondFor {
+            recipient: "a".to_string(),
+        })
+        .unwrap(),
+    });
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(&token_contract, &[]),
+        receive,
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Std(_)));
+
+    let receive = Receive(Cw20ReceiveMsg {
+        sender: vault.clone(),
+        amount: Uint128::new(10),
+        msg: to_binary(&Cw20HookMsg::UnbondFor {
+            recipient: "alice".to_string(),
+        })
+        .unwrap(),
+    });
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(&token_contract, &[]),
+        receive,
+    )
+    .unwrap();
+    assert_eq!(res.attributes[1], attr("from", "alice"));
+    assert_eq!(res.attributes[4], attr("sender", "vault"));
+
+    // the request belongs to alice
+    let requests = |address: &str| {
+        let res: UnbondRequestsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                UnbondRequests {
+                    address: address.to_string(),
+                    start_from: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        res.requests
+    };
+    assert_eq!(requests("alice"), vec![(1, Uint128::new(10))]);
+    assert_eq!(requests("vault"), vec![]);
+}
+
+/// Covers unbond requests held by cw721 tickets and withdrawn by their holder.
+#[test]
+pub fn proper_unbond_tickets() {
+    let mut deps = dependencies(&[]);
+    let validator = sample_validator(DEFAULT_VALIDATOR.to_string());
+    set_validator_mock(&mut deps.querier);
+
+    let token_contract = "token".to_string();
+    init(
+        deps.borrow_mut(),
+        "owner1".to_string(),
+        "reward".to_string(),
+        token_contract.clone(),
+        validator.address.clone(),
+    );
+    do_register_validator(deps.as_mut(), validator.clone());
+
+    // only the owner registers the ticket contract
+    let update = ExecuteMsg::UpdateUnbondTicketContract {
+        contract: Some(MOCK_TICKET_CONTRACT_ADDR.to_string()),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        update.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    execute(deps.as_mut(), mock_env(), mock_info("owner1", &[]), update).unwrap();
+
+    let bob = "bob".to_string();
+    let bond_amount = Uint128::new(1000u128);
+    do_bond(deps.as_mut(), bob.clone(), bond_amount, validator.clone());
+    set_delegation(&mut deps.querier, validator, 1001000, "uluna");
+
+    // each unbond mints a ticket and submits its own batch
+    let mut env = mock_env();
+    let owner_balance = Uint128::new(1000000u128);
+    for i in 0..3u128 {
+        let bob_balance = bond_amount - Uint128::new(100 * i);
+        deps.querier.with_token_balances(&[(
+            &token_contract,
+            &[
+                (&bob, &bob_balance),
+                (&"owner1".to_string(), &owner_balance),
+            ],
+        )]);
+        env.block.time = env.block.time.plus_seconds(31);
+        let res = do_unbond(
+            deps.as_mut(),
+            bob.clone(),
+            env.clone(),
+            mock_info(&token_contract, &[]),
+            Uint128::new(100),
+        );
+        let ticket_id = (i + 1).to_string();
+        assert!(res
+            .attributes
+            .contains(&attr("ticket_id", ticket_id.clone())));
+        let mint = res
+            .messages
+            .iter()
+            .find_map(|m| match &m.msg {
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr, msg, ..
+                }) if contract_addr == MOCK_TICKET_CONTRACT_ADDR => {
+                    Some(from_binary::<UnbondTicketMsg>(msg).unwrap())
+                }
+                _ => None,
+            })
+            .unwrap();
+        match mint {
+            UnbondTicketMsg::Mint {
+                token_id,
+                owner,
+                ticket,
+            } => {
+                assert_eq!(token_id, ticket_id);
+                assert_eq!(owner, bob);
+                assert_eq!(ticket.batch_id, i as u64 + 1);
+                assert_eq!(ticket.amount, Uint128::new(100));
+                assert_eq!(ticket.applied_exchange_rate, Decimal::one());
+            }
+            _ => panic!("Unexpected message: {:?}", mint),
+        }
+    }
+
+    // the requests are not on the wait list of bob
+    let requests: UnbondRequestsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            UnbondRequests {
+                address: bob.clone(),
+                start_from: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(requests.requests.is_empty());
+
+    // a fourth ticket stays in the current batch
+    deps.querier.with_token_balances(&[(
+        &token_contract,
+        &[
+            (&bob, &Uint128::new(700)),
+            (&"owner1".to_string(), &owner_balance),
+        ],
+    )]);
+    do_unbond(
+        deps.as_mut(),
+        bob.clone(),
+        env.clone(),
+        mock_info(&token_contract, &[]),
+        Uint128::new(100),
+    );
+    deps.querier
+        .with_ticket_owners(&[("1", "bob"), ("2", "bob"), ("3", "bob"), ("4", "bob")]);
+
+    // the position lists the tickets held by bob
+    let position: PositionResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::Position {
+                address: bob.clone(),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let tickets: Vec<(String, u64, UnbondBatchStatus)> = position
+        .unbond_tickets
+        .into_iter()
+        .map(|t| (t.ticket_id, t.batch.batch_id, t.batch.status))
+        .collect();
+    assert_eq!(
+        tickets,
+        vec![
+            ("1".to_string(), 1, UnbondBatchStatus::Unbonding),
+            ("2".to_string(), 2, UnbondBatchStatus::Unbonding),
+            ("3".to_string(), 3, UnbondBatchStatus::Unbonding),
+            ("4".to_string(), 4, UnbondBatchStatus::Open),
+        ]
+    );
+
+    // only the holder cancels a ticket, and only in the current batch
+    let cancel = |ticket_id: &str| ExecuteMsg::CancelUnbondTicket {
+        ticket_id: ticket_id.to_string(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("alice", &[]),
+        cancel("4"),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(&bob, &[]),
+        cancel("1"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::TicketBatchSubmitted {
+            ticket_id: "1".to_string()
+        }
+    );
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(&bob, &[]),
+        cancel("4"),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: token_contract.clone(),
+                msg: to_binary(&Cw20ExecuteMsg::Mint {
+                    recipient: bob.clone(),
+                    amount: Uint128::new(100),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: MOCK_TICKET_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&UnbondTicketMsg::Burn {
+                    token_id: "4".to_string(),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+    assert!(res.attributes.contains(&attr("ticket_id", "4")));
+    assert!(query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::UnbondTicket {
+            ticket_id: "4".to_string()
+        }
+    )
+    .is_err());
+
+    // the ticket contract is fixed while tickets are outstanding
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner1", &[]),
+        ExecuteMsg::UpdateUnbondTicketContract { contract: None },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::OutstandingUnbondTickets {});
+
+    // bob sold the second ticket to alice
+    deps.querier
+        .with_ticket_owners(&[("1", "bob"), ("2", "alice"), ("3", "bob")]);
+    deps.querier.with_native_balances(&[(
+        MOCK_CONTRACT_ADDR.to_string(),
+        Coin {
+            denom: "uluna".to_string(),
+            amount: Uint128::new(300),
+        },
+    )]);
+    env.block.time = env.block.time.plus_seconds(3);
+
+    let withdraw = |ticket_ids: &[&str]| ExecuteMsg::WithdrawUnbonded {
+        batch_ids: None,
+        recipient: None,
+        ticket_ids: Some(ticket_ids.iter().map(|id| id.to_string()).collect()),
+    };
+    let burn = |token_id: &str| {
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: MOCK_TICKET_CONTRACT_ADDR.to_string(),
+            msg: to_binary(&UnbondTicketMsg::Burn {
+                token_id: token_id.to_string(),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(&bob, &[]),
+        withdraw(&["2"]),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // a withdrawal selects either batches or tickets
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(&bob, &[]),
+        ExecuteMsg::WithdrawUnbonded {
+            batch_ids: Some(vec![1]),
+            recipient: None,
+            ticket_ids: Some(vec!["1".to_string()]),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::WithdrawSelectionConflict {});
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("alice", &[]),
+        withdraw(&["2"]),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(BankMsg::Send {
+                to_address: "alice".to_string(),
+                amount: vec![coin(100, "uluna")],
+            }),
+            SubMsg::new(burn("2")),
+        ]
+    );
+
+    // the ticket lookup shows what the other tickets are worth
+    let ticket: UnbondTicketResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::UnbondTicket {
+                ticket_id: "3".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(ticket.amount, Uint128::new(100));
+    assert_eq!(ticket.batch.status, UnbondBatchStatus::Released);
+    assert_eq!(ticket.withdrawable, Uint128::new(100));
+
+    deps.querier.with_native_balances(&[(
+        MOCK_CONTRACT_ADDR.to_string(),
+        Coin {
+            denom: "uluna".to_string(),
+            amount: Uint128::new(200),
+        },
+    )]);
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info(&bob, &[]),
+        withdraw(&["3", "1"]),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(BankMsg::Send {
+                to_address: bob,
+                amount: vec![coin(200, "uluna")],
+            }),
+            SubMsg::new(burn("1")),
+            SubMsg::new(burn("3")),
+        ]
+    );
+
+    // no ticket is left
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner1", &[]),
+        ExecuteMsg::UpdateUnbondTicketContract { contract: None },
+    )
+    .unwrap();
+}
+
+/// Covers instant unbonds paid by the liquidity buffer and its replenishment.
+#[test]
+pub fn proper_instant_unbond() {
+    let mut deps = dependencies(&[]);
+    let validator = sample_validator(DEFAULT_VALIDATOR.to_string());
+    set_validator_mock(&mut deps.querier);
+
+    let owner = "owner1".to_string();
+    let token_contract = "token".to_string();
+    init(
+        deps.borrow_mut(),
+        owner.clone(),
+        "reward".to_string(),
+        token_contract.clone(),
+        validator.address.clone(),
+    );
+    do_register_validator(deps.as_mut(), validator.clone());
+
+    let bob = "bob".to_string();
+    let bond_amount = Uint128::new(1000u128);
+    do_bond(deps.as_mut(), bob.clone(), bond_amount, validator.clone());
+    set_delegation(&mut deps.querier, validator, bond_amount.u128(), "uluna");
+    deps.querier
+        .with_token_balances(&[(&token_contract, &[(&bob, &bond_amount)])]);
+
+    // only the owner configures the buffer, with fractions up to one
+    let update = ExecuteMsg::UpdateLiquidityBuffer {
+        fee: Some(Decimal::percent(1)),
+        max_utilisation: Some(Decimal::percent(50)),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(&bob, &[]),
+        update.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(&owner, &[]),
+        ExecuteMsg::UpdateLiquidityBuffer {
+            fee: Some(Decimal::percent(101)),
+            max_utilisation: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::FractionTooLarge {
+            name: "fee".to_string()
+        }
+    );
+    execute(deps.as_mut(), mock_env(), mock_info(&owner, &[]), update).unwrap();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(&owner, &[coin(400, "uluna")]),
+        ExecuteMsg::DepositLiquidityBuffer {},
+    )
+    .unwrap();
+
+    let buffer = |deps: Deps| -> LiquidityBufferResponse {
+        from_binary(&query(deps, mock_env(), QueryMsg::LiquidityBuffer {}).unwrap()).unwrap()
+    };
+    assert_eq!(
+        buffer(deps.as_ref()),
+        LiquidityBufferResponse {
+            balance: Uint128::new(400),
+            lent: Uint128::zero(),
+            fee: Decimal::percent(1),
+            max_utilisation: Decimal::percent(50),
+            available: Uint128::new(200),
+        }
+    );
+
+    let instant_unbond = |amount: u128| {
+        Receive(Cw20ReceiveMsg {
+            sender: bob.clone(),
+            amount: Uint128::new(amount),
+            msg: to_binary(&Cw20HookMsg::InstantUnbond {}).unwrap(),
+        })
+    };
+
+    // the payout is capped by the utilisation of the buffer
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(&token_contract, &[]),
+        instant_unbond(300),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InsufficientLiquidity {
+            available: Uint128::new(200)
+        }
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(&token_contract, &[]),
+        instant_unbond(100),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: token_contract.clone(),
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: Uint128::new(100)
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(BankMsg::Send {
+                to_address: bob.clone(),
+                amount: vec![coin(99, "uluna")],
+            }),
+        ]
+    );
+    assert!(res.attributes.contains(&attr("fee", "1")));
+
+    let after_unbond = buffer(deps.as_ref());
+    assert_eq!(after_unbond.balance, Uint128::new(301));
+    assert_eq!(after_unbond.lent, Uint128::new(99));
+    assert_eq!(after_unbond.available, Uint128::new(101));
+
+    // the buffer gets the unbonded coins once the batch is released
+    deps.querier.with_token_balances(&[(
+        &token_contract,
+        &[(&bob, &bond_amount.checked_sub(Uint128::new(100)).unwrap())],
+    )]);
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(31);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(&bob, &[]),
+        ExecuteMsg::SubmitBatch {},
+    )
+    .unwrap();
+
+    deps.querier.with_native_balances(&[(
+        MOCK_CONTRACT_ADDR.to_string(),
+        Coin {
+            denom: "uluna".to_string(),
+            amount: Uint128::new(401),
+        },
+    )]);
+    env.block.time = env.block.time.plus_seconds(3);
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info(&bob, &[]),
+        ExecuteMsg::ProcessBatches { limit: None },
+    )
+    .unwrap();
+
+    let replenished = buffer(deps.as_ref());
+    assert_eq!(replenished.balance, Uint128::new(401));
+    assert_eq!(replenished.lent, Uint128::zero());
+    assert_eq!(replenished.available, Uint128::new(200));
+
+    // the owner takes the coins of the buffer back
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(&owner, &[]),
+        ExecuteMsg::WithdrawLiquidityBuffer {
+            amount: Uint128::new(402),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InsufficientLiquidity {
+            available: Uint128::new(401)
+        }
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(&owner, &[]),
+        ExecuteMsg::WithdrawLiquidityBuffer {
+            amount: Uint128::new(401),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: owner,
+            amount: vec![coin(401, "uluna")],
+        })]
+    );
+}
+
+/// Covers the status and timing of the batches along their lifecycle.
+#[test]
+pub fn proper_unbond_batches() {
+    let mut deps = dependencies(&[]);
+    let validator = sample_validator(DEFAULT_VALIDATOR.to_string());
+    set_validator_mock(&mut deps.querier);
+
+    let token_contract = "token".to_string();
+    init(
+        deps.borrow_mut(),
+        "owner1".to_string(),
+        "reward".to_string(),
+        token_contract.clone(),
+        validator.address.clone(),
+    );
+    do_register_validator(deps.as_mut(), validator.clone());
+
+    let bob = "bob".to_string();
+    let bond_amount = Uint128::new(1000u128);
+    do_bond(deps.as_mut(), bob.clone(), bond_amount, validator.clone());
+    set_delegation(&mut deps.querier, validator, bond_amount.u128(), "uluna");
+    deps.querier
+        .with_token_balances(&[(&token_contract, &[(&bob, &bond_amount)])]);
+
+    let start = mock_env().block.time.seconds();
+    do_unbond(
+        deps.as_mut(),
+        bob.clone(),
+        mock_env(),
+        mock_info(&token_contract, &[]),
+        Uint128::new(100),
+    );
+    deps.querier.with_token_balances(&[(
+        &token_contract,
+        &[(&bob, &bond_amount.checked_sub(Uint128::new(100)).unwrap())],
+    )]);
+
+    let batches = |deps: Deps, env: Env, start_from: Option<u64>| -> Vec<UnbondBatch> {
+        let res: UnbondBatchesResponse = from_binary(
+            &query(
+                deps,
+                env,
+                QueryMsg::UnbondBatches {
+                    start_from,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        res.batches
+    };
+
+    let open = UnbondBatch {
+        batch_id: 1,
+        status: UnbondBatchStatus::Open,
+        requested: Uint128::new(100),
+        submission_time: None,
+        next_epoch_time: Some(start + 31),
+        expected_release_time: start + 31 + 2,
+        expected_amount: Uint128::new(100),
+        received_amount: None,
+        slashed_amount: None,
+    };
+    assert_eq!(batches(deps.as_ref(), mock_env(), None), vec![open.clone()]);
+
+    // the epoch period has passed
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(31);
+    assert_eq!(
+        batches(deps.as_ref(), env.clone(), None),
+        vec![UnbondBatch {
+            status: UnbondBatchStatus::PendingUndelegation,
+            ..open.clone()
+        }]
+    );
+
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(&bob, &[]),
+        ExecuteMsg::SubmitBatch {},
+    )
+    .unwrap();
+    let unbonding = UnbondBatch {
+        batch_id: 1,
+        status: UnbondBatchStatus::Unbonding,
+        requested: Uint128::new(100),
+        submission_time: Some(start + 31),
+        next_epoch_time: None,
+        expected_release_time: start + 31 + 2,
+        expected_amount: Uint128::new(100),
+        received_amount: None,
+        slashed_amount: None,
+    };
+    let next = UnbondBatch {
+        batch_id: 2,
+        status: UnbondBatchStatus::Open,
+        requested: Uint128::zero(),
+        submission_time: None,
+        next_epoch_time: Some(start + 31 + 31),
+        expected_release_time: start + 31 + 31 + 2,
+        expected_amount: Uint128::zero(),
+        received_amount: None,
+        slashed_amount: None,
+    };
+    assert_eq!(
+        batches(deps.as_ref(), env.clone(), None),
+        vec![unbonding, next.clone()]
+    );
+
+    // the batch lost 10 to slashing while unbonding
+    deps.querier.with_native_balances(&[(
+        MOCK_CONTRACT_ADDR.to_string(),
+        Coin {
+            denom: "uluna".to_string(),
+            amount: Uint128::new(90),
+        },
+    )]);
+    env.block.time = env.block.time.plus_seconds(3);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(&bob, &[]),
+        ExecuteMsg::ProcessBatches { limit: None },
+    )
+    .unwrap();
+    let released = batches(deps.as_ref(), env.clone(), None);
+    assert_eq!(released[0].status, UnbondBatchStatus::Released);
+    // the release rounds the slashed amount up
+    assert_eq!(released[0].received_amount, Some(Uint128::new(89)));
+    assert_eq!(released[0].slashed_amount, Some(Uint128::new(11)));
+
+    // the current batch only shows up after the submitted ones
+    assert_eq!(batches(deps.as_ref(), env.clone(), Some(1)), vec![next]);
+    assert_eq!(batches(deps.as_ref(), env, Some(2)), vec![]);
+}
+
+/// Covers the position of a user gathered across the contracts.
+#[test]
+pub fn proper_position() {
+    let mut deps = dependencies(&[]);
+    let validator = sample_validator(DEFAULT_VALIDATOR.to_string());
+    set_validator_mock(&mut deps.querier);
+
+    let token_contract = "token".to_string();
+    init(
+        deps.borrow_mut(),
+        "owner1".to_string(),
+        MOCK_REWARD_CONTRACT_ADDR.to_string(),
+        token_contract.clone(),
+        validator.address.clone(),
+    );
+    do_register_validator(deps.as_mut(), validator.clone());
+
+    let bob = "bob".to_string();
+    do_bond(
+        deps.as_mut(),
+        bob.clone(),
+        Uint128::new(1000),
+        validator.clone(),
+    );
+    set_delegation(&mut deps.querier, validator.clone(), 1000, "uluna");
+    deps.querier
+        .with_token_balances(&[(&token_contract, &[(&bob, &Uint128::new(1000))])]);
+
+    // a request in a submitted batch and another one in the current batch
+    let start = mock_env().block.time.seconds();
+    do_unbond(
+        deps.as_mut(),
+        bob.clone(),
+        mock_env(),
+        mock_info(&token_contract, &[]),
+        Uint128::new(100),
+    );
+    deps.querier
+        .with_token_balances(&[(&token_contract, &[(&bob, &Uint128::new(900))])]);
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(31);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(&bob, &[]),
+        ExecuteMsg::SubmitBatch {},
+    )
+    .unwrap();
+    set_delegation(&mut deps.querier, validator, 900, "uluna");
+
+    env.block.time = env.block.time.plus_seconds(1);
+    do_unbond(
+        deps.as_mut(),
+        bob.clone(),
+        env.clone(),
+        mock_info(&token_contract, &[]),
+        Uint128::new(100),
+    );
+    deps.querier
+        .with_token_balances(&[(&token_contract, &[(&bob, &Uint128::new(800))])]);
+    let mir = RewardAssetAmount {
+        asset: RewardAsset::Token {
+            contract_addr: "mir".to_string(),
+        },
+        amount: Uint128::new(7),
+    };
+    deps.querier.with_accrued_rewards(&[(
+        &bob,
+        AccruedRewardsResponse {
+            rewards: Uint128::new(42),
+            asset_rewards: vec![mir.clone()],
+        },
+    )]);
+
+    env.block.time = env.block.time.plus_seconds(2);
+    let position: PositionResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::Position {
+                address: bob.clone(),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        position,
+        PositionResponse {
+            address: bob,
+            balance: Uint128::new(800),
+            exchange_rate: Decimal::one(),
+            balance_value: Uint128::new(800),
+            unbond_requests: vec![
+                PositionUnbondRequest {
+                    amount: Uint128::new(100),
+                    batch: UnbondBatch {
+                        batch_id: 1,
+                        status: UnbondBatchStatus::Unbonding,
+                        requested: Uint128::new(100),
+                        submission_time: Some(start + 31),
+                        next_epoch_time: None,
+                        expected_release_time: start + 31 + 2,
+                        expected_amount: Uint128::new(100),
+                        received_amount: None,
+                        slashed_amount: None,
+                    },
+                },
+                PositionUnbondRequest {
+                    amount: Uint128::new(100),
+                    batch: UnbondBatch {
+                        batch_id: 2,
+                        status: UnbondBatchStatus::Open,
+                        requested: Uint128::new(100),
+                        submission_time: None,
+                        next_epoch_time: Some(start + 31 + 31),
+                        expected_release_time: start + 31 + 31 + 2,
+                        expected_amount: Uint128::new(100),
+                        received_amount: None,
+                        slashed_amount: None,
+                    },
+                },
+            ],
+            withdrawable: Uint128::new(100),
+            accrued_rewards: Uint128::new(42),
+            asset_rewards: vec![mir],
+            unbond_tickets: vec![],
+        }
+    );
+
+    // the requests are paged, the withdrawable coins are not
+    let position: PositionResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::Position {
+                address: "bob".to_string(),
+                start_after: None,
+                limit: Some(1),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(position.unbond_requests.len(), 1);
+    assert_eq!(position.unbond_requests[0].batch.batch_id, 1);
+    assert_eq!(position.withdrawable, Uint128::new(100));
+    let position: PositionResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env,
+            QueryMsg::Position {
+                address: "bob".to_string(),
+                start_after: Some(1),
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(position.unbond_requests.len(), 1);
+    assert_eq!(position.unbond_requests[0].batch.batch_id, 2);
+}
+
+/// Covers the record of the slashings found by the checks and their validators.
+#[test]
+pub fn proper_slashing_history() {
+    let mut deps = dependencies(&[]);
+    let validator = sample_validator(DEFAULT_VALIDATOR.to_string());
+    let validator2 = sample_validator(DEFAULT_VALIDATOR2.to_string());
+    set_validator_mock(&mut deps.querier);
+
+    let owner = "owner1".to_string();
+    let token_contract = "token".to_string();
+    init(
+        deps.borrow_mut(),
+        owner.clone(),
+        "reward".to_string(),
+        token_contract.clone(),
+        validator.address.clone(),
+    );
+    do_register_validator(deps.as_mut(), validator.clone());
+    do_register_validator(deps.as_mut(), validator2.clone());
+
+    // the instantiation delegated 1000000 to the first validator
+    let bob = "bob".to_string();
+    do_bond(
+        deps.as_mut(),
+        bob.clone(),
+        Uint128::new(1000),
+        validator.clone(),
+    );
+    do_bond(
+        deps.as_mut(),
+        bob.clone(),
+        Uint128::new(500),
+        validator2.clone(),
+    );
+    deps.querier.with_token_balances(&[(
+        &token_contract,
+        &[
+            (&bob, &Uint128::new(1500)),
+            (&owner, &Uint128::new(1000000)),
+        ],
+    )]);
+
+    let validators = [validator.clone(), validator2.clone()];
+    set_delegation_query(
+        &mut deps.querier,
+        &[
+            sample_delegation(validator.address.clone(), coin(1000900, "uluna")),
+            sample_delegation(validator2.address.clone(), coin(500, "uluna")),
+        ],
+        &validators,
+    );
+
+    let mut env = mock_env();
+    env.block.height += 1;
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(&bob, &[]),
+        CheckSlashing {},
+    )
+    .unwrap();
+    let exchange_rate_after = Decimal::from_ratio(1001400u128, 1001500u128);
+    assert_eq!(
+        res.events,
+        vec![Event::new("slashing").add_attributes(vec![
+            attr("id", "1"),
+            attr("bonded_before", "1001500"),
+            attr("bonded_after", "1001400"),
+            attr("exchange_rate_before", "1"),
+            attr("exchange_rate_after", exchange_rate_after.to_string()),
+            attr("validator", DEFAULT_VALIDATOR),
+            attr("validator_loss", "100"),
+        ])]
+    );
+
+    let first = SlashingEvent {
+        id: 1,
+        time: env.block.time.seconds(),
+        height: env.block.height,
+        bonded_before: Uint128::new(1001500),
+        bonded_after: Uint128::new(1001400),
+        exchange_rate_before: Decimal::one(),
+        exchange_rate_after,
+        validators: vec![(DEFAULT_VALIDATOR.to_string(), Uint128::new(100))],
+    };
+    let history = |deps: Deps, start_after: Option<u64>| -> Vec<SlashingEvent> {
+        let res: SlashingHistoryResponse = from_binary(
+            &query(
+                deps,
+                mock_env(),
+                QueryMsg::SlashingHistory {
+                    start_after,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        res.history
+    };
+    assert_eq!(history(deps.as_ref(), None), vec![first.clone()]);
+
+    // nothing new is found by the next check
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(&bob, &[]),
+        CheckSlashing {},
+    )
+    .unwrap();
+    assert!(res.events.is_empty());
+
+    // the second validator is slashed after the first check
+    set_delegation_query(
+        &mut deps.querier,
+        &[
+            sample_delegation(validator.address.clone(), coin(1000900, "uluna")),
+            sample_delegation(validator2.address.clone(), coin(450, "uluna")),
+        ],
+        &validators,
+    );
+    execute(deps.as_mut(), env, mock_info(&bob, &[]), CheckSlashing {}).unwrap();
+
+    let slashings = history(deps.as_ref(), None);
+    assert_eq!(slashings.len(), 2);
+    assert_eq!(slashings[0], first);
+    assert_eq!(slashings[1].bonded_after, Uint128::new(1001350));
+    assert_eq!(
+        slashings[1].validators,
+        vec![(DEFAULT_VALIDATOR2.to_string(), Uint128::new(50))]
+    );
+    assert_eq!(history(deps.as_ref(), Some(1)), vec![slashings[1].clone()]);
+}
+
+/// Covers the exchange rate checkpoints and the averages over them.
+#[test]
+pub fn proper_exchange_rate_twap() {
+    let mut deps = dependencies(&[]);
+    let validator = sample_validator(DEFAULT_VALIDATOR.to_string());
+    set_validator_mock(&mut deps.querier);
+
+    let owner = "owner1".to_string();
+    let token_contract = "token".to_string();
+    init(
+        deps.borrow_mut(),
+        owner.clone(),
+        "reward".to_string(),
+        token_contract.clone(),
+        validator.address.clone(),
+    );
+    do_register_validator(deps.as_mut(), validator.clone());
+
+    // the instantiation bonded for the owner
+    deps.querier
+        .with_token_balances(&[(&token_contract, &[(&owner, &Uint128::new(1000000))])]);
+    let bob = "bob".to_string();
+    do_bond(
+        deps.as_mut(),
+        bob.clone(),
+        Uint128::new(1000),
+        validator.clone(),
+    );
+    deps.querier.with_token_balances(&[(
+        &token_contract,
+        &[
+            (&bob, &Uint128::new(1000)),
+            (&owner, &Uint128::new(1000000)),
+        ],
+    )]);
+
+    // a slashing moves the rate 100 seconds after the instantiation
+    let start = mock_env().block.time.seconds();
+    set_delegation(&mut deps.querier, validator, 1000500, "uluna");
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(100);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(&bob, &[]),
+        CheckSlashing {},
+    )
+    .unwrap();
+    let slashed_rate = Decimal::from_ratio(1000500u128, 1001000u128);
+
+    env.block.time = env.block.time.plus_seconds(100);
+    let rate_at = |deps: Deps, time: u64| {
+        query(deps, env.clone(), QueryMsg::ExchangeRateAt { time })
+            .map(|res| from_binary::<ExchangeRateAtResponse>(&res).unwrap())
+    };
+    assert_eq!(
+        rate_at(deps.as_ref(), start + 50).unwrap(),
+        ExchangeRateAtResponse {
+            exchange_rate: Decimal::one(),
+            checkpoint_time: start,
+        }
+    );
+    assert_eq!(
+        rate_at(deps.as_ref(), start + 150).unwrap(),
+        ExchangeRateAtResponse {
+            exchange_rate: slashed_rate,
+            checkpoint_time: start + 100,
+        }
+    );
+    assert_eq!(
+        rate_at(deps.as_ref(), start - 1).unwrap_err(),
+        ContractError::NoRateCheckpoint { time: start - 1 }
+    );
+
+    let twap = |deps: Deps, window: u64| -> ExchangeRateTwapResponse {
+        from_binary(&query(deps, env.clone(), QueryMsg::ExchangeRateTwap { window }).unwrap())
+            .unwrap()
+    };
+    assert_eq!(
+        twap(deps.as_ref(), 100),
+        ExchangeRateTwapResponse {
+            start_time: start + 100,
+            end_time: start + 200,
+            twap: slashed_rate,
+        }
+    );
+
+    // half of the window at each rate
+    let precision = Uint128::new(1_000_000_000_000_000_000u128);
+    let average = Decimal::from_ratio(
+        precision * Decimal::one() + precision * slashed_rate,
+        precision.checked_mul(Uint128::new(2)).unwrap(),
+    );
+    assert_eq!(
+        twap(deps.as_ref(), 200),
+        ExchangeRateTwapResponse {
+            start_time: start,
+            end_time: start + 200,
+            twap: average,
+        }
+    );
+
+    // the window can not reach before the first checkpoint
+    assert_eq!(twap(deps.as_ref(), 1000).start_time, start);
+    assert_eq!(twap(deps.as_ref(), 1000).twap, average);
+}
+
+/// Covers the compound reward mode, where the rewards are restaked
+/// and the exchange rate grows instead of being distributed.
+#[test]
+pub fn proper_compound_reward_mode() {
+    let mut deps = dependencies(&[]);
+    let validator = sample_validator(DEFAULT_VALIDATOR.to_string());
+    set_validator_mock(&mut deps.querier);
+
+    let owner = "owner1".to_string();
+    let reward_contract = "reward".to_string();
+    let token_contract = "token".to_string();
+    init_with_mode(
+        deps.borrow_mut(),
+        owner.clone(),
+        reward_contract.clone(),
+        token_contract.clone(),
+        validator.address.clone(),
+        RewardMode::Compound,
+    );
+    do_register_validator(deps.as_mut(), validator.clone());
+
+    let params: Parameters =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Parameters {}).unwrap()).unwrap();
+    assert_eq!(params.reward_mode, RewardMode::Compound);
+
+    let bob = "bob".to_string();
+    let owner_balance = Uint128::new(1000000u128);
+    deps.querier
+        .with_token_balances(&[(&token_contract, &[(&owner, &owner_balance)])]);
+    do_bond(
+        deps.as_mut(),
+        bob.clone(),
+        Uint128::new(1000),
+        validator.clone(),
+    );
+    deps.querier.with_token_balances(&[(
+        &token_contract,
+        &[(&owner, &owner_balance), (&bob, &Uint128::new(1000))],
+    )]);
+    set_delegation(&mut deps.querier, validator.clone(), 1001000, "uluna");
+
+    // the rewards are swapped to the underlying denom and sent back instead of distributed
+    deps.querier
+        .with_native_balances(&[(MOCK_CONTRACT_ADDR.to_string(), coin(0, "uluna"))]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(&bob, &[]),
+        ExecuteMsg::UpdateGlobalIndex {
+            airdrop_hooks: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(DistributionMsg::WithdrawDelegatorReward {
+                validator: validator.address.clone(),
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: reward_contract.clone(),
+                msg: to_binary(&SwapToUnderlying {
+                    underlying_denom: "uluna".to_string(),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: reward_contract.clone(),
+                msg: to_binary(&SendUnderlying {
+                    underlying_denom: "uluna".to_string(),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: reward_contract,
+                msg: to_binary(&UpdateRewardAssetIndexes {}).unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&ExecuteMsg::RestakeHook {
+                    prev_balance: Uint128::zero(),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+
+    // only the hub can restake
+    let restake = ExecuteMsg::RestakeHook {
+        prev_balance: Uint128::zero(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(&bob, &[]),
+        restake.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // 100100 of rewards are restaked, 1101100 / 1001000
+    deps.querier
+        .with_native_balances(&[(MOCK_CONTRACT_ADDR.to_string(), coin(100100, "uluna"))]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        restake,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(StakingMsg::Delegate {
+            validator: validator.address.clone(),
+            amount: coin(100100, "uluna"),
+        })]
+    );
+    assert!(res.attributes.contains(&attr("restaked", "100100")));
+
+    let state: StateResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap()).unwrap();
+    assert_eq!(state.total_bond_amount, Uint128::new(1101100));
+    assert_eq!(state.exchange_rate, Decimal::from_ratio(11u128, 10u128));
+    deps.querier
+        .with_native_balances(&[(MOCK_CONTRACT_ADDR.to_string(), coin(0, "uluna"))]);
+    set_delegation(&mut deps.querier, validator.clone(), 1101100, "uluna");
+
+    // a bond mints at the grown exchange rate, without any peg recovery fee
+    let simulated: SimulateBondResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateBond {
+                amount: Uint128::new(1100),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        simulated,
+        SimulateBondResponse {
+            minted: Uint128::new(1000),
+            peg_recovery_fee: Uint128::zero(),
+            exchange_rate: Decimal::from_ratio(11u128, 10u128),
+        }
+    );
+
+    // the unbond undelegates at the grown exchange rate
+    let alice = "alice".to_string();
+    do_unbond(
+        deps.as_mut(),
+        alice.clone(),
+        mock_env(),
+        mock_info(&token_contract, &[]),
+        Uint128::new(1000),
+    );
+    deps.querier
+        .with_token_balances(&[(&token_contract, &[(&owner, &owner_balance)])]);
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(31);
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info(&bob, &[]),
+        ExecuteMsg::SubmitBatch {},
+    )
+    .unwrap();
+    assert!(res.messages.contains(&SubMsg::new(StakingMsg::Undelegate {
+        validator: validator.address.clone(),
+        amount: coin(1100, "uluna"),
+    })));
+
+    // the peg recovery fee still applies once a slashing drops the rate below the threshold
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(&owner, &[]),
+        ExecuteMsg::UpdateParams {
+            epoch_period: None,
+            unbonding_period: None,
+            peg_recovery_fee: Some(Decimal::percent(1)),
+            er_threshold: Some(Decimal::one()),
+            delegation_strategy: None,
+        },
+    )
+    .unwrap();
+    set_delegation(&mut deps.querier, validator, 900000, "uluna");
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(&bob, &[]),
+        ExecuteMsg::CheckSlashing {},
+    )
+    .unwrap();
+    let simulated: SimulateBondResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateBond {
+                amount: Uint128::new(900),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(simulated.minted, Uint128::new(990));
+    assert_eq!(simulated.peg_recovery_fee, Uint128::new(10));
+}
+
+/// Covers the bond for another recipient, who receives the minted bAsset.
+#[test]
+pub fn proper_bond_for() {
+    let mut deps = dependencies(&[]);
+    let validator = sample_validator(DEFAULT_VALIDATOR.to_string());
+    set_validator_mock(&mut deps.querier);
+
+    let token_contract = "token".to_string();
+    init(
+        deps.borrow_mut(),
+        "owner1".to_string(),
+        "reward".to_string(),
+        token_contract.clone(),
+        validator.address.clone(),
+    );
+    do_register_validator(deps.as_mut(), validator.clone());
+    deps.querier.with_token_balances(&[(
+        &token_contract,
+        &[(&"owner1".to_string(), &INITIAL_DEPOSIT_AMOUNT)],
+    )]);
+    set_delegation(
+        &mut deps.querier,
+        validator.clone(),
+        INITIAL_DEPOSIT_AMOUNT.u128(),
+        "uluna",
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("reward", &[coin(100, "uluna")]),
+        ExecuteMsg::BondFor {
+            recipient: "alice".to_string(),
+            validator: Some(validator.address.clone()),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(StakingMsg::Delegate {
+                validator: validator.address,
+                amount: coin(100, "uluna"),
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: token_contract,
+                msg: to_binary(&Cw20ExecuteMsg::Mint {
+                    recipient: "alice".to_string(),
+                    amount: Uint128::new(100),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+    assert!(res.attributes.contains(&attr("from", "reward")));
+    assert!(res.attributes.contains(&attr("recipient", "alice")));
+}
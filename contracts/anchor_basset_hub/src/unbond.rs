@@ -1,125 +1,112 @@
-use crate::contract::{query_total_issued, slashing};
+use crate::buffer::replenish_liquidity_buffer;
+use crate::contract::{query_total_issued, slashed_state, slashing};
+use crate::pause::assert_not_paused;
 use crate::state::{
-    get_finished_amount, get_unbond_batches, read_unbond_history, remove_unbond_wait_list,
-    store_unbond_history, store_unbond_wait_list, CONFIG, CURRENT_BATCH, PARAMETERS, STATE,
+    all_unbond_history, decrease_expected_delegation, decrease_unbond_wait_list,
+    may_read_unbond_history, read_unbond_history, read_unbond_wait_list, record_exchange_rate,
+    store_unbond_history, store_unbond_wait_list, take_finished_amount, CurrentBatch, Parameters,
+    ReleaseRound, WithdrawRateCursor, CONFIG, CURRENT_BATCH, DEFAULT_LIMIT, LAST_TICKET_ID,
+    MAX_LIMIT, PARAMETERS, STATE, UNBOND_TICKETS, UNBOND_TICKET_CONTRACT, WITHDRAW_RATE_CURSOR,
 };
-use basset::hub::{State, UnbondHistory};
+use crate::strategy::pick_unbond_validators;
+use basset::contract_error::ContractError;
+use basset::hub::{
+    PausableOperation, SimulateUnbondResponse, State, UnbondBatch, UnbondBatchStatus,
+    UnbondBatchesResponse, UnbondHistory, UnbondTicketResponse,
+};
+use basset::unbond_ticket::{ExecuteMsg as UnbondTicketMsg, UnbondTicket};
 use cosmwasm_std::{
-    attr, coin, coins, to_binary, BankMsg, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo,
-    Response, StakingMsg, StdError, StdResult, Storage, Uint128, WasmMsg,
+    attr, coin, coins, to_binary, Addr, BankMsg, CosmosMsg, Decimal, Deps, DepsMut, Env,
+    MessageInfo, Response, StakingMsg, StdResult, Storage, Uint128, WasmMsg,
 };
 use cw20::Cw20ExecuteMsg;
-use rand::{Rng, SeedableRng, XorShiftRng};
+use cw721::{Cw721QueryMsg, OwnerOfResponse};
 use signed_integer::SignedInt;
 
+// bounds of the batches processed per call
+const DEFAULT_PROCESS_LIMIT: u32 = 10;
+const MAX_PROCESS_LIMIT: u32 = 30;
+
 /// This message must be call by receive_cw20
 /// This message will undelegate coin and burn basset token
+/// The request is recorded for `sender`
 pub(crate) fn execute_unbond(
     mut deps: DepsMut,
     env: Env,
     _info: MessageInfo,
     amount: Uint128,
     sender: String,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     // Read params
     let params = PARAMETERS.load(deps.storage)?;
     let epoch_period = params.epoch_period;
-    let threshold = params.er_threshold;
-    let recovery_fee = params.peg_recovery_fee;
 
     let mut current_batch = CURRENT_BATCH.load(deps.storage)?;
 
     // Check slashing, update state, and calculate the new exchange rate.
-    slashing(&mut deps, env.clone())?;
+    let slashing_event = slashing(&mut deps, env.clone())?;
 
     let mut state = STATE.load(deps.storage)?;
 
-    let mut total_supply = query_total_issued(deps.as_ref()).unwrap_or_default();
+    let total_supply = query_total_issued(deps.as_ref()).unwrap_or_default();
 
     // Collect all the requests within a epoch period
-    // Apply peg recovery fee
-    let amount_with_fee: Uint128;
-    if state.exchange_rate < threshold {
-        let max_peg_fee = amount * recovery_fee;
-        let required_peg_fee = ((total_supply + current_batch.requested_with_fee)
-            .checked_sub(state.total_bond_amount))?;
-        let peg_fee = Uint128::min(max_peg_fee, required_peg_fee);
-        amount_with_fee = (amount.checked_sub(peg_fee))?;
-    } else {
-        amount_with_fee = amount;
-    }
-    current_batch.requested_with_fee += amount_with_fee;
-
-    store_unbond_wait_list(
-        deps.storage,
-        current_batch.id,
-        sender.clone(),
-        amount_with_fee,
+    // Apply peg recovery fee and update the exchange rate
+    let (amount_with_fee, _) = apply_unbond(
+        &mut state,
+        &params,
+        total_supply,
+        &mut current_batch,
+        amount,
     )?;
 
-    total_supply = (total_supply.checked_sub(amount))
-        .expect("the requested can not be more than the total supply");
-
-    // Update exchange rate
-    state.update_exchange_rate(total_supply, current_batch.requested_with_fee);
-
-    let current_time = env.block.time.seconds();
-    let passed_time = current_time - state.last_unbonded_time;
+    // With unbond tickets, the request is held by a newly minted ticket.
+    let mut ticket_msgs: Vec<CosmosMsg> = vec![];
+    let mut ticket_attrs = vec![];
+    match UNBOND_TICKET_CONTRACT.may_load(deps.storage)? {
+        Some(ticket_contract) => {
+            let last_ticket_id = LAST_TICKET_ID.may_load(deps.storage)?.unwrap_or_default();
+            LAST_TICKET_ID.save(deps.storage, &(last_ticket_id + 1))?;
+            let ticket_id = (last_ticket_id + 1).to_string();
+            let ticket = UnbondTicket {
+                batch_id: current_batch.id,
+                amount: amount_with_fee,
+                applied_exchange_rate: state.exchange_rate,
+            };
+            UNBOND_TICKETS.save(deps.storage, &ticket_id, &ticket)?;
+            ticket_msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: deps.api.addr_humanize(&ticket_contract)?.to_string(),
+                msg: to_binary(&UnbondTicketMsg::Mint {
+                    token_id: ticket_id.clone(),
+                    owner: sender.clone(),
+                    ticket,
+                })?,
+                funds: vec![],
+            }));
+            ticket_attrs.push(attr("ticket_id", ticket_id));
+        }
+        None => store_unbond_wait_list(
+            deps.storage,
+            current_batch.id,
+            sender.clone(),
+            amount_with_fee,
+        )?,
+    }
 
     let mut messages: Vec<CosmosMsg> = vec![];
 
     // If the epoch period is passed, the undelegate message would be sent.
-    if passed_time > epoch_period {
-        // Apply the current exchange rate.
-        let undelegation_amount = current_batch.requested_with_fee * state.exchange_rate;
-
-        // the contract must stop if
-        if undelegation_amount == Uint128::new(1) {
-            return Err(StdError::generic_err(
-                "Burn amount must be greater than 1 ubluna",
-            ));
-        }
-
-        let delegator = env.contract.address;
-
-        let block_height = env.block.height;
-
-        // Send undelegated requests to possibly more than one validators
-        let mut undelegated_msgs = pick_validator(
-            deps.as_ref(),
-            undelegation_amount,
-            delegator.to_string(),
-            block_height,
-        )?;
-
-        messages.append(&mut undelegated_msgs);
-
-        state.total_bond_amount = (state.total_bond_amount.checked_sub(undelegation_amount))
-            .expect("undelegation amount can not be more than stored total bonded amount");
-
-        // Store history for withdraw unbonded
-        let history = UnbondHistory {
-            batch_id: current_batch.id,
-            time: env.block.time.seconds(),
-            amount: current_batch.requested_with_fee,
-            applied_exchange_rate: state.exchange_rate,
-            withdraw_rate: state.exchange_rate,
-            released: false,
-        };
-        store_unbond_history(deps.storage, current_batch.id, history)?;
-        // batch info must be updated to new batch
-        current_batch.id += 1;
-        current_batch.requested_with_fee = Uint128::zero();
-
-        // state.last_unbonded_time must be updated to the current block time
-        state.last_unbonded_time = env.block.time.seconds();
+    if is_batch_ready(&state, epoch_period, env.block.time.seconds()) {
+        messages = submit_batch(deps.branch(), &env, &mut state, &mut current_batch)?;
     }
+    messages.extend(ticket_msgs);
 
     // Store the new requested_with_fee or id in the current batch
     CURRENT_BATCH.save(deps.storage, &current_batch)?;
 
     // Store state's new exchange rate
     STATE.save(deps.storage, &state)?;
+    record_exchange_rate(deps.storage, env.block.time.seconds(), state.exchange_rate)?;
 
     // Send Burn message to token contract
     let config = CONFIG.load(deps.storage)?;
@@ -136,20 +123,413 @@ pub(crate) fn execute_unbond(
         funds: vec![],
     }));
 
-    Ok(Response::new().add_messages(messages).add_attributes(vec![
-        attr("action", "burn"),
-        attr("from", sender),
-        attr("burnt_amount", amount),
-        attr("unbonded_amount", amount_with_fee),
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            attr("action", "burn"),
+            attr("from", sender),
+            attr("burnt_amount", amount),
+            attr("unbonded_amount", amount_with_fee),
+        ])
+        .add_attributes(ticket_attrs)
+        .add_events(slashing_event))
+}
+
+/// Submit the current batch once the epoch period has passed.
+/// Permissionless, so requests do not wait for a later unbond to be undelegated.
+pub fn execute_submit_batch(
+    mut deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage, PausableOperation::Unbond)?;
+
+    let params = PARAMETERS.load(deps.storage)?;
+    let mut current_batch = CURRENT_BATCH.load(deps.storage)?;
+
+    let state = STATE.load(deps.storage)?;
+    if !is_batch_ready(&state, params.epoch_period, env.block.time.seconds()) {
+        return Err(ContractError::BatchNotReady {
+            ready_at: state.last_unbonded_time + params.epoch_period + 1,
+        });
+    }
+    if current_batch.requested_with_fee.is_zero() {
+        return Err(ContractError::EmptyBatch {});
+    }
+
+    // Check slashing, update state, and calculate the new exchange rate.
+    let slashing_event = slashing(&mut deps, env.clone())?;
+
+    let mut state = STATE.load(deps.storage)?;
+    let total_supply = query_total_issued(deps.as_ref()).unwrap_or_default();
+    state.update_exchange_rate(total_supply, current_batch.requested_with_fee);
+
+    let batch_id = current_batch.id;
+    let messages = submit_batch(deps.branch(), &env, &mut state, &mut current_batch)?;
+
+    CURRENT_BATCH.save(deps.storage, &current_batch)?;
+    STATE.save(deps.storage, &state)?;
+    record_exchange_rate(deps.storage, env.block.time.seconds(), state.exchange_rate)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            attr("action", "submit_batch"),
+            attr("batch_id", batch_id.to_string()),
+        ])
+        .add_events(slashing_event))
+}
+
+/// The current batch can be submitted once the epoch period has passed since the last one.
+pub(crate) fn is_batch_ready(state: &State, epoch_period: u64, now: u64) -> bool {
+    now - state.last_unbonded_time > epoch_period
+}
+
+/// Undelegate the current batch at the current exchange rate, record its history
+/// and open the next batch. The caller stores the updated state and batch.
+pub(crate) fn submit_batch(
+    mut deps: DepsMut,
+    env: &Env,
+    state: &mut State,
+    current_batch: &mut CurrentBatch,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    // Apply the current exchange rate.
+    let undelegation_amount = current_batch.requested_with_fee * state.exchange_rate;
+
+    // the contract must stop if
+    if undelegation_amount == Uint128::new(1) {
+        return Err(ContractError::BatchTooSmall {});
+    }
+
+    let delegator = env.contract.address.clone();
+
+    // Send undelegated requests to possibly more than one validators
+    let messages = pick_validator(deps.branch(), undelegation_amount, delegator)?;
+
+    state.total_bond_amount = (state.total_bond_amount.checked_sub(undelegation_amount))
+        .expect("undelegation amount can not be more than stored total bonded amount");
+
+    // Store history for withdraw unbonded
+    let history = UnbondHistory {
+        batch_id: current_batch.id,
+        time: env.block.time.seconds(),
+        amount: current_batch.requested_with_fee,
+        applied_exchange_rate: state.exchange_rate,
+        withdraw_rate: state.exchange_rate,
+        released: false,
+    };
+    store_unbond_history(deps.storage, current_batch.id, history)?;
+    // batch info must be updated to new batch
+    current_batch.id += 1;
+    current_batch.requested_with_fee = Uint128::zero();
+
+    // state.last_unbonded_time must be updated to the current block time
+    state.last_unbonded_time = env.block.time.seconds();
+
+    Ok(messages)
+}
+
+/// Cancel `amount` of the unbond request of the sender in the current batch.
+/// The bAsset is minted back to the sender at the rate it was requested,
+/// so the exchange rate does not move.
+pub fn execute_cancel_unbond(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage, PausableOperation::Unbond)?;
+
+    if amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let sender = info.sender.to_string();
+    let current_batch = CURRENT_BATCH.load(deps.storage)?;
+
+    let requested =
+        read_unbond_wait_list(deps.storage, current_batch.id, sender.clone()).unwrap_or_default();
+    if amount > requested {
+        return Err(ContractError::CancelExceedsRequest { requested });
+    }
+
+    decrease_unbond_wait_list(deps.storage, current_batch.id, sender.clone(), amount)?;
+    cancel_request(deps, env, current_batch, sender, amount)
+}
+
+/// Cancel the unbond request held by a ticket of the sender in the current batch
+/// and burn the ticket, the bAsset is minted back as with `execute_cancel_unbond`.
+pub fn execute_cancel_unbond_ticket(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    ticket_id: String,
+) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage, PausableOperation::Unbond)?;
+
+    let ticket_contract = unbond_ticket_contract(deps.as_ref())?;
+    assert_ticket_holder(deps.as_ref(), &ticket_contract, &ticket_id, &info.sender)?;
+
+    let current_batch = CURRENT_BATCH.load(deps.storage)?;
+    let ticket = UNBOND_TICKETS.load(deps.storage, &ticket_id)?;
+    if ticket.batch_id != current_batch.id {
+        return Err(ContractError::TicketBatchSubmitted { ticket_id });
+    }
+
+    UNBOND_TICKETS.remove(deps.storage, &ticket_id);
+    let res = cancel_request(
+        deps,
+        env,
+        current_batch,
+        info.sender.to_string(),
+        ticket.amount,
+    )?;
+    Ok(res
+        .add_message(burn_ticket_msg(&ticket_contract, ticket_id.clone())?)
+        .add_attribute("ticket_id", ticket_id))
+}
+
+/// Take `amount` out of the current batch and mint it back to `sender`.
+fn cancel_request(
+    mut deps: DepsMut,
+    env: Env,
+    mut current_batch: CurrentBatch,
+    sender: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    // Check slashing, update state, and calculate the new exchange rate.
+    let slashing_event = slashing(&mut deps, env.clone())?;
+
+    let mut state = STATE.load(deps.storage)?;
+    let total_supply = query_total_issued(deps.as_ref()).unwrap_or_default();
+
+    current_batch.requested_with_fee = current_batch.requested_with_fee.checked_sub(amount)?;
+    state.update_exchange_rate(total_supply + amount, current_batch.requested_with_fee);
+
+    CURRENT_BATCH.save(deps.storage, &current_batch)?;
+    STATE.save(deps.storage, &state)?;
+    record_exchange_rate(deps.storage, env.block.time.seconds(), state.exchange_rate)?;
+
+    // Mint the cancelled request back to the user
+    let config = CONFIG.load(deps.storage)?;
+    let token_address = deps.api.addr_humanize(
+        &config
+            .token_contract
+            .expect("the token contract must have been registered"),
+    )?;
+
+    let mint_msg = Cw20ExecuteMsg::Mint {
+        recipient: sender.clone(),
+        amount,
+    };
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: token_address.to_string(),
+            msg: to_binary(&mint_msg)?,
+            funds: vec![],
+        }))
+        .add_attributes(vec![
+            attr("action", "cancel_unbond"),
+            attr("from", sender),
+            attr("batch_id", current_batch.id.to_string()),
+            attr("minted", amount),
+        ])
+        .add_events(slashing_event))
+}
+
+/// Simulate an unbond of `amount` against the state the next unbond would see.
+pub fn query_simulate_unbond(
+    deps: Deps,
+    env: Env,
+    amount: Uint128,
+) -> StdResult<SimulateUnbondResponse> {
+    let params = PARAMETERS.load(deps.storage)?;
+    let mut current_batch = CURRENT_BATCH.load(deps.storage)?;
+    let mut state = slashed_state(deps, &env)?;
+    let total_supply = query_total_issued(deps).unwrap_or_default();
+
+    let (unbonded, peg_recovery_fee) = apply_unbond(
+        &mut state,
+        &params,
+        total_supply,
+        &mut current_batch,
+        amount,
+    )?;
+
+    Ok(SimulateUnbondResponse {
+        burned: amount,
+        unbonded,
+        peg_recovery_fee,
+        exchange_rate: state.exchange_rate,
+        estimated_amount: unbonded * state.exchange_rate,
+        estimated_release_time: estimated_release_time(&state, &params, env.block.time.seconds()),
+    })
+}
+
+/// Apply an unbond request of `amount` to the current batch and update the exchange rate.
+/// Return the amount added to the batch and the peg recovery fee deducted from it.
+pub(crate) fn apply_unbond(
+    state: &mut State,
+    params: &Parameters,
+    total_supply: Uint128,
+    current_batch: &mut CurrentBatch,
+    amount: Uint128,
+) -> StdResult<(Uint128, Uint128)> {
+    let mut peg_fee = Uint128::zero();
+    if state.exchange_rate < params.er_threshold {
+        let max_peg_fee = amount * params.peg_recovery_fee;
+        let required_peg_fee = ((total_supply + current_batch.requested_with_fee)
+            .checked_sub(state.total_bond_amount))?;
+        peg_fee = Uint128::min(max_peg_fee, required_peg_fee);
+    }
+    let amount_with_fee = (amount.checked_sub(peg_fee))?;
+    current_batch.requested_with_fee += amount_with_fee;
+
+    // the requested can not be more than the total supply
+    let total_supply = (total_supply.checked_sub(amount))?;
+    state.update_exchange_rate(total_supply, current_batch.requested_with_fee);
+
+    Ok((amount_with_fee, peg_fee))
+}
+
+/// Estimate when the batch that receives an unbond request at `now` is released.
+/// The batch is sent to the validators as soon as the epoch period has passed.
+fn estimated_release_time(state: &State, params: &Parameters, now: u64) -> u64 {
+    let submission_time = if is_batch_ready(state, params.epoch_period, now) {
+        now
+    } else {
+        state.last_unbonded_time + params.epoch_period + 1
+    };
+    submission_time + params.unbonding_period
+}
+
+/// Return the status of the submitted batches after `start_from`, followed by the current batch.
+pub fn query_unbond_batches(
+    deps: Deps,
+    env: Env,
+    start_from: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<UnbondBatchesResponse> {
+    let params = PARAMETERS.load(deps.storage)?;
+    let state = STATE.load(deps.storage)?;
+    let current_batch = CURRENT_BATCH.load(deps.storage)?;
+    let now = env.block.time.seconds();
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let mut batches: Vec<UnbondBatch> =
+        all_unbond_history(deps.storage, start_from, Some(limit as u32))?
+            .into_iter()
+            .map(|history| submitted_batch(&params, history))
+            .collect();
+
+    // the current batch comes after all the submitted ones
+    if batches.len() < limit && start_from.map_or(true, |id| id < current_batch.id) {
+        batches.push(open_batch(&state, &params, &current_batch, now));
+    }
+
+    Ok(UnbondBatchesResponse { batches })
+}
+
+/// Describe a batch that has been undelegated from its history.
+pub(crate) fn submitted_batch(params: &Parameters, history: UnbondHistory) -> UnbondBatch {
+    let expected_amount = history.amount * history.applied_exchange_rate;
+    let (status, received_amount, slashed_amount) = if history.released {
+        let received_amount = history.amount * history.withdraw_rate;
+        (
+            UnbondBatchStatus::Released,
+            Some(received_amount),
+            Some(expected_amount.saturating_sub(received_amount)),
+        )
+    } else {
+        (UnbondBatchStatus::Unbonding, None, None)
+    };
+    UnbondBatch {
+        batch_id: history.batch_id,
+        status,
+        requested: history.amount,
+        submission_time: Some(history.time),
+        next_epoch_time: None,
+        expected_release_time: history.time + params.unbonding_period,
+        expected_amount,
+        received_amount,
+        slashed_amount,
+    }
+}
+
+/// Describe the current batch, which is valued at the current exchange rate.
+pub(crate) fn open_batch(
+    state: &State,
+    params: &Parameters,
+    current_batch: &CurrentBatch,
+    now: u64,
+) -> UnbondBatch {
+    let status = if is_batch_ready(state, params.epoch_period, now) {
+        UnbondBatchStatus::PendingUndelegation
+    } else {
+        UnbondBatchStatus::Open
+    };
+    UnbondBatch {
+        batch_id: current_batch.id,
+        status,
+        requested: current_batch.requested_with_fee,
+        submission_time: None,
+        next_epoch_time: Some(state.last_unbonded_time + params.epoch_period + 1),
+        expected_release_time: estimated_release_time(state, params, now),
+        expected_amount: current_batch.requested_with_fee * state.exchange_rate,
+        received_amount: None,
+        slashed_amount: None,
+    }
+}
+
+/// Compute the withdraw rate of matured batches ahead of the withdrawals.
+/// Permissionless, so keepers can bound the work done in `WithdrawUnbonded`.
+pub fn execute_process_batches(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage, PausableOperation::WithdrawUnbonded)?;
+
+    let params = PARAMETERS.load(deps.storage)?;
+    let historical_time = env.block.time.seconds() - params.unbonding_period;
+
+    let hub_balance = deps
+        .querier
+        .query_balance(&env.contract.address, &*params.underlying_coin_denom)?
+        .amount;
+
+    let limit = limit
+        .unwrap_or(DEFAULT_PROCESS_LIMIT)
+        .min(MAX_PROCESS_LIMIT);
+    let released = process_withdraw_rate(deps.storage, historical_time, hub_balance, limit as u64)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "process_batches"),
+        attr("released_batches", released.to_string()),
     ]))
 }
 
 pub fn execute_withdraw_unbonded(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
-) -> StdResult<Response> {
+    batch_ids: Option<Vec<u64>>,
+    recipient: Option<String>,
+    ticket_ids: Option<Vec<String>>,
+) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage, PausableOperation::WithdrawUnbonded)?;
+
+    if batch_ids.is_some() && ticket_ids.is_some() {
+        return Err(ContractError::WithdrawSelectionConflict {});
+    }
+
     let sender_human = info.sender;
+    let recipient = match recipient {
+        Some(recipient) => deps.api.addr_validate(&recipient)?,
+        None => sender_human.clone(),
+    };
     let contract_address = env.contract.address.clone();
 
     // read params
@@ -166,21 +546,28 @@ pub fn execute_withdraw_unbonded(
         .amount;
 
     // calculate withdraw rate for user requests
-    process_withdraw_rate(deps.storage, historical_time, hub_balance)?;
+    process_withdraw_rate(
+        deps.storage,
+        historical_time,
+        hub_balance,
+        MAX_PROCESS_LIMIT as u64,
+    )?;
 
-    let withdraw_amount = get_finished_amount(deps.storage, sender_human.to_string()).unwrap();
+    // remove the released requests of the user that are withdrawn
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let withdraw_amount = match ticket_ids {
+        Some(ticket_ids) => {
+            let (amount, burn_msgs) = take_ticket_amount(deps.branch(), &sender_human, ticket_ids)?;
+            messages = burn_msgs;
+            amount
+        }
+        None => take_finished_amount(deps.storage, sender_human.to_string(), batch_ids)?,
+    };
 
     if withdraw_amount.is_zero() {
-        return Err(StdError::generic_err(format!(
-            "No withdrawable {} assets are available yet",
-            coin_denom
-        )));
+        return Err(ContractError::NothingToWithdraw { denom: coin_denom });
     }
 
-    // remove the previous batches for the user
-    let deprecated_batches = get_unbond_batches(deps.storage, sender_human.to_string())?;
-    remove_unbond_wait_list(deps.storage, deprecated_batches, sender_human.clone())?;
-
     // Update previous balance used for calculation in next Luna batch release
     let prev_balance = (hub_balance.checked_sub(withdraw_amount))?;
     STATE.update(deps.storage, |mut last_state| -> StdResult<State> {
@@ -188,9 +575,9 @@ pub fn execute_withdraw_unbonded(
         Ok(last_state)
     })?;
 
-    // Send the money to the user
+    // Send the money to the recipient
     let bank_msg: CosmosMsg = BankMsg::Send {
-        to_address: sender_human.to_string(),
+        to_address: recipient.to_string(),
         amount: coins(withdraw_amount.u128(), &*coin_denom),
     }
     .into();
@@ -199,79 +586,206 @@ pub fn execute_withdraw_unbonded(
         .add_attributes(vec![
             attr("action", "finish_burn"),
             attr("from", contract_address),
+            attr("recipient", recipient),
             attr("amount", withdraw_amount),
         ])
-        .add_message(bank_msg))
+        .add_message(bank_msg)
+        .add_messages(messages))
+}
+
+/// Remove the released requests of the given tickets held by `holder`
+/// and return the amount they are worth, with the messages burning the tickets.
+/// Tickets of batches that are not released yet are kept.
+fn take_ticket_amount(
+    deps: DepsMut,
+    holder: &Addr,
+    mut ticket_ids: Vec<String>,
+) -> Result<(Uint128, Vec<CosmosMsg>), ContractError> {
+    let ticket_contract = unbond_ticket_contract(deps.as_ref())?;
+
+    ticket_ids.sort();
+    ticket_ids.dedup();
+
+    let mut withdrawable_amount = Uint128::zero();
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for ticket_id in ticket_ids {
+        assert_ticket_holder(deps.as_ref(), &ticket_contract, &ticket_id, holder)?;
+
+        let ticket = UNBOND_TICKETS.load(deps.storage, &ticket_id)?;
+        match may_read_unbond_history(deps.storage, ticket.batch_id)? {
+            Some(h) if h.released => {
+                withdrawable_amount += ticket.amount * h.withdraw_rate;
+                UNBOND_TICKETS.remove(deps.storage, &ticket_id);
+                messages.push(burn_ticket_msg(&ticket_contract, ticket_id)?);
+            }
+            _ => {}
+        }
+    }
+    Ok((withdrawable_amount, messages))
+}
+
+pub(crate) fn unbond_ticket_contract(deps: Deps) -> Result<Addr, ContractError> {
+    let ticket_contract = UNBOND_TICKET_CONTRACT
+        .may_load(deps.storage)?
+        .ok_or(ContractError::UnbondTicketsDisabled {})?;
+    Ok(deps.api.addr_humanize(&ticket_contract)?)
+}
+
+/// Check with the ticket contract that `holder` owns the ticket.
+fn assert_ticket_holder(
+    deps: Deps,
+    ticket_contract: &Addr,
+    ticket_id: &str,
+    holder: &Addr,
+) -> Result<(), ContractError> {
+    let owner: OwnerOfResponse = deps.querier.query_wasm_smart(
+        ticket_contract.to_string(),
+        &Cw721QueryMsg::OwnerOf {
+            token_id: ticket_id.to_string(),
+            include_expired: None,
+        },
+    )?;
+    if owner.owner != holder.as_str() {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+fn burn_ticket_msg(ticket_contract: &Addr, ticket_id: String) -> StdResult<CosmosMsg> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: ticket_contract.to_string(),
+        msg: to_binary(&UnbondTicketMsg::Burn {
+            token_id: ticket_id,
+        })?,
+        funds: vec![],
+    }))
+}
+
+/// Look up the request held by an unbond ticket.
+pub fn query_unbond_ticket(
+    deps: Deps,
+    env: Env,
+    ticket_id: String,
+) -> Result<UnbondTicketResponse, ContractError> {
+    let params = PARAMETERS.load(deps.storage)?;
+    let state = STATE.load(deps.storage)?;
+    let current_batch = CURRENT_BATCH.load(deps.storage)?;
+    let ticket = UNBOND_TICKETS.load(deps.storage, &ticket_id)?;
+    describe_ticket(
+        deps.storage,
+        &params,
+        &state,
+        &current_batch,
+        env.block.time.seconds(),
+        ticket_id,
+        ticket,
+    )
+}
+
+/// Describe the request of a ticket and the coins it is worth once its batch has matured,
+/// as `WithdrawableUnbonded` does for the wait list.
+pub(crate) fn describe_ticket(
+    storage: &dyn Storage,
+    params: &Parameters,
+    state: &State,
+    current_batch: &CurrentBatch,
+    now: u64,
+    ticket_id: String,
+    ticket: UnbondTicket,
+) -> Result<UnbondTicketResponse, ContractError> {
+    let (batch, withdrawable) = if ticket.batch_id == current_batch.id {
+        (
+            open_batch(state, params, current_batch, now),
+            Uint128::zero(),
+        )
+    } else {
+        let history = read_unbond_history(storage, ticket.batch_id)?;
+        let withdrawable = if history.time < now - params.unbonding_period {
+            ticket.amount * history.withdraw_rate
+        } else {
+            Uint128::zero()
+        };
+        (submitted_batch(params, history), withdrawable)
+    };
+    Ok(UnbondTicketResponse {
+        ticket_id,
+        amount: ticket.amount,
+        applied_exchange_rate: ticket.applied_exchange_rate,
+        batch,
+        withdrawable,
+    })
 }
 
 /// This is designed for an accurate unbonded amount calculation.
-/// Execute while processing withdraw_unbonded
+/// Execute while processing withdraw_unbonded or process_batches.
+/// The matured batches are scanned first to sum their expected unbonded amount,
+/// then released with the withdraw rate the received coins allow.
+/// At most `limit` batches are scanned and `limit` released per call; the cursor
+/// and the received amount carry over to the next call.
+/// Return the number of released batches.
 fn process_withdraw_rate(
     storage: &mut dyn Storage,
     historical_time: u64,
     hub_balance: Uint128,
-) -> StdResult<()> {
-    // balance change of the hub contract must be checked.
-    let mut total_unbonded_amount = Uint128::zero();
-
+    limit: u64,
+) -> Result<u64, ContractError> {
     let mut state = STATE.load(storage)?;
 
+    // balance change of the hub contract must be checked.
     let balance_change = SignedInt::from_subtraction(hub_balance, state.prev_hub_balance);
     state.actual_unbonded_amount += balance_change.0;
-
-    let last_processed_batch = state.last_processed_batch;
-    let mut batch_count: u64 = 0;
-
-    // Iterate over unbonded histories that have been processed
-    // to calculate newly added unbonded amount
-    let mut i = last_processed_batch + 1;
-    loop {
-        let history: UnbondHistory;
-        match read_unbond_history(storage, i) {
-            Ok(h) => {
-                if h.time > historical_time {
-                    break;
-                }
-                if !h.released {
-                    history = h.clone();
-                } else {
+    state.prev_hub_balance = hub_balance;
+
+    let mut cursor = WITHDRAW_RATE_CURSOR
+        .may_load(storage)?
+        .unwrap_or(WithdrawRateCursor {
+            last_scanned_batch: state.last_processed_batch,
+            scanned_amount: Uint128::zero(),
+            release: None,
+        });
+
+    if cursor.release.is_none() {
+        // Sum the unbonded amount of the newly matured batches
+        let mut scanned: u64 = 0;
+        let mut scan_complete = false;
+        loop {
+            let history = match read_unbond_history(storage, cursor.last_scanned_batch + 1) {
+                Ok(h) if h.time <= historical_time && !h.released => h,
+                _ => {
+                    scan_complete = true;
                     break;
                 }
+            };
+            if scanned == limit {
+                break;
             }
-            Err(_) => break,
+            cursor.scanned_amount += history.amount * history.withdraw_rate;
+            cursor.last_scanned_batch += 1;
+            scanned += 1;
+        }
+
+        // The received coins are shared between all the matured batches,
+        // so the release can only start once all of them are known.
+        if scan_complete && cursor.last_scanned_batch > state.last_processed_batch {
+            cursor.release = Some(ReleaseRound {
+                unbonded_amount: cursor.scanned_amount,
+                actual_amount: state.actual_unbonded_amount,
+            });
         }
-        let burnt_amount = history.amount;
-        let historical_rate = history.withdraw_rate;
-        let unbonded_amount = burnt_amount * historical_rate;
-        total_unbonded_amount += unbonded_amount;
-        batch_count += 1;
-        i += 1;
     }
 
-    if batch_count >= 1 {
+    let mut released: u64 = 0;
+    if let Some(round) = cursor.release.clone() {
+        let total_unbonded_amount = round.unbonded_amount;
+
         // Use signed integer in case of some rogue transfers.
         let slashed_amount =
-            SignedInt::from_subtraction(total_unbonded_amount, state.actual_unbonded_amount);
+            SignedInt::from_subtraction(total_unbonded_amount, round.actual_amount);
+
+        while released < limit && state.last_processed_batch < cursor.last_scanned_batch {
+            let batch_id = state.last_processed_batch + 1;
+            let history = read_unbond_history(storage, batch_id)?;
 
-        // Iterate again to calculate the withdraw rate for each unprocessed history
-        let mut iterator = last_processed_batch + 1;
-        loop {
-            let history: UnbondHistory;
-            match read_unbond_history(storage, iterator) {
-                Ok(h) => {
-                    if h.time > historical_time {
-                        break;
-                    }
-                    if !h.released {
-                        history = h
-                    } else {
-                        break;
-                    }
-                }
-                Err(_) => {
-                    break;
-                }
-            }
             let burnt_amount_of_batch = history.amount;
             let historical_rate_of_batch = history.withdraw_rate;
             let unbonded_amount_of_batch = burnt_amount_of_batch * historical_rate_of_batch;
@@ -304,63 +818,47 @@ fn process_withdraw_rate(
             // store the history and mark it as released
             history_for_i.withdraw_rate = new_withdraw_rate;
             history_for_i.released = true;
-            store_unbond_history(storage, iterator, history_for_i)?;
-            state.last_processed_batch = iterator;
-            iterator += 1;
+            store_unbond_history(storage, batch_id, history_for_i)?;
+            replenish_liquidity_buffer(storage, batch_id, new_withdraw_rate)?;
+            state.last_processed_batch = batch_id;
+            released += 1;
+        }
+
+        if state.last_processed_batch == cursor.last_scanned_batch {
+            // the round is over; keep only what has been received since it started
+            state.actual_unbonded_amount = state
+                .actual_unbonded_amount
+                .saturating_sub(round.actual_amount);
+            cursor.scanned_amount = Uint128::zero();
+            cursor.release = None;
         }
     }
-    // Store state.actual_unbonded_amount for future new batches release
-    state.actual_unbonded_amount = Uint128::zero();
+
+    WITHDRAW_RATE_CURSOR.save(storage, &cursor)?;
     STATE.save(storage, &state)?;
 
-    Ok(())
+    Ok(released)
 }
 
 fn pick_validator(
-    deps: Deps,
+    deps: DepsMut,
     claim: Uint128,
-    delegator: String,
-    block_height: u64,
-) -> StdResult<Vec<CosmosMsg>> {
+    delegator: Addr,
+) -> Result<Vec<CosmosMsg>, ContractError> {
     //read params
     let params = PARAMETERS.load(deps.storage)?;
     let coin_denom = params.underlying_coin_denom;
 
-    let mut messages: Vec<CosmosMsg> = vec![];
-    let mut claimed = claim;
+    // spread the claim over the validators instead of draining them one by one
+    let undelegations = pick_unbond_validators(deps.as_ref(), delegator, claim)?;
 
-    let all_delegations = deps
-        .querier
-        .query_all_delegations(delegator)
-        .expect("There must be at least one delegation");
-
-    // pick a random validator
-    // if it does not have requested amount, undelegate all it has
-    // and pick another random validator
-    let mut iteration_index = 0;
-    let mut deletable_delegations = all_delegations;
-
-    while claimed.u128() > 0 {
-        let mut rng = XorShiftRng::seed_from_u64(block_height + iteration_index);
-        let random_index = rng.gen_range(0, deletable_delegations.len());
-        let delegation = deletable_delegations.remove(random_index);
-        let val = delegation.amount.amount;
-        let undelegated_amount: Uint128;
-        if val.u128() > claimed.u128() {
-            undelegated_amount = claimed;
-            claimed = Uint128::zero();
-        } else {
-            undelegated_amount = val;
-            claimed = (claimed.checked_sub(val))?;
-        }
-        if undelegated_amount.u128() > 0 {
-            let msgs: CosmosMsg = CosmosMsg::Staking(StakingMsg::Undelegate {
-                validator: delegation.validator,
-                amount: coin(undelegated_amount.u128(), &*coin_denom),
-            });
-            messages.push(msgs);
-        }
-        iteration_index += 1;
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for (validator, amount) in undelegations {
+        decrease_expected_delegation(deps.storage, &validator, amount)?;
+        messages.push(CosmosMsg::Staking(StakingMsg::Undelegate {
+            validator,
+            amount: coin(amount.u128(), &*coin_denom),
+        }));
     }
     Ok(messages)
 }
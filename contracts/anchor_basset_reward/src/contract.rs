@@ -1,31 +1,62 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 
-use crate::global::{execute_swap, execute_update_global_index};
-use crate::state::{read_config, read_state, store_config, store_state, Config, State};
+use crate::global::{
+    execute_send_underlying, execute_swap, execute_swap_to_underlying, execute_update_global_index,
+    execute_update_reward_asset_indexes,
+};
+use crate::querier::query_asset_balance;
+use crate::state::{
+    read_config, read_max_spreads, read_reward_index, read_state, store_config, store_reward_index,
+    store_state, Config, RewardIndex, State, MAX_SPREADS,
+};
 use crate::user::{
-    execute_claim_rewards, execute_decrease_balance, execute_increase_balance,
-    query_accrued_rewards, query_holder, query_holders,
+    asset_transfer_msg, execute_claim_and_compound, execute_claim_rewards, execute_compound_hook,
+    execute_decrease_balance, execute_increase_balance, query_accrued_rewards, query_holder,
+    query_holders,
 };
 use cosmwasm_std::{
-    to_binary, Binary, Decimal, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Uint128,
+    attr, to_binary, Binary, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo, Response,
+    StdResult, Uint128,
 };
 
+use basset::contract_error::ContractError;
+use basset::ownership::{
+    accept_ownership, cancel_ownership_proposal, propose_new_owner, query_pending_owner,
+};
 use basset::reward::{
-    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, StateResponse,
+    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, RewardAsset,
+    RewardIndexResponse, StateResponse,
 };
+use basset::version::migrate_contract_version;
+use cw2::set_contract_version;
 use terra_cosmwasm::TerraMsgWrapper;
 
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:anchor-basset-reward";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Each balance change accrues every reward asset, the retired ones included,
+/// so their number is bounded.
+const MAX_REWARD_ASSETS: usize = 10;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
     _env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     msg: InstantiateMsg,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
     let conf = Config {
         hub_contract: deps.api.addr_canonicalize(&msg.hub_contract)?,
         reward_denom: msg.reward_denom,
+        owner: Some(deps.api.addr_canonicalize(info.sender.as_str())?),
+        protocol_fee: Decimal::zero(),
+        fee_collector: None,
+        reward_assets: vec![],
+        retired_reward_assets: vec![],
     };
 
     store_config(deps.storage, &conf)?;
@@ -35,6 +66,7 @@ pub fn instantiate(
             global_index: Decimal::zero(),
             total_balance: Uint128::zero(),
             prev_reward_balance: Uint128::zero(),
+            total_protocol_fee: Uint128::zero(),
         },
     )?;
 
@@ -47,11 +79,55 @@ pub fn execute(
     env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
-) -> StdResult<Response<TerraMsgWrapper>> {
+) -> Result<Response<TerraMsgWrapper>, ContractError> {
     match msg {
         ExecuteMsg::ClaimRewards { recipient } => execute_claim_rewards(deps, env, info, recipient),
+        ExecuteMsg::ClaimAndCompound {
+            validator,
+            max_spread,
+        } => execute_claim_and_compound(deps, env, info, validator, max_spread),
+        ExecuteMsg::CompoundHook {
+            holder,
+            prev_balance,
+            validator,
+        } => execute_compound_hook(deps, env, info, holder, prev_balance, validator),
         ExecuteMsg::SwapToRewardDenom {} => execute_swap(deps, env, info),
         ExecuteMsg::UpdateGlobalIndex {} => execute_update_global_index(deps, env, info),
+        ExecuteMsg::SwapToUnderlying { underlying_denom } => {
+            execute_swap_to_underlying(deps, env, info, underlying_denom)
+        }
+        ExecuteMsg::SendUnderlying { underlying_denom } => {
+            execute_send_underlying(deps, env, info, underlying_denom)
+        }
+        ExecuteMsg::UpdateRewardAssetIndexes {} => {
+            execute_update_reward_asset_indexes(deps, env, info)
+        }
+        ExecuteMsg::ProposeNewOwner { new_owner } => {
+            execute_propose_new_owner(deps, env, info, new_owner)
+        }
+        ExecuteMsg::CancelOwnershipProposal {} => {
+            execute_cancel_ownership_proposal(deps, env, info)
+        }
+        ExecuteMsg::AcceptOwnership {} => execute_accept_ownership(deps, env, info),
+        ExecuteMsg::UpdateConfig {
+            protocol_fee,
+            fee_collector,
+            remove_fee_collector,
+        } => execute_update_config(
+            deps,
+            env,
+            info,
+            protocol_fee,
+            fee_collector,
+            remove_fee_collector.unwrap_or(false),
+        ),
+        ExecuteMsg::AddRewardAsset { asset } => execute_add_reward_asset(deps, env, info, asset),
+        ExecuteMsg::RemoveRewardAsset { asset, recipient } => {
+            execute_remove_reward_asset(deps, env, info, asset, recipient)
+        }
+        ExecuteMsg::UpdateMaxSpread { denom, max_spread } => {
+            execute_update_max_spread(deps, env, info, denom, max_spread)
+        }
         ExecuteMsg::IncreaseBalance { address, amount } => {
             execute_increase_balance(deps, env, info, address, amount)
         }
@@ -61,9 +137,235 @@ pub fn execute(
     }
 }
 
+pub fn execute_propose_new_owner(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    new_owner: String,
+) -> Result<Response<TerraMsgWrapper>, ContractError> {
+    let config = read_config(deps.storage)?;
+    let owner = config.owner.ok_or(ContractError::Unauthorized {})?;
+    propose_new_owner(deps, &info.sender, &owner, &new_owner)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "propose_new_owner"),
+        attr("new_owner", new_owner),
+    ]))
+}
+
+pub fn execute_cancel_ownership_proposal(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response<TerraMsgWrapper>, ContractError> {
+    let config = read_config(deps.storage)?;
+    let owner = config.owner.ok_or(ContractError::Unauthorized {})?;
+    cancel_ownership_proposal(deps, &info.sender, &owner)?;
+
+    Ok(Response::new().add_attributes(vec![attr("action", "cancel_ownership_proposal")]))
+}
+
+pub fn execute_accept_ownership(
+    mut deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response<TerraMsgWrapper>, ContractError> {
+    let new_owner = accept_ownership(deps.branch(), &info.sender)?;
+    let mut config = read_config(deps.storage)?;
+    config.owner = Some(new_owner);
+    store_config(deps.storage, &config)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "accept_ownership"),
+        attr("owner", info.sender),
+    ]))
+}
+
+pub fn execute_update_config(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    protocol_fee: Option<Decimal>,
+    fee_collector: Option<String>,
+    remove_fee_collector: bool,
+) -> Result<Response<TerraMsgWrapper>, ContractError> {
+    let mut config = read_config(deps.storage)?;
+    let owner = config.owner.clone().ok_or(ContractError::Unauthorized {})?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(fee) = protocol_fee {
+        if fee > Decimal::one() {
+            return Err(ContractError::FractionTooLarge {
+                name: "protocol_fee".to_string(),
+            });
+        }
+        config.protocol_fee = fee;
+    }
+
+    if remove_fee_collector {
+        config.fee_collector = None;
+    } else if let Some(collector) = fee_collector {
+        let collector = deps.api.addr_validate(&collector)?;
+        config.fee_collector = Some(deps.api.addr_canonicalize(collector.as_str())?);
+    }
+
+    store_config(deps.storage, &config)?;
+
+    Ok(Response::new().add_attributes(vec![attr("action", "update_config")]))
+}
+
+/// Distribute `asset` with its own global index from the next global index update.
+/// A retired asset is distributed again from the index it was retired at.
+/// Only creator/owner is allowed to execute
+pub fn execute_add_reward_asset(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    asset: RewardAsset,
+) -> Result<Response<TerraMsgWrapper>, ContractError> {
+    let mut config = read_config(deps.storage)?;
+    let owner = config.owner.clone().ok_or(ContractError::Unauthorized {})?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let asset = match asset {
+        RewardAsset::Native { denom } => RewardAsset::Native { denom },
+        RewardAsset::Token { contract_addr } => RewardAsset::Token {
+            contract_addr: deps.api.addr_validate(&contract_addr)?.to_string(),
+        },
+    };
+
+    let is_reward_denom =
+        matches!(&asset, RewardAsset::Native { denom } if denom == &config.reward_denom);
+    if is_reward_denom || config.reward_assets.contains(&asset) {
+        return Err(ContractError::RewardAssetAlreadyTracked {
+            asset: asset.to_string(),
+        });
+    }
+
+    if let Some(position) = config
+        .retired_reward_assets
+        .iter()
+        .position(|item| item == &asset)
+    {
+        config.retired_reward_assets.remove(position);
+    } else {
+        if config.claimable_assets().len() >= MAX_REWARD_ASSETS {
+            return Err(ContractError::TooManyRewardAssets {
+                max: MAX_REWARD_ASSETS,
+            });
+        }
+        // whatever the contract already holds of the asset goes to the current holders
+        store_reward_index(deps.storage, &asset, &RewardIndex::default())?;
+    }
+    config.reward_assets.push(asset.clone());
+    store_config(deps.storage, &config)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "add_reward_asset"),
+        attr("asset", asset.to_string()),
+    ]))
+}
+
+/// Stop distributing `asset` and sweep the balance of the contract in it received
+/// since the last global index update to `recipient`. The asset is retired: the holders
+/// keep claiming the rewards they accrued until then.
+/// Nothing is swept if the balance can not be queried, e.g. for a broken token.
+/// Only creator/owner is allowed to execute
+pub fn execute_remove_reward_asset(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    asset: RewardAsset,
+    recipient: Option<String>,
+) -> Result<Response<TerraMsgWrapper>, ContractError> {
+    let mut config = read_config(deps.storage)?;
+    let owner = config.owner.clone().ok_or(ContractError::Unauthorized {})?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let position = config
+        .reward_assets
+        .iter()
+        .position(|item| item == &asset)
+        .ok_or_else(|| ContractError::RewardAssetNotTracked {
+            asset: asset.to_string(),
+        })?;
+    config.reward_assets.remove(position);
+    config.retired_reward_assets.push(asset.clone());
+    store_config(deps.storage, &config)?;
+
+    let recipient = match recipient {
+        Some(recipient) => deps.api.addr_validate(&recipient)?,
+        None => info.sender,
+    };
+
+    let mut messages: Vec<CosmosMsg<TerraMsgWrapper>> = vec![];
+    let owed = read_reward_index(deps.storage, &asset)?.prev_balance;
+    let swept = query_asset_balance(deps.as_ref(), &asset, env.contract.address)
+        .map(|balance| balance.saturating_sub(owed))
+        .unwrap_or_default();
+    if !swept.is_zero() {
+        messages.push(asset_transfer_msg(
+            deps.as_ref(),
+            &asset,
+            swept,
+            recipient.to_string(),
+        )?);
+    }
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "remove_reward_asset"),
+        attr("asset", asset.to_string()),
+        attr("recipient", recipient),
+        attr("swept", swept),
+    ]))
+}
+
+/// Set or remove the max spread tolerated when swapping `denom` rewards.
+/// Only creator/owner is allowed to execute
+pub fn execute_update_max_spread(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    denom: String,
+    max_spread: Option<Decimal>,
+) -> Result<Response<TerraMsgWrapper>, ContractError> {
+    let config = read_config(deps.storage)?;
+    let owner = config.owner.ok_or(ContractError::Unauthorized {})?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    match max_spread {
+        Some(spread) => {
+            if spread > Decimal::one() {
+                return Err(ContractError::FractionTooLarge {
+                    name: "max_spread".to_string(),
+                });
+            }
+            MAX_SPREADS.save(deps.storage, &denom, &spread)?;
+        }
+        None => MAX_SPREADS.remove(deps.storage, &denom),
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_max_spread"),
+        attr("denom", denom),
+        attr(
+            "max_spread",
+            max_spread.map_or_else(|| "none".to_string(), |spread| spread.to_string()),
+        ),
+    ]))
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+    let res = match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
         QueryMsg::State {} => to_binary(&query_state(deps)?),
         QueryMsg::AccruedRewards { address } => to_binary(&query_accrued_rewards(deps, address)?),
@@ -71,27 +373,75 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::Holders { start_after, limit } => {
             to_binary(&query_holders(deps, start_after, limit)?)
         }
-    }
+    };
+    Ok(res?)
 }
 
 fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     let config: Config = read_config(deps.storage)?;
     Ok(ConfigResponse {
+        owner: config
+            .owner
+            .map(|owner| deps.api.addr_humanize(&owner))
+            .transpose()?
+            .map(|owner| owner.to_string()),
+        pending_owner: query_pending_owner(deps)?,
         hub_contract: deps.api.addr_humanize(&config.hub_contract)?.to_string(),
         reward_denom: config.reward_denom,
+        protocol_fee: config.protocol_fee,
+        fee_collector: config
+            .fee_collector
+            .map(|collector| deps.api.addr_humanize(&collector))
+            .transpose()?
+            .map(|collector| collector.to_string()),
+        reward_assets: config.reward_assets,
+        retired_reward_assets: config.retired_reward_assets,
+        max_spreads: read_max_spreads(deps.storage)?,
     })
 }
 
 fn query_state(deps: Deps) -> StdResult<StateResponse> {
     let state: State = read_state(deps.storage)?;
+    let reward_indexes = read_config(deps.storage)?
+        .claimable_assets()
+        .into_iter()
+        .map(|asset| {
+            let index = read_reward_index(deps.storage, &asset)?;
+            Ok(RewardIndexResponse {
+                asset,
+                global_index: index.global_index,
+                prev_balance: index.prev_balance,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
     Ok(StateResponse {
         global_index: state.global_index,
         total_balance: state.total_balance,
         prev_reward_balance: state.prev_reward_balance,
+        total_protocol_fee: state.total_protocol_fee,
+        reward_indexes,
     })
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
-    Ok(Response::default())
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    migrate_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    if let Some(owner) = msg.owner {
+        let mut config = read_config(deps.storage)?;
+        // an existing owner only hands over through the ownership transfer
+        if config.owner.is_some() {
+            return Err(ContractError::OwnerAlreadySet {});
+        }
+        config.owner = Some(
+            deps.api
+                .addr_canonicalize(deps.api.addr_validate(&owner)?.as_str())?,
+        );
+        store_config(deps.storage, &config)?;
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "migrate"),
+        attr("version", CONTRACT_VERSION),
+    ]))
 }
@@ -1,107 +1,393 @@
-use crate::state::{read_config, read_state, store_state, Config, State};
+use crate::querier::query_asset_balance;
+use crate::state::{
+    read_config, read_max_spread, read_reward_index, read_state, store_reward_index, store_state,
+    Config, State,
+};
 
 use crate::math::decimal_summation_in_256;
 
+use basset::contract_error::ContractError;
+use basset::deduct_tax;
+use basset::reward::RewardAsset;
 use cosmwasm_std::{
-    attr, CosmosMsg, Decimal, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
+    attr, BankMsg, Coin, CosmosMsg, Decimal, DepsMut, Env, Event, MessageInfo, Response, StdResult,
+    Uint128,
 };
 use terra_cosmwasm::{create_swap_msg, ExchangeRatesResponse, TerraMsgWrapper, TerraQuerier};
 /// Swap all native tokens to reward_denom
 /// Only hub_contract is allowed to execute
-#[allow(clippy::if_same_then_else)]
-#[allow(clippy::needless_collect)]
 pub fn execute_swap(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-) -> StdResult<Response<TerraMsgWrapper>> {
+) -> Result<Response<TerraMsgWrapper>, ContractError> {
     let config = read_config(deps.storage)?;
     let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
 
     if sender_raw != config.hub_contract {
-        return Err(StdError::generic_err("unauthorized"));
+        return Err(ContractError::Unauthorized {});
     }
 
     let contr_addr = env.contract.address;
     let balance = deps.querier.query_all_balances(contr_addr)?;
+    let (messages, events) = swap_msgs(&deps, balance, config.reward_denom.clone(), &config)?;
+
+    let res = Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![attr("action", "swap")])
+        .add_events(events);
+
+    Ok(res)
+}
+
+/// Swap all native tokens to the underlying denom of the hub, which compounds them.
+/// The rewards still owed to the holders are left in reward_denom.
+/// Only hub_contract is allowed to execute
+pub fn execute_swap_to_underlying(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    underlying_denom: String,
+) -> Result<Response<TerraMsgWrapper>, ContractError> {
+    let config = read_config(deps.storage)?;
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+
+    if sender_raw != config.hub_contract {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let state = read_state(deps.storage)?;
+    let mut balance = deps.querier.query_all_balances(env.contract.address)?;
+    for coin in balance.iter_mut() {
+        if coin.denom == config.reward_denom {
+            coin.amount = coin.amount.saturating_sub(state.prev_reward_balance);
+        }
+    }
+    balance.retain(|coin| !coin.amount.is_zero());
+
+    let (messages, events) = swap_msgs(&deps, balance, underlying_denom, &config)?;
+
+    let res = Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![attr("action", "swap_to_underlying")])
+        .add_events(events);
+
+    Ok(res)
+}
+
+/// Send the underlying denom held by the contract back to the hub to be restaked,
+/// after the protocol fee is sent to the fee collector.
+/// Only hub_contract is allowed to execute
+pub fn execute_send_underlying(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    underlying_denom: String,
+) -> Result<Response<TerraMsgWrapper>, ContractError> {
+    let config = read_config(deps.storage)?;
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+
+    if sender_raw != config.hub_contract {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut amount = deps
+        .querier
+        .query_balance(env.contract.address, underlying_denom.as_str())?
+        .amount;
+    if underlying_denom == config.reward_denom {
+        let state = read_state(deps.storage)?;
+        amount = amount.saturating_sub(state.prev_reward_balance);
+    }
+    let underlying_asset = RewardAsset::Native {
+        denom: underlying_denom.clone(),
+    };
+    if config.claimable_assets().contains(&underlying_asset) {
+        let index = read_reward_index(deps.storage, &underlying_asset)?;
+        amount = amount.saturating_sub(index.prev_balance);
+    }
+
+    // the fee is taken from the compounded rewards as from the distributed ones
+    let mut messages: Vec<CosmosMsg<TerraMsgWrapper>> = vec![];
+    let mut protocol_fee = Uint128::zero();
+    if let Some(fee_collector) = config.fee_collector {
+        protocol_fee = amount * config.protocol_fee;
+        if !protocol_fee.is_zero() {
+            messages.push(CosmosMsg::Bank(BankMsg::Send {
+                to_address: deps.api.addr_humanize(&fee_collector)?.to_string(),
+                amount: vec![deduct_tax(
+                    &deps.querier,
+                    Coin {
+                        denom: underlying_denom.clone(),
+                        amount: protocol_fee,
+                    },
+                )?],
+            }));
+        }
+    }
+    let amount = amount.checked_sub(protocol_fee)?;
+
+    if !amount.is_zero() {
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: deps.api.addr_humanize(&config.hub_contract)?.to_string(),
+            amount: vec![deduct_tax(
+                &deps.querier,
+                Coin {
+                    denom: underlying_denom,
+                    amount,
+                },
+            )?],
+        }));
+    }
+
+    let res = Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "send_underlying"),
+        attr("amount", amount),
+        attr("protocol_fee", protocol_fee),
+    ]);
+
+    Ok(res)
+}
+
+/// Create swap messages from the coins of `balance` to `target_denom`.
+/// Coins without an exchange rate against `target_denom` and the reward assets,
+/// which are distributed as they are, are left untouched. Of a retired reward asset,
+/// only what the holders can not claim anymore is swapped.
+/// Coins whose simulated swap is further from the oracle rate than their max spread
+/// are left for the next round, with a `swap_skipped` event.
+fn swap_msgs(
+    deps: &DepsMut,
+    mut balance: Vec<Coin>,
+    target_denom: String,
+    config: &Config,
+) -> StdResult<(Vec<CosmosMsg<TerraMsgWrapper>>, Vec<Event>)> {
     let mut messages: Vec<CosmosMsg<TerraMsgWrapper>> = Vec::new();
+    let mut events: Vec<Event> = Vec::new();
 
-    let reward_denom = config.reward_denom;
+    balance.retain(|coin| {
+        !config.reward_assets.contains(&RewardAsset::Native {
+            denom: coin.denom.clone(),
+        })
+    });
+    for coin in balance.iter_mut() {
+        let asset = RewardAsset::Native {
+            denom: coin.denom.clone(),
+        };
+        if config.retired_reward_assets.contains(&asset) {
+            let owed = read_reward_index(deps.storage, &asset)?.prev_balance;
+            coin.amount = coin.amount.saturating_sub(owed);
+        }
+    }
+    balance.retain(|coin| !coin.amount.is_zero());
 
     let denoms: Vec<String> = balance.iter().map(|item| item.denom.clone()).collect();
 
-    let exchange_rates = query_exchange_rates(&deps, reward_denom.clone(), denoms)?;
-    let known_denoms: Vec<String> = exchange_rates
-        .exchange_rates
-        .iter()
-        .map(|item| item.quote_denom.clone())
-        .collect();
+    let exchange_rates = query_exchange_rates(deps, target_denom.clone(), denoms)?;
 
     for coin in balance {
-        if coin.denom == reward_denom.clone() || !known_denoms.contains(&coin.denom) {
+        if coin.denom == target_denom {
             continue;
         }
+        let exchange_rate = match exchange_rates
+            .exchange_rates
+            .iter()
+            .find(|item| item.quote_denom == coin.denom)
+        {
+            Some(item) => item.exchange_rate,
+            None => continue,
+        };
 
-        messages.push(create_swap_msg(coin, reward_denom.to_string()));
+        if let Some(max_spread) = read_max_spread(deps.storage, &coin.denom)? {
+            let spread = query_swap_spread(deps, &coin, &target_denom, exchange_rate)?;
+            if spread > max_spread {
+                events.push(Event::new("swap_skipped").add_attributes(vec![
+                    attr("denom", coin.denom.clone()),
+                    attr("amount", coin.amount),
+                    attr("spread", spread.to_string()),
+                    attr("max_spread", max_spread.to_string()),
+                ]));
+                continue;
+            }
+        }
+
+        messages.push(create_swap_msg(coin, target_denom.clone()));
     }
 
-    let res = Response::new()
-        .add_messages(messages)
-        .add_attributes(vec![attr("action", "swap")]);
+    Ok((messages, events))
+}
 
-    Ok(res)
+/// Return the share of `offer_coin` lost by swapping it on the market
+/// compared with the oracle `exchange_rate` of `ask_denom` in the offer denom.
+pub(crate) fn query_swap_spread(
+    deps: &DepsMut,
+    offer_coin: &Coin,
+    ask_denom: &str,
+    exchange_rate: Decimal,
+) -> StdResult<Decimal> {
+    let querier = TerraQuerier::new(&deps.querier);
+    let swap = querier.query_swap(offer_coin.clone(), ask_denom)?;
+
+    // the received coins valued back in the offer denom at the oracle rate
+    let received_value = swap.receive.amount * exchange_rate;
+    Ok(Decimal::from_ratio(
+        offer_coin.amount.saturating_sub(received_value),
+        offer_coin.amount,
+    ))
 }
 
-/// Increase global_index according to claimed rewards amount
+/// Increase global_index according to claimed rewards amount,
+/// and the index of each reward asset according to its received amount
+/// The protocol fee is sent to the fee collector before the distribution
 /// Only hub_contract is allowed to execute
 pub fn execute_update_global_index(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-) -> StdResult<Response<TerraMsgWrapper>> {
+) -> Result<Response<TerraMsgWrapper>, ContractError> {
     let config: Config = read_config(deps.storage)?;
     let mut state: State = read_state(deps.storage)?;
 
     // Permission check
     if config.hub_contract != deps.api.addr_canonicalize(info.sender.as_str())? {
-        return Err(StdError::generic_err("Unauthorized"));
+        return Err(ContractError::Unauthorized {});
     }
 
     // Zero staking balance check
     if state.total_balance.is_zero() {
-        return Err(StdError::generic_err("No asset is bonded by Hub"));
+        return Err(ContractError::NoAssetBonded {});
     }
 
-    let reward_denom = read_config(deps.storage)?.reward_denom;
+    let reward_denom = config.reward_denom;
 
     // Load the reward contract balance
     let balance = deps
         .querier
-        .query_balance(env.contract.address, reward_denom.as_str())?;
+        .query_balance(env.contract.address.clone(), reward_denom.as_str())?;
 
     let previous_balance = state.prev_reward_balance;
 
     // claimed_rewards = current_balance - prev_balance;
     let claimed_rewards = balance.amount.checked_sub(previous_balance)?;
 
-    state.prev_reward_balance = balance.amount;
+    // the fee is only taken once a collector has been set
+    let mut messages: Vec<CosmosMsg<TerraMsgWrapper>> = vec![];
+    let mut protocol_fee = Uint128::zero();
+    if let Some(fee_collector) = config.fee_collector {
+        protocol_fee = claimed_rewards * config.protocol_fee;
+        if !protocol_fee.is_zero() {
+            messages.push(CosmosMsg::Bank(BankMsg::Send {
+                to_address: deps.api.addr_humanize(&fee_collector)?.to_string(),
+                amount: vec![deduct_tax(
+                    &deps.querier,
+                    Coin {
+                        denom: reward_denom,
+                        amount: protocol_fee,
+                    },
+                )?],
+            }));
+        }
+    }
+    let distributed_rewards = claimed_rewards.checked_sub(protocol_fee)?;
 
-    // global_index += claimed_rewards / total_balance;
+    state.prev_reward_balance = balance.amount.checked_sub(protocol_fee)?;
+    state.total_protocol_fee += protocol_fee;
+
+    // global_index += distributed_rewards / total_balance;
     state.global_index = decimal_summation_in_256(
         state.global_index,
-        Decimal::from_ratio(claimed_rewards, state.total_balance),
+        Decimal::from_ratio(distributed_rewards, state.total_balance),
     );
     store_state(deps.storage, &state)?;
 
+    let events =
+        update_reward_asset_indexes(deps, &env, &config.reward_assets, state.total_balance)?;
+
     let attributes = vec![
         attr("action", "update_global_index"),
         attr("claimed_rewards", claimed_rewards),
+        attr("protocol_fee", protocol_fee),
     ];
-    let res = Response::new().add_attributes(attributes);
+    let res = Response::new()
+        .add_messages(messages)
+        .add_attributes(attributes)
+        .add_events(events);
 
     Ok(res)
 }
 
+/// Increase the index of each reward asset according to its received amount,
+/// leaving the reward denom to be compounded.
+/// Only hub_contract is allowed to execute
+pub fn execute_update_reward_asset_indexes(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response<TerraMsgWrapper>, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let state: State = read_state(deps.storage)?;
+
+    if config.hub_contract != deps.api.addr_canonicalize(info.sender.as_str())? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // the received balances wait for the next update while nothing is bonded,
+    // not to fail the compounding of the hub
+    let events = if state.total_balance.is_zero() {
+        vec![]
+    } else {
+        update_reward_asset_indexes(deps, &env, &config.reward_assets, state.total_balance)?
+    };
+
+    Ok(Response::new()
+        .add_attributes(vec![attr("action", "update_reward_asset_indexes")])
+        .add_events(events))
+}
+
+/// An asset whose balance can not be queried, or is below the indexed balance,
+/// keeps its index until the next update, with a `reward_asset_skipped` event.
+fn update_reward_asset_indexes(
+    deps: DepsMut,
+    env: &Env,
+    reward_assets: &[RewardAsset],
+    total_balance: Uint128,
+) -> StdResult<Vec<Event>> {
+    let mut events: Vec<Event> = vec![];
+    for asset in reward_assets {
+        let mut index = read_reward_index(deps.storage, asset)?;
+        let balance = match query_asset_balance(deps.as_ref(), asset, env.contract.address.clone())
+        {
+            Ok(balance) => balance,
+            Err(err) => {
+                events.push(reward_asset_skipped_event(asset, err.to_string()));
+                continue;
+            }
+        };
+        if balance < index.prev_balance {
+            events.push(reward_asset_skipped_event(
+                asset,
+                format!(
+                    "Balance {} below the indexed balance {}",
+                    balance, index.prev_balance
+                ),
+            ));
+            continue;
+        }
+        let received = balance.checked_sub(index.prev_balance)?;
+        index.prev_balance = balance;
+        index.global_index = decimal_summation_in_256(
+            index.global_index,
+            Decimal::from_ratio(received, total_balance),
+        );
+        store_reward_index(deps.storage, asset, &index)?;
+    }
+    Ok(events)
+}
+
+fn reward_asset_skipped_event(asset: &RewardAsset, error: String) -> Event {
+    Event::new("reward_asset_skipped")
+        .add_attributes(vec![attr("asset", asset.to_string()), attr("error", error)])
+}
+
 pub fn query_exchange_rates(
     deps: &DepsMut,
     base_denom: String,
@@ -1,6 +1,17 @@
-use basset::hub::Config;
-use cosmwasm_std::{Addr, Binary, CanonicalAddr, Deps, QueryRequest, StdResult, WasmQuery};
+use basset::hub::{Config, PausableOperation, QueryMsg as HubQueryMsg, PAUSED_OPERATIONS_KEY};
+use basset::reward::RewardAsset;
+use cosmwasm_std::{
+    from_slice, Addr, Binary, CanonicalAddr, Deps, QueryRequest, StdResult, Uint128, WasmQuery,
+};
 use cosmwasm_storage::to_length_prefixed;
+use cw20::{BalanceResponse as Cw20BalanceResponse, Cw20QueryMsg};
+use serde::{Deserialize, Serialize};
+
+/// The part of the hub parameters the reward contract relies on.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct HubParameters {
+    pub underlying_coin_denom: String,
+}
 
 pub fn query_token_contract(deps: Deps, contract_addr: Addr) -> StdResult<CanonicalAddr> {
     let conf: Config = deps
@@ -15,3 +26,37 @@ pub fn query_token_contract(deps: Deps, contract_addr: Addr) -> StdResult<Canoni
         .token_contract
         .expect("the token contract must have been registered"))
 }
+
+pub fn query_paused_operations(deps: Deps, hub_addr: Addr) -> StdResult<Vec<PausableOperation>> {
+    let paused = deps
+        .querier
+        .query_wasm_raw(hub_addr, PAUSED_OPERATIONS_KEY.as_bytes())?;
+
+    match paused {
+        Some(paused) => from_slice(&paused),
+        None => Ok(vec![]),
+    }
+}
+
+pub fn query_underlying_denom(deps: Deps, hub_addr: Addr) -> StdResult<String> {
+    let params: HubParameters = deps
+        .querier
+        .query_wasm_smart(hub_addr, &HubQueryMsg::Parameters {})?;
+    Ok(params.underlying_coin_denom)
+}
+
+/// Balance of `address` in a reward asset
+pub fn query_asset_balance(deps: Deps, asset: &RewardAsset, address: Addr) -> StdResult<Uint128> {
+    match asset {
+        RewardAsset::Native { denom } => Ok(deps.querier.query_balance(address, denom)?.amount),
+        RewardAsset::Token { contract_addr } => {
+            let res: Cw20BalanceResponse = deps.querier.query_wasm_smart(
+                contract_addr,
+                &Cw20QueryMsg::Balance {
+                    address: address.to_string(),
+                },
+            )?;
+            Ok(res.balance)
+        }
+    }
+}
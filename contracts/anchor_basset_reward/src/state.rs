@@ -3,17 +3,46 @@ use cosmwasm_std::{Addr, Api, CanonicalAddr, Decimal, Deps, Order, StdResult, St
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use basset::reward::HolderResponse;
+use basset::reward::{HolderAssetRewards, HolderResponse, RewardAsset};
 use cw_storage_plus::{Bound, Item, Map};
 
 pub const STATE: Item<State> = Item::new("\u{0}\u{5}state");
 pub const CONFIG: Item<Config> = Item::new("\u{0}\u{6}config");
 pub const HOLDERS: Map<&[u8], Holder> = Map::new("holders");
+pub const REWARD_INDEXES: Map<&str, RewardIndex> = Map::new("reward_indexes");
+pub const HOLDER_ASSET_REWARDS: Map<(&[u8], &str), HolderAssetReward> =
+    Map::new("holder_asset_rewards");
+pub const MAX_SPREADS: Map<&str, Decimal> = Map::new("max_spreads");
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
     pub hub_contract: CanonicalAddr,
     pub reward_denom: String,
+    #[serde(default)]
+    pub owner: Option<CanonicalAddr>,
+    /// Share of the claimed rewards sent to `fee_collector` before they are distributed
+    /// or sent back to the hub to be compounded
+    #[serde(default)]
+    pub protocol_fee: Decimal,
+    #[serde(default)]
+    pub fee_collector: Option<CanonicalAddr>,
+    /// Rewards distributed with their own global index instead of being swapped
+    #[serde(default)]
+    pub reward_assets: Vec<RewardAsset>,
+    /// Reward assets no longer distributed, whose accrued rewards are still claimed
+    #[serde(default)]
+    pub retired_reward_assets: Vec<RewardAsset>,
+}
+
+impl Config {
+    /// The reward assets the holders accrue and claim, the retired ones included
+    pub fn claimable_assets(&self) -> Vec<RewardAsset> {
+        self.reward_assets
+            .iter()
+            .chain(self.retired_reward_assets.iter())
+            .cloned()
+            .collect()
+    }
 }
 
 pub fn store_config(storage: &mut dyn Storage, config: &Config) -> StdResult<()> {
@@ -29,6 +58,9 @@ pub struct State {
     pub global_index: Decimal,
     pub total_balance: Uint128,
     pub prev_reward_balance: Uint128,
+    /// Protocol fee taken in reward_denom, the fee on compounded rewards is not counted
+    #[serde(default)]
+    pub total_protocol_fee: Uint128,
 }
 
 pub fn store_state(storage: &mut dyn Storage, state: &State) -> StdResult<()> {
@@ -46,6 +78,90 @@ pub struct Holder {
     pub pending_rewards: Decimal,
 }
 
+/// Global index of a reward asset, keyed by the asset
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct RewardIndex {
+    pub global_index: Decimal,
+    pub prev_balance: Uint128,
+}
+
+/// Index and pending rewards of a holder in a reward asset
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct HolderAssetReward {
+    pub index: Decimal,
+    pub pending_rewards: Decimal,
+}
+
+pub fn read_reward_index(storage: &dyn Storage, asset: &RewardAsset) -> StdResult<RewardIndex> {
+    REWARD_INDEXES.load(storage, &asset.to_string())
+}
+
+pub fn store_reward_index(
+    storage: &mut dyn Storage,
+    asset: &RewardAsset,
+    index: &RewardIndex,
+) -> StdResult<()> {
+    REWARD_INDEXES.save(storage, &asset.to_string(), index)
+}
+
+/// A holder without an entry for the asset has not accrued any of it yet.
+pub fn read_holder_asset_reward(
+    storage: &dyn Storage,
+    holder_address: &CanonicalAddr,
+    asset: &RewardAsset,
+) -> StdResult<HolderAssetReward> {
+    Ok(HOLDER_ASSET_REWARDS
+        .may_load(storage, (holder_address.as_slice(), &asset.to_string()))?
+        .unwrap_or_default())
+}
+
+pub fn store_holder_asset_reward(
+    storage: &mut dyn Storage,
+    holder_address: &CanonicalAddr,
+    asset: &RewardAsset,
+    reward: &HolderAssetReward,
+) -> StdResult<()> {
+    HOLDER_ASSET_REWARDS.save(
+        storage,
+        (holder_address.as_slice(), &asset.to_string()),
+        reward,
+    )
+}
+
+pub fn read_holder_asset_rewards(
+    storage: &dyn Storage,
+    holder_address: &CanonicalAddr,
+) -> StdResult<Vec<HolderAssetRewards>> {
+    let config = read_config(storage)?;
+    config
+        .claimable_assets()
+        .into_iter()
+        .map(|asset| {
+            let reward = read_holder_asset_reward(storage, holder_address, &asset)?;
+            Ok(HolderAssetRewards {
+                asset,
+                index: reward.index,
+                pending_rewards: reward.pending_rewards,
+            })
+        })
+        .collect()
+}
+
+/// A denom without a max spread is swapped at whatever the market gives.
+pub fn read_max_spread(storage: &dyn Storage, denom: &str) -> StdResult<Option<Decimal>> {
+    MAX_SPREADS.may_load(storage, denom)
+}
+
+pub fn read_max_spreads(storage: &dyn Storage) -> StdResult<Vec<(String, Decimal)>> {
+    MAX_SPREADS
+        .range(storage, None, None, Order::Ascending)
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((String::from_utf8(k)?, v))
+        })
+        .collect()
+}
+
 // This is similar to HashMap<holder's address, Hodler>
 pub fn store_holder(
     storage: &mut dyn Storage,
@@ -83,12 +199,14 @@ pub fn read_holders(
         .take(limit)
         .map(|elem| {
             let (k, v) = elem?;
-            let address: String = deps.api.addr_humanize(&CanonicalAddr::from(k))?.to_string();
+            let holder_address = CanonicalAddr::from(k);
+            let address: String = deps.api.addr_humanize(&holder_address)?.to_string();
             Ok(HolderResponse {
                 address,
                 balance: v.balance,
                 index: v.index,
                 pending_rewards: v.pending_rewards,
+                asset_rewards: read_holder_asset_rewards(deps.storage, &holder_address)?,
             })
         })
         .collect()
@@ -135,12 +253,12 @@ mod test {
     }
 
     /// balances are state of the erc20 tokens
-    pub fn legacy_holders(storage: &mut dyn Storage) -> Bucket<Holder> {
+    pub fn legacy_holders(storage: &mut dyn Storage) -> Bucket<'_, Holder> {
         bucket(storage, PREFIX_HOLDERS)
     }
 
     /// balances are state of the erc20 tokens (read-only version for queries)
-    pub fn legacy_holders_read(storage: &dyn Storage) -> ReadonlyBucket<Holder> {
+    pub fn legacy_holders_read(storage: &dyn Storage) -> ReadonlyBucket<'_, Holder> {
         bucket_read(storage, PREFIX_HOLDERS)
     }
 
@@ -153,6 +271,7 @@ mod test {
                 global_index: Default::default(),
                 total_balance: Default::default(),
                 prev_reward_balance: Default::default(),
+                total_protocol_fee: Default::default(),
             },
         )
         .unwrap();
@@ -171,6 +290,11 @@ mod test {
             &Config {
                 hub_contract: deps.api.addr_canonicalize("hub").unwrap(),
                 reward_denom: "".to_string(),
+                owner: None,
+                protocol_fee: Default::default(),
+                fee_collector: None,
+                reward_assets: vec![],
+                retired_reward_assets: vec![],
             },
         )
         .unwrap();
@@ -1,14 +1,17 @@
-use basset::hub::Config;
+use crate::querier::HubParameters;
+use basset::hub::{Config, PausableOperation, QueryMsg as HubQueryMsg, PAUSED_OPERATIONS_KEY};
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    from_slice, to_binary, Api, Coin, ContractResult, Decimal, OwnedDeps, Querier, QuerierResult,
-    QueryRequest, SystemError, SystemResult, Uint128, WasmQuery,
+    from_binary, from_slice, to_binary, Api, Coin, ContractResult, Decimal, OwnedDeps, Querier,
+    QuerierResult, QueryRequest, SystemError, SystemResult, Uint128, WasmQuery,
 };
 use cosmwasm_storage::to_length_prefixed;
+use cw20::{BalanceResponse, Cw20QueryMsg};
+use std::collections::HashMap;
 use std::str::FromStr;
 use terra_cosmwasm::{
-    ExchangeRateItem, ExchangeRatesResponse, TaxCapResponse, TaxRateResponse, TerraQuery,
-    TerraQueryWrapper, TerraRoute,
+    ExchangeRateItem, ExchangeRatesResponse, SwapResponse, TaxCapResponse, TaxRateResponse,
+    TerraQuery, TerraQueryWrapper, TerraRoute,
 };
 
 pub const MOCK_HUB_CONTRACT_ADDR: &str = "hub";
@@ -31,6 +34,9 @@ pub fn mock_dependencies(
 
 pub struct WasmMockQuerier {
     base: MockQuerier<TerraQueryWrapper>,
+    paused_operations: Vec<PausableOperation>,
+    token_balances: HashMap<String, Uint128>,
+    swap_spreads: HashMap<String, Decimal>,
 }
 
 impl Querier for WasmMockQuerier {
@@ -92,6 +98,29 @@ impl WasmMockQuerier {
                         }
                         _ => panic!("DO NOT ENTER HERE"),
                     }
+                } else if &TerraRoute::Market == route {
+                    match query_data {
+                        TerraQuery::Swap {
+                            offer_coin,
+                            ask_denom,
+                        } => {
+                            // the oracle rate of 22.1, less the configured spread
+                            let spread = self
+                                .swap_spreads
+                                .get(&offer_coin.denom)
+                                .copied()
+                                .unwrap_or_else(Decimal::zero);
+                            let amount = offer_coin.amount.multiply_ratio(10u128, 221u128);
+                            let receive = Coin {
+                                denom: ask_denom.clone(),
+                                amount: amount.checked_sub(amount * spread).unwrap(),
+                            };
+                            SystemResult::Ok(ContractResult::from(to_binary(&SwapResponse {
+                                receive,
+                            })))
+                        }
+                        _ => panic!("DO NOT ENTER HERE"),
+                    }
                 } else {
                     panic!("DO NOT ENTER HERE")
                 }
@@ -116,6 +145,8 @@ impl WasmMockQuerier {
                             ),
                         };
                         SystemResult::Ok(ContractResult::from(to_binary(&config)))
+                    } else if key.as_slice() == PAUSED_OPERATIONS_KEY.as_bytes() {
+                        SystemResult::Ok(ContractResult::from(to_binary(&self.paused_operations)))
                     } else {
                         unimplemented!()
                     }
@@ -123,6 +154,31 @@ impl WasmMockQuerier {
                     unimplemented!()
                 }
             }
+            QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
+                if *contract_addr == MOCK_HUB_CONTRACT_ADDR {
+                    match from_binary(msg).unwrap() {
+                        HubQueryMsg::Parameters {} => {
+                            SystemResult::Ok(ContractResult::from(to_binary(&HubParameters {
+                                underlying_coin_denom: "uluna".to_string(),
+                            })))
+                        }
+                        _ => unimplemented!(),
+                    }
+                } else if let Some(balance) = self.token_balances.get(contract_addr) {
+                    match from_binary(msg).unwrap() {
+                        Cw20QueryMsg::Balance { address: _ } => {
+                            SystemResult::Ok(ContractResult::from(to_binary(&BalanceResponse {
+                                balance: *balance,
+                            })))
+                        }
+                        _ => unimplemented!(),
+                    }
+                } else {
+                    SystemResult::Err(SystemError::NoSuchContract {
+                        addr: contract_addr.clone(),
+                    })
+                }
+            }
             _ => self.base.handle_query(request),
         }
     }
@@ -130,6 +186,31 @@ impl WasmMockQuerier {
 
 impl WasmMockQuerier {
     pub fn new(base: MockQuerier<TerraQueryWrapper>) -> Self {
-        WasmMockQuerier { base }
+        WasmMockQuerier {
+            base,
+            paused_operations: vec![],
+            token_balances: HashMap::new(),
+            swap_spreads: HashMap::new(),
+        }
+    }
+
+    // configure the balance of the reward contract in cw20 tokens
+    pub fn with_token_balances(&mut self, balances: &[(&str, u128)]) {
+        for (contract_addr, balance) in balances {
+            self.token_balances
+                .insert(contract_addr.to_string(), Uint128::new(*balance));
+        }
+    }
+
+    // configure the operations paused on the hub
+    pub fn with_paused_operations(&mut self, paused_operations: &[PausableOperation]) {
+        self.paused_operations = paused_operations.to_vec();
+    }
+
+    // configure the spread of the market swaps from the offered denoms
+    pub fn with_swap_spreads(&mut self, spreads: &[(&str, Decimal)]) {
+        for (denom, spread) in spreads {
+            self.swap_spreads.insert(denom.to_string(), *spread);
+        }
     }
 }
@@ -5,34 +5,48 @@
 //! You can easily convert unit tests to integration tests as follows:
 //! 1. Copy them over verbatim
 //! 2. Then change
-//!      let mut deps = mock_dependencies(&[]);
+//!    ```text
+//!    let mut deps = mock_dependencies(&[]);
+//!    ```
 //!    to
-//!      let mut deps = mock_instance(WASM, &[]);
+//!    ```text
+//!    let mut deps = mock_instance(WASM, &[]);
+//!    ```
 //! 3. If you access raw storage, where ever you see something like:
-//!      deps.storage.get(CONFIG_KEY).expect("no data stored");
+//!    ```text
+//!    deps.storage.get(CONFIG_KEY).expect("no data stored");
+//!    ```
 //!    replace it with:
-//!      deps.with_storage(|store| {
-//!          let data = store.get(CONFIG_KEY).expect("no data stored");
-//!          //...
-//!      });
+//!    ```text
+//!    deps.with_storage(|store| {
+//!        let data = store.get(CONFIG_KEY).expect("no data stored");
+//!        //...
+//!    });
+//!    ```
 //! 4. Anywhere you see query(deps.as_ref(), mock_env(),...) you must replace it with query(&mut deps, ...)
 
-use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    from_binary, Api, BankMsg, Coin, CosmosMsg, Decimal, StdError, SubMsg, Uint128,
+    attr, from_binary, to_binary, Api, BankMsg, Coin, CosmosMsg, Decimal, Event, OwnedDeps, SubMsg,
+    Uint128, WasmMsg,
 };
 use terra_cosmwasm::create_swap_msg;
 
 use crate::contract::{execute, instantiate, migrate, query};
 use crate::math::{decimal_multiplication_in_256, decimal_subtraction_in_256};
-use crate::state::{store_holder, store_state, Holder, State};
+use crate::state::{read_config, store_config, store_holder, store_state, Holder, State};
 use crate::testing::mock_querier::{
-    mock_dependencies, MOCK_HUB_CONTRACT_ADDR, MOCK_TOKEN_CONTRACT_ADDR,
+    mock_dependencies, WasmMockQuerier, MOCK_HUB_CONTRACT_ADDR, MOCK_TOKEN_CONTRACT_ADDR,
 };
+use basset::contract_error::ContractError;
+use basset::hub::{ExecuteMsg as HubExecuteMsg, PausableOperation};
 use basset::reward::{
-    ConfigResponse, ExecuteMsg, HolderResponse, HoldersResponse, InstantiateMsg, MigrateMsg,
-    QueryMsg, StateResponse,
+    AccruedRewardsResponse, ConfigResponse, ExecuteMsg, HolderAssetRewards, HolderResponse,
+    HoldersResponse, InstantiateMsg, MigrateMsg, QueryMsg, RewardAsset, RewardAssetAmount,
+    RewardIndexResponse, StateResponse,
 };
+use cw2::{get_contract_version, set_contract_version};
+use cw20::Cw20ExecuteMsg;
 use std::str::FromStr;
 
 const DEFAULT_REWARD_DENOM: &str = "uusd";
@@ -59,8 +73,15 @@ fn proper_init() {
     assert_eq!(
         config_response,
         ConfigResponse {
+            owner: Some("addr0000".to_string()),
+            pending_owner: None,
             hub_contract: String::from(MOCK_HUB_CONTRACT_ADDR),
             reward_denom: DEFAULT_REWARD_DENOM.to_string(),
+            protocol_fee: Decimal::zero(),
+            fee_collector: None,
+            reward_assets: vec![],
+            retired_reward_assets: vec![],
+            max_spreads: vec![],
         }
     );
 
@@ -71,7 +92,9 @@ fn proper_init() {
         StateResponse {
             global_index: Decimal::zero(),
             total_balance: Uint128::new(0u128),
-            prev_reward_balance: Uint128::zero()
+            prev_reward_balance: Uint128::zero(),
+            total_protocol_fee: Uint128::zero(),
+            reward_indexes: vec![],
         }
     );
 }
@@ -138,6 +161,246 @@ pub fn swap_to_reward_denom() {
     );
 }
 
+#[test]
+fn swap_with_max_spread() {
+    let mut deps = mock_dependencies(&[
+        Coin {
+            denom: "ukrw".to_string(),
+            amount: Uint128::new(221000u128),
+        },
+        Coin {
+            denom: "usdr".to_string(),
+            amount: Uint128::new(221000u128),
+        },
+        Coin {
+            denom: "uinr".to_string(),
+            amount: Uint128::new(221000u128),
+        },
+    ]);
+    deps.querier.with_swap_spreads(&[
+        ("ukrw", Decimal::percent(5)),
+        ("usdr", Decimal::percent(1)),
+        ("uinr", Decimal::percent(50)),
+    ]);
+
+    let init_msg = default_init();
+    let info = mock_info("addr0000", &[]);
+
+    instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
+
+    // only the owner sets the max spreads
+    let msg = ExecuteMsg::UpdateMaxSpread {
+        denom: "ukrw".to_string(),
+        max_spread: Some(Decimal::percent(2)),
+    };
+    let info = mock_info("addr0001", &[]);
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let msg = ExecuteMsg::UpdateMaxSpread {
+        denom: "ukrw".to_string(),
+        max_spread: Some(Decimal::percent(101)),
+    };
+    let info = mock_info("addr0000", &[]);
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::FractionTooLarge {
+            name: "max_spread".to_string()
+        }
+    );
+
+    for denom in ["ukrw", "usdr"] {
+        let msg = ExecuteMsg::UpdateMaxSpread {
+            denom: denom.to_string(),
+            max_spread: Some(Decimal::percent(2)),
+        };
+        let info = mock_info("addr0000", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    }
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_response: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        config_response.max_spreads,
+        vec![
+            ("ukrw".to_string(), Decimal::percent(2)),
+            ("usdr".to_string(), Decimal::percent(2)),
+        ]
+    );
+
+    // ukrw is over its max spread and stays in the contract,
+    // uinr has no max spread and is swapped whatever the spread
+    let info = mock_info(MOCK_HUB_CONTRACT_ADDR, &[]);
+    let msg = ExecuteMsg::SwapToRewardDenom {};
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(create_swap_msg(
+                Coin {
+                    denom: "usdr".to_string(),
+                    amount: Uint128::new(221000u128),
+                },
+                DEFAULT_REWARD_DENOM.to_string()
+            )),
+            SubMsg::new(create_swap_msg(
+                Coin {
+                    denom: "uinr".to_string(),
+                    amount: Uint128::new(221000u128),
+                },
+                DEFAULT_REWARD_DENOM.to_string()
+            )),
+        ]
+    );
+    assert_eq!(
+        res.events,
+        vec![Event::new("swap_skipped").add_attributes(vec![
+            attr("denom", "ukrw"),
+            attr("amount", "221000"),
+            attr("spread", "0.05"),
+            attr("max_spread", "0.02"),
+        ])]
+    );
+
+    // removing the limit swaps ukrw again
+    let msg = ExecuteMsg::UpdateMaxSpread {
+        denom: "ukrw".to_string(),
+        max_spread: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::SwapToRewardDenom {},
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 3);
+    assert!(res.events.is_empty());
+}
+
+#[test]
+fn swap_and_send_underlying() {
+    let mut deps = mock_dependencies(&[
+        Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(100u128),
+        },
+        Coin {
+            denom: "ukrw".to_string(),
+            amount: Uint128::new(1000u128),
+        },
+        Coin {
+            denom: "uluna".to_string(),
+            amount: Uint128::new(500u128),
+        },
+        Coin {
+            denom: "mnt".to_string(),
+            amount: Uint128::new(50u128),
+        },
+    ]);
+
+    let init_msg = default_init();
+    let info = mock_info("addr0000", &[]);
+
+    instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
+
+    // 40 uusd are still owed to the holders
+    store_state(
+        &mut deps.storage,
+        &State {
+            global_index: Decimal::one(),
+            total_balance: Uint128::from(40u128),
+            prev_reward_balance: Uint128::from(40u128),
+            total_protocol_fee: Uint128::zero(),
+        },
+    )
+    .unwrap();
+
+    let swap = ExecuteMsg::SwapToUnderlying {
+        underlying_denom: "uluna".to_string(),
+    };
+    let send = ExecuteMsg::SendUnderlying {
+        underlying_denom: "uluna".to_string(),
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), swap.clone());
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+    let res = execute(deps.as_mut(), mock_env(), info, send.clone());
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+
+    let info = mock_info(MOCK_HUB_CONTRACT_ADDR, &[]);
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), swap).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(create_swap_msg(
+                Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::new(60u128),
+                },
+                "uluna".to_string()
+            )),
+            SubMsg::new(create_swap_msg(
+                Coin {
+                    denom: "ukrw".to_string(),
+                    amount: Uint128::new(1000u128),
+                },
+                "uluna".to_string()
+            )),
+        ]
+    );
+
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), send.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: MOCK_HUB_CONTRACT_ADDR.to_string(),
+            amount: vec![Coin {
+                denom: "uluna".to_string(),
+                amount: Uint128::from(495u128), // 1% tax
+            },]
+        }))]
+    );
+
+    // the protocol fee is taken before the rest is sent back
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::UpdateConfig {
+            protocol_fee: Some(Decimal::percent(10)),
+            fee_collector: Some("treasury".to_string()),
+            remove_fee_collector: None,
+        },
+    )
+    .unwrap();
+    let res = execute(deps.as_mut(), mock_env(), info, send).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "treasury".to_string(),
+                amount: vec![Coin {
+                    denom: "uluna".to_string(),
+                    amount: Uint128::from(49u128), // 1% tax
+                },]
+            })),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: MOCK_HUB_CONTRACT_ADDR.to_string(),
+                amount: vec![Coin {
+                    denom: "uluna".to_string(),
+                    amount: Uint128::from(445u128), // 1% tax
+                },]
+            })),
+        ]
+    );
+    assert!(res.attributes.contains(&attr("protocol_fee", "50")));
+}
+
 #[test]
 fn update_global_index() {
     let mut deps = mock_dependencies(&[Coin {
@@ -155,18 +418,12 @@ fn update_global_index() {
     // Failed unauthorized try
     let info = mock_info("addr0000", &[]);
     let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
-    match res {
-        Err(_e) => {}
-        _ => panic!("DO NOT ENTER HERE"),
-    }
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
 
     // Failed zero staking balance
     let info = mock_info(MOCK_HUB_CONTRACT_ADDR, &[]);
     let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone());
-    match res {
-        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "No asset is bonded by Hub"),
-        _ => panic!("DO NOT ENTER HERE"),
-    }
+    assert_eq!(res.unwrap_err(), ContractError::NoAssetBonded {});
 
     store_state(
         &mut deps.storage,
@@ -174,6 +431,7 @@ fn update_global_index() {
             global_index: Decimal::zero(),
             total_balance: Uint128::from(100u128),
             prev_reward_balance: Uint128::zero(),
+            total_protocol_fee: Uint128::zero(),
         },
     )
     .unwrap();
@@ -189,9 +447,122 @@ fn update_global_index() {
         StateResponse {
             global_index: Decimal::one(),
             total_balance: Uint128::from(100u128),
-            prev_reward_balance: Uint128::from(100u128)
+            prev_reward_balance: Uint128::from(100u128),
+            total_protocol_fee: Uint128::zero(),
+            reward_indexes: vec![],
+        }
+    );
+}
+
+#[test]
+fn update_global_index_with_protocol_fee() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(1000u128),
+    }]);
+
+    let init_msg = default_init();
+    let info = mock_info("addr0000", &[]);
+
+    instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
+
+    // only the owner can set the fee
+    let msg = ExecuteMsg::UpdateConfig {
+        protocol_fee: Some(Decimal::percent(10)),
+        fee_collector: Some("treasury".to_string()),
+        remove_fee_collector: None,
+    };
+    let info = mock_info("addr0001", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+
+    let info = mock_info("addr0000", &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::UpdateConfig {
+            protocol_fee: Some(Decimal::percent(101)),
+            fee_collector: None,
+            remove_fee_collector: None,
+        },
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::FractionTooLarge {
+            name: "protocol_fee".to_string()
+        }
+    );
+
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_response: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config_response.protocol_fee, Decimal::percent(10));
+    assert_eq!(config_response.fee_collector, Some("treasury".to_string()));
+
+    store_state(
+        &mut deps.storage,
+        &State {
+            global_index: Decimal::zero(),
+            total_balance: Uint128::from(100u128),
+            prev_reward_balance: Uint128::zero(),
+            total_protocol_fee: Uint128::zero(),
+        },
+    )
+    .unwrap();
+
+    // claimed_rewards = 1000, protocol_fee = 100, total_balance = 100
+    // global_index == 9
+    let info = mock_info(MOCK_HUB_CONTRACT_ADDR, &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::UpdateGlobalIndex {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "treasury".to_string(),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(99u128), // 1% tax
+            },]
+        }))]
+    );
+    assert!(res.attributes.contains(&attr("protocol_fee", "100")));
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap();
+    let state_response: StateResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        state_response,
+        StateResponse {
+            global_index: Decimal::from_ratio(9u128, 1u128),
+            total_balance: Uint128::from(100u128),
+            prev_reward_balance: Uint128::from(900u128),
+            total_protocol_fee: Uint128::from(100u128),
+            reward_indexes: vec![],
         }
     );
+
+    // the fee collector can be removed
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::UpdateConfig {
+            protocol_fee: None,
+            fee_collector: None,
+            remove_fee_collector: Some(true),
+        },
+    )
+    .unwrap();
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_response: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config_response.protocol_fee, Decimal::percent(10));
+    assert_eq!(config_response.fee_collector, None);
 }
 
 #[test]
@@ -213,10 +584,7 @@ fn increase_balance() {
 
     let info = mock_info("addr0000", &[]);
     let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
-    match res {
-        Err(_e) => {}
-        _ => panic!("DO NOT ENTER HERE"),
-    };
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
 
     let info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -237,6 +605,7 @@ fn increase_balance() {
             balance: Uint128::from(100u128),
             index: Decimal::zero(),
             pending_rewards: Decimal::zero(),
+            asset_rewards: vec![],
         }
     );
 
@@ -269,6 +638,7 @@ fn increase_balance() {
             balance: Uint128::from(200u128),
             index: Decimal::one(),
             pending_rewards: Decimal::from_str("100").unwrap(),
+            asset_rewards: vec![],
         }
     );
 }
@@ -292,10 +662,7 @@ fn increase_balance_with_decimals() {
 
     let info = mock_info("addr0000", &[]);
     let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
-    match res {
-        Err(_e) => {}
-        _ => panic!("DO NOT ENTER HERE"),
-    };
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
 
     let info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -316,21 +683,644 @@ fn increase_balance_with_decimals() {
             balance: Uint128::from(11u128),
             index: Decimal::zero(),
             pending_rewards: Decimal::zero(),
+            asset_rewards: vec![],
+        }
+    );
+
+    // claimed_rewards = 100000 , total_balance = 11
+    // global_index == 9077.727272727272727272
+    let info = mock_info(MOCK_HUB_CONTRACT_ADDR, &[]);
+    let msg = ExecuteMsg::UpdateGlobalIndex {};
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
+    let msg = ExecuteMsg::IncreaseBalance {
+        address: String::from("addr0000"),
+        amount: Uint128::from(10u128),
+    };
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Holder {
+            address: String::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let holder_response: HolderResponse = from_binary(&res).unwrap();
+    let index = decimal_multiplication_in_256(
+        Decimal::from_ratio(Uint128::new(100000), Uint128::new(11)),
+        Decimal::one(),
+    );
+    let user_pend_reward = decimal_multiplication_in_256(
+        Decimal::from_str("11").unwrap(),
+        decimal_subtraction_in_256(holder_response.index, Decimal::zero()),
+    );
+    assert_eq!(
+        holder_response,
+        HolderResponse {
+            address: String::from("addr0000"),
+            balance: Uint128::from(21u128),
+            index,
+            pending_rewards: user_pend_reward,
+            asset_rewards: vec![],
+        }
+    );
+}
+
+#[test]
+fn decrease_balance() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(100u128),
+    }]);
+
+    let init_msg = default_init();
+    let info = mock_info("addr0000", &[]);
+
+    instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
+
+    let msg = ExecuteMsg::DecreaseBalance {
+        address: String::from("addr0000"),
+        amount: Uint128::from(100u128),
+    };
+
+    // Failed unautorized
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+
+    // Failed underflow
+    let info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::DecreaseExceedsBalance {
+            balance: Uint128::zero()
+        }
+    );
+
+    // Increase balance first
+    let msg = ExecuteMsg::IncreaseBalance {
+        address: String::from("addr0000"),
+        amount: Uint128::from(100u128),
+    };
+
+    let info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // claimed_rewards = 100, total_balance = 100
+    // global_index == 1
+    let info = mock_info(MOCK_HUB_CONTRACT_ADDR, &[]);
+    let msg = ExecuteMsg::UpdateGlobalIndex {};
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
+    let msg = ExecuteMsg::DecreaseBalance {
+        address: String::from("addr0000"),
+        amount: Uint128::from(100u128),
+    };
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Holder {
+            address: String::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let holder_response: HolderResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        holder_response,
+        HolderResponse {
+            address: String::from("addr0000"),
+            balance: Uint128::zero(),
+            index: Decimal::one(),
+            pending_rewards: Decimal::from_str("100").unwrap(),
+            asset_rewards: vec![],
+        }
+    );
+}
+
+#[test]
+fn claim_rewards() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(100u128),
+    }]);
+
+    let init_msg = default_init();
+    let info = mock_info("addr0000", &[]);
+
+    instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
+
+    let msg = ExecuteMsg::IncreaseBalance {
+        address: String::from("addr0000"),
+        amount: Uint128::from(100u128),
+    };
+
+    let info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Holder {
+            address: String::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let holder_response: HolderResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        holder_response,
+        HolderResponse {
+            address: String::from("addr0000"),
+            balance: Uint128::from(100u128),
+            index: Decimal::zero(),
+            pending_rewards: Decimal::zero(),
+            asset_rewards: vec![],
+        }
+    );
+
+    // claimed_rewards = 100, total_balance = 100
+    // global_index == 1
+    let info = mock_info(MOCK_HUB_CONTRACT_ADDR, &[]);
+    let msg = ExecuteMsg::UpdateGlobalIndex {};
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::ClaimRewards { recipient: None };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("addr0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(99u128), // 1% tax
+            },]
+        }))]
+    );
+
+    // claims are rejected while paused on the hub
+    deps.querier
+        .with_paused_operations(&[PausableOperation::ClaimRewards]);
+    let msg = ExecuteMsg::ClaimRewards { recipient: None };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::OperationPaused {
+            operation: PausableOperation::ClaimRewards
+        }
+    );
+    deps.querier.with_paused_operations(&[]);
+
+    // nothing left to claim
+    let msg = ExecuteMsg::ClaimRewards { recipient: None };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    assert_eq!(res.unwrap_err(), ContractError::NoRewardsAccrued {});
+
+    // Set recipient
+    // claimed_rewards = 100, total_balance = 100
+    // global_index == 1
+    let info = mock_info(MOCK_HUB_CONTRACT_ADDR, &[]);
+    let msg = ExecuteMsg::UpdateGlobalIndex {};
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::ClaimRewards {
+        recipient: Some(String::from("addr0001")),
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("addr0001"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(99u128), // 1% tax
+            },]
+        }))]
+    );
+}
+
+#[test]
+fn claim_and_compound() {
+    let mut deps = mock_dependencies(&[
+        Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(100u128),
+        },
+        Coin {
+            denom: "uluna".to_string(),
+            amount: Uint128::new(500u128),
+        },
+    ]);
+
+    let init_msg = default_init();
+    let info = mock_info("addr0000", &[]);
+
+    instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
+
+    let msg = ExecuteMsg::IncreaseBalance {
+        address: String::from("addr0000"),
+        amount: Uint128::from(100u128),
+    };
+    let info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // claimed_rewards = 100, total_balance = 100
+    // global_index == 1
+    let info = mock_info(MOCK_HUB_CONTRACT_ADDR, &[]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::UpdateGlobalIndex {},
+    )
+    .unwrap();
+
+    // the rewards are swapped to the underlying denom, then bonded by the hook
+    let info = mock_info("addr0000", &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::ClaimAndCompound {
+            validator: None,
+            max_spread: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(create_swap_msg(
+                Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::new(100u128),
+                },
+                "uluna".to_string()
+            )),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                msg: to_binary(&ExecuteMsg::CompoundHook {
+                    holder: "addr0000".to_string(),
+                    prev_balance: Uint128::new(500u128),
+                    validator: None,
+                })
+                .unwrap(),
+                funds: vec![],
+            })),
+        ]
+    );
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap();
+    let state_response: StateResponse = from_binary(&res).unwrap();
+    assert_eq!(state_response.prev_reward_balance, Uint128::zero());
+
+    // nothing left to claim
+    let info = mock_info("addr0000", &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::ClaimAndCompound {
+            validator: None,
+            max_spread: None,
+        },
+    );
+    assert_eq!(res.unwrap_err(), ContractError::NoRewardsAccrued {});
+
+    let hook = ExecuteMsg::CompoundHook {
+        holder: "addr0000".to_string(),
+        prev_balance: Uint128::new(100u128),
+        validator: None,
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, hook.clone());
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+
+    // 400 uluna received from the swap
+    let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, hook).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: MOCK_HUB_CONTRACT_ADDR.to_string(),
+            msg: to_binary(&HubExecuteMsg::BondFor {
+                recipient: "addr0000".to_string(),
+                validator: None,
+            })
+            .unwrap(),
+            funds: vec![Coin {
+                denom: "uluna".to_string(),
+                amount: Uint128::new(396u128), // 1% tax
+            }],
+        }))]
+    );
+}
+
+#[test]
+fn claim_and_compound_with_max_spread() {
+    // 221000 uusd of rewards accrued by addr0000, swapped at a 5% spread
+    fn setup() -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
+        let mut deps = mock_dependencies(&[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(221000u128),
+        }]);
+        deps.querier
+            .with_swap_spreads(&[("uusd", Decimal::percent(5))]);
+
+        let info = mock_info("addr0000", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, default_init()).unwrap();
+
+        let msg = ExecuteMsg::IncreaseBalance {
+            address: String::from("addr0000"),
+            amount: Uint128::from(100u128),
+        };
+        let info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info(MOCK_HUB_CONTRACT_ADDR, &[]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::UpdateGlobalIndex {},
+        )
+        .unwrap();
+        deps
+    }
+
+    // the max spread of the message
+    let mut deps = setup();
+    let info = mock_info("addr0000", &[]);
+    let msg = ExecuteMsg::ClaimAndCompound {
+        validator: None,
+        max_spread: Some(Decimal::percent(2)),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    assert_eq!(res.unwrap_err(), ContractError::MaxSpreadAssertion {});
+
+    // the max spread of the reward denom
+    let mut deps = setup();
+    let update_max_spread = ExecuteMsg::UpdateMaxSpread {
+        denom: "uusd".to_string(),
+        max_spread: Some(Decimal::percent(2)),
+    };
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), mock_env(), info, update_max_spread.clone()).unwrap();
+
+    let msg = ExecuteMsg::ClaimAndCompound {
+        validator: None,
+        max_spread: None,
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    assert_eq!(res.unwrap_err(), ContractError::MaxSpreadAssertion {});
+
+    // the max spread of the message overrides the one of the denom
+    let mut deps = setup();
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), mock_env(), info, update_max_spread).unwrap();
+
+    let msg = ExecuteMsg::ClaimAndCompound {
+        validator: None,
+        max_spread: Some(Decimal::percent(5)),
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(create_swap_msg(
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(221000u128),
+            },
+            "uluna".to_string()
+        ))
+    );
+}
+
+#[test]
+fn reward_assets() {
+    let mut deps = mock_dependencies(&[
+        Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(100u128),
+        },
+        Coin {
+            denom: "uluna".to_string(),
+            amount: Uint128::new(50u128),
+        },
+    ]);
+    deps.querier.with_token_balances(&[("mirtoken", 30)]);
+
+    let init_msg = default_init();
+    let info = mock_info("addr0000", &[]);
+
+    instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
+
+    let uluna = RewardAsset::Native {
+        denom: "uluna".to_string(),
+    };
+    let mir = RewardAsset::Token {
+        contract_addr: "mirtoken".to_string(),
+    };
+
+    // only the owner adds reward assets, once each
+    let info = mock_info("addr0001", &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::AddRewardAsset {
+            asset: uluna.clone(),
+        },
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+
+    let info = mock_info("addr0000", &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::AddRewardAsset {
+            asset: RewardAsset::Native {
+                denom: DEFAULT_REWARD_DENOM.to_string(),
+            },
+        },
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::RewardAssetAlreadyTracked {
+            asset: DEFAULT_REWARD_DENOM.to_string()
+        }
+    );
+
+    for asset in [uluna.clone(), mir.clone()] {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::AddRewardAsset { asset },
+        )
+        .unwrap();
+    }
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::AddRewardAsset {
+            asset: uluna.clone(),
+        },
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::RewardAssetAlreadyTracked {
+            asset: "uluna".to_string()
+        }
+    );
+
+    // the reward assets are not swapped
+    let info = mock_info(MOCK_HUB_CONTRACT_ADDR, &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::SwapToRewardDenom {},
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+
+    let msg = ExecuteMsg::IncreaseBalance {
+        address: String::from("addr0000"),
+        amount: Uint128::from(100u128),
+    };
+    let token_info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
+    execute(deps.as_mut(), mock_env(), token_info.clone(), msg).unwrap();
+
+    // every asset gets its own index: 100 / 100, 50 / 100 and 30 / 100
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::UpdateGlobalIndex {},
+    )
+    .unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap();
+    let state_response: StateResponse = from_binary(&res).unwrap();
+    assert_eq!(state_response.global_index, Decimal::one());
+    assert_eq!(
+        state_response.reward_indexes,
+        vec![
+            RewardIndexResponse {
+                asset: uluna.clone(),
+                global_index: Decimal::percent(50),
+                prev_balance: Uint128::new(50u128),
+            },
+            RewardIndexResponse {
+                asset: mir.clone(),
+                global_index: Decimal::percent(30),
+                prev_balance: Uint128::new(30u128),
+            },
+        ]
+    );
+
+    // a later holder does not accrue what was distributed before
+    let msg = ExecuteMsg::IncreaseBalance {
+        address: String::from("addr0001"),
+        amount: Uint128::from(100u128),
+    };
+    execute(deps.as_mut(), mock_env(), token_info, msg).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::AccruedRewards {
+            address: String::from("addr0001"),
+        },
+    )
+    .unwrap();
+    let accrued: AccruedRewardsResponse = from_binary(&res).unwrap();
+    assert_eq!(accrued.rewards, Uint128::zero());
+    assert!(accrued
+        .asset_rewards
+        .iter()
+        .all(|reward| reward.amount.is_zero()));
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::AccruedRewards {
+            address: String::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let accrued: AccruedRewardsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        accrued,
+        AccruedRewardsResponse {
+            rewards: Uint128::new(100u128),
+            asset_rewards: vec![
+                RewardAssetAmount {
+                    asset: uluna.clone(),
+                    amount: Uint128::new(50u128),
+                },
+                RewardAssetAmount {
+                    asset: mir.clone(),
+                    amount: Uint128::new(30u128),
+                },
+            ],
         }
     );
 
-    // claimed_rewards = 100000 , total_balance = 11
-    // global_index == 9077.727272727272727272
-    let info = mock_info(MOCK_HUB_CONTRACT_ADDR, &[]);
-    let msg = ExecuteMsg::UpdateGlobalIndex {};
-    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
-
-    let info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
-    let msg = ExecuteMsg::IncreaseBalance {
-        address: String::from("addr0000"),
-        amount: Uint128::from(10u128),
-    };
-    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    // the claim pays the whole basket
+    let info = mock_info("addr0000", &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::ClaimRewards { recipient: None },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("addr0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::new(99u128), // 1% tax
+                }],
+            })),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("addr0000"),
+                amount: vec![Coin {
+                    denom: "uluna".to_string(),
+                    amount: Uint128::new(49u128), // 1% tax
+                }],
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "mirtoken".to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("addr0000"),
+                    amount: Uint128::new(30u128),
+                })
+                .unwrap(),
+                funds: vec![],
+            })),
+        ]
+    );
+    assert!(res
+        .attributes
+        .contains(&attr("asset_rewards", "50uluna,30mirtoken")));
 
     let res = query(
         deps.as_ref(),
@@ -341,183 +1331,365 @@ fn increase_balance_with_decimals() {
     )
     .unwrap();
     let holder_response: HolderResponse = from_binary(&res).unwrap();
-    let index = decimal_multiplication_in_256(
-        Decimal::from_ratio(Uint128::new(100000), Uint128::new(11)),
-        Decimal::one(),
-    );
-    let user_pend_reward = decimal_multiplication_in_256(
-        Decimal::from_str("11").unwrap(),
-        decimal_subtraction_in_256(holder_response.index, Decimal::zero()),
-    );
     assert_eq!(
-        holder_response,
-        HolderResponse {
-            address: String::from("addr0000"),
-            balance: Uint128::from(21u128),
-            index,
-            pending_rewards: user_pend_reward,
-        }
+        holder_response.asset_rewards,
+        vec![
+            HolderAssetRewards {
+                asset: uluna,
+                index: Decimal::percent(50),
+                pending_rewards: Decimal::zero(),
+            },
+            HolderAssetRewards {
+                asset: mir,
+                index: Decimal::percent(30),
+                pending_rewards: Decimal::zero(),
+            },
+        ]
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::ClaimRewards { recipient: None },
     );
+    assert_eq!(res.unwrap_err(), ContractError::NoRewardsAccrued {});
 }
 
 #[test]
-fn decrease_balance() {
+fn update_reward_asset_indexes() {
     let mut deps = mock_dependencies(&[Coin {
         denom: "uusd".to_string(),
         amount: Uint128::new(100u128),
     }]);
+    deps.querier.with_token_balances(&[("mirtoken", 30)]);
 
     let init_msg = default_init();
     let info = mock_info("addr0000", &[]);
 
-    instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
-
-    let msg = ExecuteMsg::DecreaseBalance {
-        address: String::from("addr0000"),
-        amount: Uint128::from(100u128),
-    };
+    instantiate(deps.as_mut(), mock_env(), info.clone(), init_msg).unwrap();
 
-    // Failed unautorized
-    let info = mock_info("addr0000", &[]);
-    let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
-    match res {
-        Err(_e) => {}
-        _ => panic!("DO NOT ENTER HERE"),
+    let mir = RewardAsset::Token {
+        contract_addr: "mirtoken".to_string(),
     };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::AddRewardAsset { asset: mir.clone() },
+    )
+    .unwrap();
 
-    // Failed underflow
-    let info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
-    let res = execute(deps.as_mut(), mock_env(), info, msg);
-    match res {
-        Err(StdError::GenericErr { msg, .. }) => {
-            assert_eq!(msg, "Decrease amount cannot exceed user balance: 0")
-        }
-        _ => panic!("DO NOT ENTER HERE"),
-    };
+    // nothing is indexed while nothing is bonded
+    let hub_info = mock_info(MOCK_HUB_CONTRACT_ADDR, &[]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        hub_info.clone(),
+        ExecuteMsg::UpdateRewardAssetIndexes {},
+    )
+    .unwrap();
 
-    // Increase balance first
     let msg = ExecuteMsg::IncreaseBalance {
         address: String::from("addr0000"),
         amount: Uint128::from(100u128),
     };
+    let token_info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
+    execute(deps.as_mut(), mock_env(), token_info, msg).unwrap();
 
-    let info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
-    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
-
-    // claimed_rewards = 100, total_balance = 100
-    // global_index == 1
-    let info = mock_info(MOCK_HUB_CONTRACT_ADDR, &[]);
-    let msg = ExecuteMsg::UpdateGlobalIndex {};
-    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
-
-    let info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
-    let msg = ExecuteMsg::DecreaseBalance {
-        address: String::from("addr0000"),
-        amount: Uint128::from(100u128),
-    };
-    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    // only the hub updates the indexes
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::UpdateRewardAssetIndexes {},
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
 
-    let res = query(
-        deps.as_ref(),
+    // the reward assets are indexed, the uusd are left to be compounded
+    execute(
+        deps.as_mut(),
         mock_env(),
-        QueryMsg::Holder {
-            address: String::from("addr0000"),
-        },
+        hub_info,
+        ExecuteMsg::UpdateRewardAssetIndexes {},
     )
     .unwrap();
-    let holder_response: HolderResponse = from_binary(&res).unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap();
+    let state_response: StateResponse = from_binary(&res).unwrap();
+    assert_eq!(state_response.global_index, Decimal::zero());
+    assert_eq!(state_response.prev_reward_balance, Uint128::zero());
     assert_eq!(
-        holder_response,
-        HolderResponse {
-            address: String::from("addr0000"),
-            balance: Uint128::zero(),
-            index: Decimal::one(),
-            pending_rewards: Decimal::from_str("100").unwrap(),
-        }
+        state_response.reward_indexes,
+        vec![RewardIndexResponse {
+            asset: mir,
+            global_index: Decimal::percent(30),
+            prev_balance: Uint128::new(30u128),
+        }]
     );
 }
 
 #[test]
-fn claim_rewards() {
+fn remove_reward_assets() {
     let mut deps = mock_dependencies(&[Coin {
         denom: "uusd".to_string(),
         amount: Uint128::new(100u128),
     }]);
+    deps.querier.with_token_balances(&[("mirtoken", 30)]);
 
     let init_msg = default_init();
     let info = mock_info("addr0000", &[]);
 
-    instantiate(deps.as_mut(), mock_env(), info, init_msg).unwrap();
+    instantiate(deps.as_mut(), mock_env(), info.clone(), init_msg).unwrap();
+
+    let mir = RewardAsset::Token {
+        contract_addr: "mirtoken".to_string(),
+    };
+    // the balance query of the broken token always fails
+    let broken = RewardAsset::Token {
+        contract_addr: "brokentoken".to_string(),
+    };
+
+    for asset in [mir.clone(), broken.clone()] {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::AddRewardAsset { asset },
+        )
+        .unwrap();
+    }
+
+    // the number of reward assets is capped
+    for i in 0..8 {
+        let asset = RewardAsset::Native {
+            denom: format!("udenom{}", i),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::AddRewardAsset { asset },
+        )
+        .unwrap();
+    }
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::AddRewardAsset {
+            asset: RewardAsset::Native {
+                denom: "uluna".to_string(),
+            },
+        },
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::TooManyRewardAssets { max: 10 }
+    );
 
     let msg = ExecuteMsg::IncreaseBalance {
         address: String::from("addr0000"),
         amount: Uint128::from(100u128),
     };
+    let token_info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
+    execute(deps.as_mut(), mock_env(), token_info, msg).unwrap();
 
-    let info = mock_info(MOCK_TOKEN_CONTRACT_ADDR, &[]);
-    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    // the broken token does not block the index update of the others
+    let hub_info = mock_info(MOCK_HUB_CONTRACT_ADDR, &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        hub_info.clone(),
+        ExecuteMsg::UpdateGlobalIndex {},
+    )
+    .unwrap();
+    assert_eq!(res.events.len(), 1);
+    assert_eq!(res.events[0].ty, "reward_asset_skipped");
+    assert_eq!(res.events[0].attributes[0], attr("asset", "brokentoken"));
 
-    let res = query(
-        deps.as_ref(),
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap();
+    let state_response: StateResponse = from_binary(&res).unwrap();
+    assert_eq!(state_response.global_index, Decimal::one());
+    assert_eq!(
+        state_response.reward_indexes[..2],
+        [
+            RewardIndexResponse {
+                asset: mir.clone(),
+                global_index: Decimal::percent(30),
+                prev_balance: Uint128::new(30u128),
+            },
+            RewardIndexResponse {
+                asset: broken.clone(),
+                global_index: Decimal::zero(),
+                prev_balance: Uint128::zero(),
+            },
+        ]
+    );
+
+    // nor does a balance below the indexed one
+    deps.querier.with_token_balances(&[("mirtoken", 20)]);
+    let res = execute(
+        deps.as_mut(),
         mock_env(),
-        QueryMsg::Holder {
-            address: String::from("addr0000"),
-        },
+        hub_info,
+        ExecuteMsg::UpdateGlobalIndex {},
     )
     .unwrap();
-    let holder_response: HolderResponse = from_binary(&res).unwrap();
+    assert_eq!(res.events.len(), 2);
     assert_eq!(
-        holder_response,
-        HolderResponse {
-            address: String::from("addr0000"),
-            balance: Uint128::from(100u128),
-            index: Decimal::zero(),
-            pending_rewards: Decimal::zero(),
+        res.events[0],
+        Event::new("reward_asset_skipped").add_attributes(vec![
+            attr("asset", "mirtoken"),
+            attr("error", "Balance 20 below the indexed balance 30"),
+        ])
+    );
+    assert_eq!(res.events[1].attributes[0], attr("asset", "brokentoken"));
+
+    // only the owner removes the tracked reward assets
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        ExecuteMsg::RemoveRewardAsset {
+            asset: mir.clone(),
+            recipient: None,
+        },
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::RemoveRewardAsset {
+            asset: RewardAsset::Native {
+                denom: "uluna".to_string(),
+            },
+            recipient: None,
+        },
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::RewardAssetNotTracked {
+            asset: "uluna".to_string()
         }
     );
 
-    // claimed_rewards = 100, total_balance = 100
-    // global_index == 1
-    let info = mock_info(MOCK_HUB_CONTRACT_ADDR, &[]);
-    let msg = ExecuteMsg::UpdateGlobalIndex {};
-    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    // nothing to sweep from the broken token
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::RemoveRewardAsset {
+            asset: broken.clone(),
+            recipient: None,
+        },
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+    assert!(res.attributes.contains(&attr("swept", "0")));
 
-    let msg = ExecuteMsg::ClaimRewards { recipient: None };
-    let info = mock_info("addr0000", &[]);
-    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    // only the 10 received since the last update are swept, the 30 distributed stay
+    deps.querier.with_token_balances(&[("mirtoken", 40)]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::RemoveRewardAsset {
+            asset: mir.clone(),
+            recipient: Some(String::from("addr0002")),
+        },
+    )
+    .unwrap();
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: String::from("addr0000"),
-            amount: vec![Coin {
-                denom: "uusd".to_string(),
-                amount: Uint128::from(99u128), // 1% tax
-            },]
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "mirtoken".to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: String::from("addr0002"),
+                amount: Uint128::new(10u128),
+            })
+            .unwrap(),
+            funds: vec![],
         }))]
     );
 
-    // Set recipient
-    // claimed_rewards = 100, total_balance = 100
-    // global_index == 1
-    let info = mock_info(MOCK_HUB_CONTRACT_ADDR, &[]);
-    let msg = ExecuteMsg::UpdateGlobalIndex {};
-    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_response: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config_response.reward_assets.len(), 8);
+    assert_eq!(
+        config_response.retired_reward_assets,
+        vec![broken, mir.clone()]
+    );
 
-    let msg = ExecuteMsg::ClaimRewards {
-        recipient: Some(String::from("addr0001")),
-    };
-    let info = mock_info("addr0000", &[]);
-    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    // the holder still claims the rewards of the retired asset
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::AccruedRewards {
+            address: String::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let accrued: AccruedRewardsResponse = from_binary(&res).unwrap();
+    assert!(accrued.asset_rewards.contains(&RewardAssetAmount {
+        asset: mir.clone(),
+        amount: Uint128::new(30u128),
+    }));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::ClaimRewards { recipient: None },
+    )
+    .unwrap();
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: String::from("addr0001"),
-            amount: vec![Coin {
-                denom: "uusd".to_string(),
-                amount: Uint128::from(99u128), // 1% tax
-            },]
-        }))]
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("addr0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::new(99u128), // 1% tax
+                }],
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "mirtoken".to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("addr0000"),
+                    amount: Uint128::new(30u128),
+                })
+                .unwrap(),
+                funds: vec![],
+            })),
+        ]
     );
+
+    // a retired asset is distributed again from its index
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::AddRewardAsset { asset: mir.clone() },
+    )
+    .unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_response: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config_response.reward_assets.last(), Some(&mir));
+    assert_eq!(config_response.retired_reward_assets.len(), 1);
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap();
+    let state_response: StateResponse = from_binary(&res).unwrap();
+    assert!(state_response
+        .reward_indexes
+        .contains(&RewardIndexResponse {
+            asset: mir,
+            global_index: Decimal::percent(30),
+            prev_balance: Uint128::zero(),
+        }));
 }
 
 #[test]
@@ -556,6 +1728,7 @@ fn claim_rewards_with_decimals() {
             balance: Uint128::from(11u128),
             index: Decimal::zero(),
             pending_rewards: Decimal::zero(),
+            asset_rewards: vec![],
         }
     );
 
@@ -600,6 +1773,7 @@ fn claim_rewards_with_decimals() {
             balance: Uint128::from(11u128),
             index,
             pending_rewards: Decimal::from_str("0.999999999999999991").unwrap(),
+            asset_rewards: vec![],
         }
     );
 
@@ -610,7 +1784,9 @@ fn claim_rewards_with_decimals() {
         StateResponse {
             global_index: index,
             total_balance: Uint128::new(11u128),
-            prev_reward_balance: Uint128::new(1)
+            prev_reward_balance: Uint128::new(1),
+            total_protocol_fee: Uint128::zero(),
+            reward_indexes: vec![],
         }
     );
 }
@@ -667,18 +1843,21 @@ fn query_holders() {
                     balance: Uint128::from(100u128),
                     index: Decimal::zero(),
                     pending_rewards: Decimal::zero(),
+                    asset_rewards: vec![],
                 },
                 HolderResponse {
                     address: String::from("addr0001"),
                     balance: Uint128::from(200u128),
                     index: Decimal::zero(),
                     pending_rewards: Decimal::zero(),
+                    asset_rewards: vec![],
                 },
                 HolderResponse {
                     address: String::from("addr0002"),
                     balance: Uint128::from(300u128),
                     index: Decimal::zero(),
                     pending_rewards: Decimal::zero(),
+                    asset_rewards: vec![],
                 },
             ],
         }
@@ -703,6 +1882,7 @@ fn query_holders() {
                 balance: Uint128::from(100u128),
                 index: Decimal::zero(),
                 pending_rewards: Decimal::zero(),
+                asset_rewards: vec![],
             }],
         }
     );
@@ -727,12 +1907,14 @@ fn query_holders() {
                     balance: Uint128::from(200u128),
                     index: Decimal::zero(),
                     pending_rewards: Decimal::zero(),
+                    asset_rewards: vec![],
                 },
                 HolderResponse {
                     address: String::from("addr0002"),
                     balance: Uint128::from(300u128),
                     index: Decimal::zero(),
                     pending_rewards: Decimal::zero(),
+                    asset_rewards: vec![],
                 }
             ],
         }
@@ -757,6 +1939,7 @@ fn query_holders() {
                 balance: Uint128::from(200u128),
                 index: Decimal::zero(),
                 pending_rewards: Decimal::zero(),
+                asset_rewards: vec![],
             }],
         }
     );
@@ -789,6 +1972,7 @@ fn proper_prev_balance() {
             global_index,
             total_balance: all_balance,
             prev_reward_balance: rewards,
+            total_protocol_fee: Uint128::zero(),
         },
     )
     .unwrap();
@@ -857,7 +2041,9 @@ fn proper_prev_balance() {
         StateResponse {
             global_index,
             total_balance: all_balance,
-            prev_reward_balance: Uint128::new(1)
+            prev_reward_balance: Uint128::new(1),
+            total_protocol_fee: Uint128::zero(),
+            reward_indexes: vec![],
         }
     );
 
@@ -877,6 +2063,7 @@ fn proper_prev_balance() {
             balance: amount1,
             index: global_index,
             pending_rewards: Decimal::from_str("0.212799238975421283").unwrap(),
+            asset_rewards: vec![],
         }
     );
 
@@ -896,6 +2083,7 @@ fn proper_prev_balance() {
             balance: amount2,
             index: global_index,
             pending_rewards: Decimal::from_str("0.078595712259178717").unwrap(),
+            asset_rewards: vec![],
         }
     );
 
@@ -915,10 +2103,106 @@ fn proper_prev_balance() {
             balance: amount3,
             index: global_index,
             pending_rewards: Decimal::from_str("0.701700000000000000").unwrap(),
+            asset_rewards: vec![],
         }
     );
 }
 
+#[test]
+fn proper_ownership_transfer() {
+    let mut deps = mock_dependencies(&[]);
+    let init_msg = default_init();
+    let info = mock_info("owner", &[]);
+    instantiate(deps.as_mut(), mock_env(), info.clone(), init_msg).unwrap();
+
+    let propose = ExecuteMsg::ProposeNewOwner {
+        new_owner: "new_owner".to_string(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("new_owner", &[]),
+        propose.clone(),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), propose).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "propose_new_owner"),
+            attr("new_owner", "new_owner")
+        ]
+    );
+
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config.owner, Some("owner".to_string()));
+    assert_eq!(config.pending_owner, Some("new_owner".to_string()));
+
+    // only the owner cancels
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("new_owner", &[]),
+        ExecuteMsg::CancelOwnershipProposal {},
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::AcceptOwnership {},
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("new_owner", &[]),
+        ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap();
+
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config.owner, Some("new_owner".to_string()));
+    assert_eq!(config.pending_owner, None);
+
+    // the previous owner has no rights anymore
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::CancelOwnershipProposal {},
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("new_owner", &[]),
+        ExecuteMsg::CancelOwnershipProposal {},
+    );
+    assert_eq!(res.unwrap_err(), ContractError::NoPendingOwner {});
+
+    // the migration can not replace the owner
+    let msg = MigrateMsg {
+        owner: Some("admin".to_string()),
+    };
+    let res = migrate(deps.as_mut(), mock_env(), msg.clone());
+    assert_eq!(res.unwrap_err(), ContractError::OwnerAlreadySet {});
+
+    // but assigns one to a contract instantiated without it
+    let mut config = read_config(&deps.storage).unwrap();
+    config.owner = None;
+    store_config(&mut deps.storage, &config).unwrap();
+    migrate(deps.as_mut(), mock_env(), msg).unwrap();
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config.owner, Some("admin".to_string()));
+}
+
 #[test]
 fn test_migrate() {
     let mut deps = mock_dependencies(&[Coin {
@@ -926,5 +2210,31 @@ fn test_migrate() {
         amount: Uint128::new(100u128),
     }]);
 
-    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    migrate(deps.as_mut(), mock_env(), MigrateMsg { owner: None }).unwrap();
+
+    let version = get_contract_version(&deps.storage).unwrap();
+    assert_eq!(version.contract, "crates.io:anchor-basset-reward");
+    assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+
+    // downgrades are refused
+    set_contract_version(&mut deps.storage, "crates.io:anchor-basset-reward", "9.0.0").unwrap();
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { owner: None });
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::CannotDowngrade {
+            stored: "9.0.0".to_string(),
+            new: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    );
+
+    // another contract can not be migrated to the reward contract
+    set_contract_version(&mut deps.storage, "crates.io:anchor-basset-hub", "1.0.0").unwrap();
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { owner: None });
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::ContractNameMismatch {
+            expected: "crates.io:anchor-basset-reward".to_string(),
+            found: "crates.io:anchor-basset-hub".to_string(),
+        }
+    );
 }
@@ -1,13 +1,21 @@
-use crate::querier::query_token_contract;
+use crate::global::{query_exchange_rates, query_swap_spread};
+use crate::querier::{query_paused_operations, query_token_contract, query_underlying_denom};
 use crate::state::{
-    read_config, read_holder, read_holders, read_state, store_holder, store_state, Config, Holder,
-    State,
+    read_config, read_holder, read_holder_asset_reward, read_holder_asset_rewards, read_holders,
+    read_max_spread, read_reward_index, read_state, store_holder, store_holder_asset_reward,
+    store_reward_index, store_state, Config, Holder, State,
 };
-use basset::reward::{AccruedRewardsResponse, HolderResponse, HoldersResponse};
+use basset::contract_error::ContractError;
+use basset::hub::{ExecuteMsg as HubExecuteMsg, PausableOperation};
+use basset::reward::{
+    AccruedRewardsResponse, ExecuteMsg, HolderResponse, HoldersResponse, RewardAsset,
+    RewardAssetAmount,
+};
+use cw20::Cw20ExecuteMsg;
 
 use cosmwasm_std::{
-    attr, BankMsg, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo, Response, StdError,
-    StdResult, Uint128,
+    attr, to_binary, BankMsg, CanonicalAddr, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env,
+    MessageInfo, Response, StdError, StdResult, Storage, Uint128, WasmMsg,
 };
 
 use crate::math::{
@@ -15,14 +23,17 @@ use crate::math::{
 };
 use basset::deduct_tax;
 use std::str::FromStr;
-use terra_cosmwasm::TerraMsgWrapper;
+use terra_cosmwasm::{create_swap_msg, TerraMsgWrapper};
 
 pub fn execute_claim_rewards(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
     recipient: Option<String>,
-) -> StdResult<Response<TerraMsgWrapper>> {
+) -> Result<Response<TerraMsgWrapper>, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    assert_claim_not_paused(deps.as_ref(), &config)?;
+
     let holder_addr = info.sender;
     let holder_addr_raw = deps.api.addr_canonicalize(holder_addr.as_str())?;
     let recipient = match recipient {
@@ -30,60 +41,312 @@ pub fn execute_claim_rewards(
         None => holder_addr.clone(),
     };
 
-    let mut holder: Holder = read_holder(deps.storage, &holder_addr_raw)?;
-    let mut state: State = read_state(deps.storage)?;
-    let config: Config = read_config(deps.storage)?;
+    let rewards = settle_rewards(deps.storage, &holder_addr_raw)?;
+    let holder = read_holder(deps.storage, &holder_addr_raw)?;
+    let asset_rewards = settle_asset_rewards(
+        deps.storage,
+        &config.claimable_assets(),
+        &holder_addr_raw,
+        holder.balance,
+    )?;
+
+    if rewards.is_zero() && asset_rewards.is_empty() {
+        return Err(ContractError::NoRewardsAccrued {});
+    }
 
-    let reward_with_decimals =
-        calculate_decimal_rewards(state.global_index, holder.index, holder.balance)?;
+    let mut messages: Vec<CosmosMsg<TerraMsgWrapper>> = vec![];
+    if !rewards.is_zero() {
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![deduct_tax(
+                &deps.querier,
+                Coin {
+                    denom: config.reward_denom,
+                    amount: rewards,
+                },
+            )?],
+        }));
+    }
+    for (asset, amount) in asset_rewards.iter() {
+        messages.push(asset_transfer_msg(
+            deps.as_ref(),
+            asset,
+            *amount,
+            recipient.to_string(),
+        )?);
+    }
 
-    let all_reward_with_decimals =
-        decimal_summation_in_256(reward_with_decimals, holder.pending_rewards);
-    let decimals = get_decimals(all_reward_with_decimals)?;
+    let mut attributes = vec![
+        attr("action", "claim_reward"),
+        attr("holder_address", holder_addr),
+        attr("rewards", rewards),
+    ];
+    if !asset_rewards.is_empty() {
+        let claimed: Vec<String> = asset_rewards
+            .iter()
+            .map(|(asset, amount)| format!("{}{}", amount, asset))
+            .collect();
+        attributes.push(attr("asset_rewards", claimed.join(",")));
+    }
 
-    let rewards = all_reward_with_decimals * Uint128::new(1);
+    let res = Response::new()
+        .add_attributes(attributes)
+        .add_messages(messages);
 
+    Ok(res)
+}
+
+/// Claim the rewards of the sender, swap them to the underlying denom of the hub
+/// and bond them through the hub, which mints the bAsset to the sender.
+/// The bond happens in `CompoundHook` once the swap has been executed.
+/// Without `max_spread`, the max spread of the reward denom applies, if any.
+pub fn execute_claim_and_compound(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    validator: Option<String>,
+    max_spread: Option<Decimal>,
+) -> Result<Response<TerraMsgWrapper>, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    assert_claim_not_paused(deps.as_ref(), &config)?;
+
+    let holder_addr = info.sender;
+    let holder_addr_raw = deps.api.addr_canonicalize(holder_addr.as_str())?;
+
+    let rewards = settle_rewards(deps.storage, &holder_addr_raw)?;
     if rewards.is_zero() {
-        return Err(StdError::generic_err("No rewards have accrued yet"));
+        return Err(ContractError::NoRewardsAccrued {});
     }
 
-    let new_balance = (state.prev_reward_balance.checked_sub(rewards))?;
-    state.prev_reward_balance = new_balance;
-    store_state(deps.storage, &state)?;
+    let hub_addr = deps.api.addr_humanize(&config.hub_contract)?;
+    let underlying_denom = query_underlying_denom(deps.as_ref(), hub_addr)?;
+    let prev_balance = deps
+        .querier
+        .query_balance(env.contract.address.clone(), underlying_denom.as_str())?
+        .amount;
 
-    holder.pending_rewards = decimals;
-    holder.index = state.global_index;
-    store_holder(deps.storage, &holder_addr_raw, &holder)?;
+    let offer_coin = Coin {
+        denom: config.reward_denom,
+        amount: rewards,
+    };
+    let max_spread = match max_spread {
+        Some(max_spread) => Some(max_spread),
+        None => read_max_spread(deps.storage, &offer_coin.denom)?,
+    };
+    if let Some(max_spread) = max_spread {
+        let exchange_rates = query_exchange_rates(
+            &deps,
+            underlying_denom.clone(),
+            vec![offer_coin.denom.clone()],
+        )?;
+        let exchange_rate = exchange_rates
+            .exchange_rates
+            .iter()
+            .find(|item| item.quote_denom == offer_coin.denom)
+            .map(|item| item.exchange_rate)
+            .ok_or_else(|| {
+                StdError::generic_err(format!("No exchange rate for {}", offer_coin.denom))
+            })?;
+        let spread = query_swap_spread(&deps, &offer_coin, &underlying_denom, exchange_rate)?;
+        if spread > max_spread {
+            return Err(ContractError::MaxSpreadAssertion {});
+        }
+    }
+
+    let messages: Vec<CosmosMsg<TerraMsgWrapper>> = vec![
+        create_swap_msg(offer_coin, underlying_denom),
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            msg: to_binary(&ExecuteMsg::CompoundHook {
+                holder: holder_addr.to_string(),
+                prev_balance,
+                validator,
+            })?,
+            funds: vec![],
+        }),
+    ];
 
-    let bank_msg: CosmosMsg<TerraMsgWrapper> = CosmosMsg::Bank(BankMsg::Send {
-        to_address: recipient.to_string(),
-        amount: vec![deduct_tax(
+    let res = Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "claim_and_compound"),
+        attr("holder_address", holder_addr),
+        attr("rewards", rewards),
+    ]);
+
+    Ok(res)
+}
+
+/// Bond the underlying coins received from the swap since `prev_balance` for `holder`.
+/// Only the contract itself is allowed to execute
+pub fn execute_compound_hook(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    holder: String,
+    prev_balance: Uint128,
+    validator: Option<String>,
+) -> Result<Response<TerraMsgWrapper>, ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let config: Config = read_config(deps.storage)?;
+    let hub_addr = deps.api.addr_humanize(&config.hub_contract)?;
+    let underlying_denom = query_underlying_denom(deps.as_ref(), hub_addr.clone())?;
+    let balance = deps
+        .querier
+        .query_balance(env.contract.address, underlying_denom.as_str())?
+        .amount;
+
+    let amount = balance.checked_sub(prev_balance)?;
+    if amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let bond_msg: CosmosMsg<TerraMsgWrapper> = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: hub_addr.to_string(),
+        msg: to_binary(&HubExecuteMsg::BondFor {
+            recipient: holder.clone(),
+            validator,
+        })?,
+        funds: vec![deduct_tax(
             &deps.querier,
             Coin {
-                denom: config.reward_denom,
-                amount: rewards,
+                denom: underlying_denom,
+                amount,
             },
         )?],
     });
 
     let res = Response::new()
         .add_attributes(vec![
-            attr("action", "claim_reward"),
-            attr("holder_address", holder_addr),
-            attr("rewards", rewards),
+            attr("action", "compound_rewards"),
+            attr("holder_address", holder),
+            attr("bonded", amount),
         ])
-        .add_message(bank_msg);
+        .add_message(bond_msg);
 
     Ok(res)
 }
 
+fn assert_claim_not_paused(deps: Deps, config: &Config) -> Result<(), ContractError> {
+    let hub_addr = deps.api.addr_humanize(&config.hub_contract)?;
+    if query_paused_operations(deps, hub_addr)?.contains(&PausableOperation::ClaimRewards) {
+        return Err(ContractError::OperationPaused {
+            operation: PausableOperation::ClaimRewards,
+        });
+    }
+    Ok(())
+}
+
+/// Move the accrued rewards of the holder out of the reward accounting.
+/// Return the amount to pay out, which can be zero; the decimals stay pending.
+fn settle_rewards(
+    storage: &mut dyn Storage,
+    holder_addr_raw: &CanonicalAddr,
+) -> Result<Uint128, ContractError> {
+    let mut holder: Holder = read_holder(storage, holder_addr_raw)?;
+    let mut state: State = read_state(storage)?;
+
+    let reward_with_decimals =
+        calculate_decimal_rewards(state.global_index, holder.index, holder.balance)?;
+
+    let all_reward_with_decimals =
+        decimal_summation_in_256(reward_with_decimals, holder.pending_rewards);
+    let decimals = get_decimals(all_reward_with_decimals)?;
+
+    let rewards = all_reward_with_decimals * Uint128::new(1);
+
+    let new_balance = (state.prev_reward_balance.checked_sub(rewards))?;
+    state.prev_reward_balance = new_balance;
+    store_state(storage, &state)?;
+
+    holder.pending_rewards = decimals;
+    holder.index = state.global_index;
+    store_holder(storage, holder_addr_raw, &holder)?;
+
+    Ok(rewards)
+}
+
+/// Add the rewards accrued by `balance` in each reward asset to the pending rewards
+/// of the holder and move its indexes to the global ones.
+fn accrue_asset_rewards(
+    storage: &mut dyn Storage,
+    reward_assets: &[RewardAsset],
+    holder_addr_raw: &CanonicalAddr,
+    balance: Uint128,
+) -> StdResult<()> {
+    for asset in reward_assets {
+        let global_index = read_reward_index(storage, asset)?.global_index;
+        let mut reward = read_holder_asset_reward(storage, holder_addr_raw, asset)?;
+        let accrued = calculate_decimal_rewards(global_index, reward.index, balance)?;
+        reward.pending_rewards = decimal_summation_in_256(accrued, reward.pending_rewards);
+        reward.index = global_index;
+        store_holder_asset_reward(storage, holder_addr_raw, asset, &reward)?;
+    }
+    Ok(())
+}
+
+/// Move the accrued rewards of the holder in each reward asset out of the reward accounting.
+/// Return the non zero amounts to pay out; the decimals stay pending.
+fn settle_asset_rewards(
+    storage: &mut dyn Storage,
+    reward_assets: &[RewardAsset],
+    holder_addr_raw: &CanonicalAddr,
+    balance: Uint128,
+) -> StdResult<Vec<(RewardAsset, Uint128)>> {
+    accrue_asset_rewards(storage, reward_assets, holder_addr_raw, balance)?;
+
+    let mut rewards = vec![];
+    for asset in reward_assets {
+        let mut reward = read_holder_asset_reward(storage, holder_addr_raw, asset)?;
+        let amount = reward.pending_rewards * Uint128::new(1);
+        if amount.is_zero() {
+            continue;
+        }
+
+        let mut index = read_reward_index(storage, asset)?;
+        index.prev_balance = index.prev_balance.checked_sub(amount)?;
+        store_reward_index(storage, asset, &index)?;
+
+        reward.pending_rewards = get_decimals(reward.pending_rewards)?;
+        store_holder_asset_reward(storage, holder_addr_raw, asset, &reward)?;
+        rewards.push((asset.clone(), amount));
+    }
+    Ok(rewards)
+}
+
+pub(crate) fn asset_transfer_msg(
+    deps: Deps,
+    asset: &RewardAsset,
+    amount: Uint128,
+    recipient: String,
+) -> StdResult<CosmosMsg<TerraMsgWrapper>> {
+    Ok(match asset {
+        RewardAsset::Native { denom } => CosmosMsg::Bank(BankMsg::Send {
+            to_address: recipient,
+            amount: vec![deduct_tax(
+                &deps.querier,
+                Coin {
+                    denom: denom.clone(),
+                    amount,
+                },
+            )?],
+        }),
+        RewardAsset::Token { contract_addr } => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: contract_addr.clone(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer { recipient, amount })?,
+            funds: vec![],
+        }),
+    })
+}
+
 pub fn execute_increase_balance(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
     address: String,
     amount: Uint128,
-) -> StdResult<Response<TerraMsgWrapper>> {
+) -> Result<Response<TerraMsgWrapper>, ContractError> {
     let config = read_config(deps.storage)?;
     let owner_human = deps.api.addr_humanize(&config.hub_contract)?;
     let address_raw = deps.api.addr_canonicalize(&address)?;
@@ -95,11 +358,17 @@ pub fn execute_increase_balance(
 
     // Check sender is token contract
     if sender != token_address {
-        return Err(StdError::generic_err("unauthorized"));
+        return Err(ContractError::Unauthorized {});
     }
 
     let mut state: State = read_state(deps.storage)?;
     let mut holder: Holder = read_holder(deps.storage, &address_raw)?;
+    accrue_asset_rewards(
+        deps.storage,
+        &config.claimable_assets(),
+        &address_raw,
+        holder.balance,
+    )?;
 
     // get decimals
     let rewards = calculate_decimal_rewards(state.global_index, holder.index, holder.balance)?;
@@ -128,7 +397,7 @@ pub fn execute_decrease_balance(
     info: MessageInfo,
     address: String,
     amount: Uint128,
-) -> StdResult<Response<TerraMsgWrapper>> {
+) -> Result<Response<TerraMsgWrapper>, ContractError> {
     let config = read_config(deps.storage)?;
     let hub_contract = deps.api.addr_humanize(&config.hub_contract)?;
     let address_raw = deps.api.addr_canonicalize(&address)?;
@@ -137,17 +406,22 @@ pub fn execute_decrease_balance(
     if query_token_contract(deps.as_ref(), hub_contract)?
         != deps.api.addr_canonicalize(info.sender.as_str())?
     {
-        return Err(StdError::generic_err("unauthorized"));
+        return Err(ContractError::Unauthorized {});
     }
 
     let mut state: State = read_state(deps.storage)?;
     let mut holder: Holder = read_holder(deps.storage, &address_raw)?;
     if holder.balance < amount {
-        return Err(StdError::generic_err(format!(
-            "Decrease amount cannot exceed user balance: {}",
-            holder.balance
-        )));
+        return Err(ContractError::DecreaseExceedsBalance {
+            balance: holder.balance,
+        });
     }
+    accrue_asset_rewards(
+        deps.storage,
+        &config.claimable_assets(),
+        &address_raw,
+        holder.balance,
+    )?;
 
     let rewards = calculate_decimal_rewards(state.global_index, holder.index, holder.balance)?;
 
@@ -173,7 +447,8 @@ pub fn execute_decrease_balance(
 pub fn query_accrued_rewards(deps: Deps, address: String) -> StdResult<AccruedRewardsResponse> {
     let global_index = read_state(deps.storage)?.global_index;
 
-    let holder: Holder = read_holder(deps.storage, &deps.api.addr_canonicalize(&address)?)?;
+    let holder_addr_raw = deps.api.addr_canonicalize(&address)?;
+    let holder: Holder = read_holder(deps.storage, &holder_addr_raw)?;
     let reward_with_decimals =
         calculate_decimal_rewards(global_index, holder.index, holder.balance)?;
     let all_reward_with_decimals =
@@ -181,16 +456,34 @@ pub fn query_accrued_rewards(deps: Deps, address: String) -> StdResult<AccruedRe
 
     let rewards = all_reward_with_decimals * Uint128::new(1);
 
-    Ok(AccruedRewardsResponse { rewards })
+    let asset_rewards = read_config(deps.storage)?
+        .claimable_assets()
+        .into_iter()
+        .map(|asset| {
+            let global_index = read_reward_index(deps.storage, &asset)?.global_index;
+            let reward = read_holder_asset_reward(deps.storage, &holder_addr_raw, &asset)?;
+            let accrued = calculate_decimal_rewards(global_index, reward.index, holder.balance)?;
+            let amount =
+                decimal_summation_in_256(accrued, reward.pending_rewards) * Uint128::new(1);
+            Ok(RewardAssetAmount { asset, amount })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(AccruedRewardsResponse {
+        rewards,
+        asset_rewards,
+    })
 }
 
 pub fn query_holder(deps: Deps, address: String) -> StdResult<HolderResponse> {
-    let holder: Holder = read_holder(deps.storage, &deps.api.addr_canonicalize(&address)?)?;
+    let holder_addr_raw = deps.api.addr_canonicalize(&address)?;
+    let holder: Holder = read_holder(deps.storage, &holder_addr_raw)?;
     Ok(HolderResponse {
         address,
         balance: holder.balance,
         index: holder.index,
         pending_rewards: holder.pending_rewards,
+        asset_rewards: read_holder_asset_rewards(deps.storage, &holder_addr_raw)?,
     })
 }
 
@@ -225,12 +518,12 @@ fn calculate_decimal_rewards(
 
 // calculate the reward with decimal
 fn get_decimals(value: Decimal) -> StdResult<Decimal> {
-    let stringed: &str = &*value.to_string();
-    let parts: &[&str] = &*stringed.split('.').collect::<Vec<&str>>();
+    let stringed: &str = &value.to_string();
+    let parts: &[&str] = &stringed.split('.').collect::<Vec<&str>>();
     match parts.len() {
         1 => Ok(Decimal::zero()),
         2 => {
-            let decimals = Decimal::from_str(&*("0.".to_owned() + parts[1]))?;
+            let decimals = Decimal::from_str(&("0.".to_owned() + parts[1]))?;
             Ok(decimals)
         }
         _ => Err(StdError::generic_err("Unexpected number of dots")),
@@ -1,22 +1,32 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 
-use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+use cosmwasm_std::{attr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
 
 use cw20_legacy::allowances::{execute_decrease_allowance, execute_increase_allowance};
 use cw20_legacy::contract::instantiate as cw20_init;
 use cw20_legacy::contract::query as cw20_query;
 use cw20_legacy::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
 
+use crate::error::ContractError;
 use crate::handler::*;
-use crate::msg::TokenInitMsg;
+use crate::msg::{MigrateMsg, TokenInitMsg};
 use crate::state::store_hub_contract;
+use basset::contract_error::ContractError as BassetContractError;
+use basset::version::migrate_contract_version;
+use cw2::{get_contract_version, set_contract_version};
 use cw20::MinterResponse;
-use cw20_legacy::ContractError;
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:anchor-basset-token";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// cw20-legacy records its own name on instantiation.
+const CW20_LEGACY_CONTRACT_NAME: &str = "crates.io:cw20-base";
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     msg: TokenInitMsg,
@@ -27,7 +37,7 @@ pub fn instantiate(
     )?;
 
     cw20_init(
-        deps,
+        deps.branch(),
         env,
         info,
         InstantiateMsg {
@@ -42,6 +52,8 @@ pub fn instantiate(
         },
     )?;
 
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
     Ok(Response::default())
 }
 
@@ -67,12 +79,16 @@ pub fn execute(
             spender,
             amount,
             expires,
-        } => execute_increase_allowance(deps, env, info, spender, amount, expires),
+        } => Ok(execute_increase_allowance(
+            deps, env, info, spender, amount, expires,
+        )?),
         ExecuteMsg::DecreaseAllowance {
             spender,
             amount,
             expires,
-        } => execute_decrease_allowance(deps, env, info, spender, amount, expires),
+        } => Ok(execute_decrease_allowance(
+            deps, env, info, spender, amount, expires,
+        )?),
         ExecuteMsg::TransferFrom {
             owner,
             recipient,
@@ -92,3 +108,25 @@ pub fn execute(
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     cw20_query(deps, _env, msg)
 }
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(
+    deps: DepsMut,
+    _env: Env,
+    _msg: MigrateMsg,
+) -> Result<Response, BassetContractError> {
+    // tokens instantiated before the version was recorded carry the cw20-legacy one.
+    let legacy = get_contract_version(deps.storage)
+        .map(|stored| stored.contract == CW20_LEGACY_CONTRACT_NAME)
+        .unwrap_or(true);
+    if legacy {
+        set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    } else {
+        migrate_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "migrate"),
+        attr("version", CONTRACT_VERSION),
+    ]))
+}
@@ -0,0 +1,16 @@
+use basset::contract_error::ContractError as BassetContractError;
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+/// The cw20 errors of the underlying token, plus the bAsset ones raised by the hub checks.
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Cw20(#[from] cw20_legacy::ContractError),
+
+    #[error("{0}")]
+    Basset(#[from] BassetContractError),
+}
@@ -2,7 +2,8 @@ use cosmwasm_std::{
     to_binary, Binary, CosmosMsg, DepsMut, Env, MessageInfo, Response, SubMsg, Uint128, WasmMsg,
 };
 
-use crate::querier::query_reward_contract;
+use crate::error::ContractError;
+use crate::querier::{assert_transfer_not_paused, query_reward_contract};
 use basset::reward::ExecuteMsg::{DecreaseBalance, IncreaseBalance};
 use cw20_legacy::allowances::{
     execute_burn_from as cw20_burn_from, execute_send_from as cw20_send_from,
@@ -12,7 +13,6 @@ use cw20_legacy::contract::{
     execute_burn as cw20_burn, execute_mint as cw20_mint, execute_send as cw20_send,
     execute_transfer as cw20_transfer,
 };
-use cw20_legacy::ContractError;
 
 pub fn execute_transfer(
     deps: DepsMut,
@@ -21,6 +21,8 @@ pub fn execute_transfer(
     recipient: String,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
+    assert_transfer_not_paused(&deps)?;
+
     let sender = info.sender.clone();
     let reward_contract = query_reward_contract(&deps)?;
 
@@ -107,11 +109,13 @@ pub fn execute_send(
     amount: Uint128,
     msg: Binary,
 ) -> Result<Response, ContractError> {
+    assert_transfer_not_paused(&deps)?;
+
     let sender = info.sender.clone();
     let reward_contract = query_reward_contract(&deps)?;
 
     let res: Response = cw20_send(deps, env, info, contract.clone(), amount, msg)?;
-    let messages = vec![
+    let messages = [
         vec![
             SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: reward_contract.to_string(),
@@ -149,6 +153,8 @@ pub fn execute_transfer_from(
     recipient: String,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
+    assert_transfer_not_paused(&deps)?;
+
     let reward_contract = query_reward_contract(&deps)?;
 
     let valid_owner = deps.api.addr_validate(owner.as_str())?;
@@ -214,12 +220,14 @@ pub fn execute_send_from(
     amount: Uint128,
     msg: Binary,
 ) -> Result<Response, ContractError> {
+    assert_transfer_not_paused(&deps)?;
+
     let reward_contract = query_reward_contract(&deps)?;
 
     let valid_owner = deps.api.addr_validate(owner.as_str())?;
 
     let res: Response = cw20_send_from(deps, env, info, owner, contract.clone(), amount, msg)?;
-    let messages = vec![
+    let messages = [
         vec![
             SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: reward_contract.to_string(),
@@ -1,8 +1,9 @@
-use cosmwasm_std::{Addr, Binary, DepsMut, QueryRequest, StdResult, WasmQuery};
+use cosmwasm_std::{from_slice, Addr, Binary, DepsMut, QueryRequest, StdResult, WasmQuery};
 use cosmwasm_storage::to_length_prefixed;
 
 use crate::state::read_hub_contract;
-use basset::hub::Config;
+use basset::contract_error::ContractError;
+use basset::hub::{Config, PausableOperation, PAUSED_OPERATIONS_KEY};
 
 pub fn query_reward_contract(deps: &DepsMut) -> StdResult<Addr> {
     let hub_address = deps
@@ -25,3 +26,23 @@ pub fn query_reward_contract(deps: &DepsMut) -> StdResult<Addr> {
         .unwrap();
     Ok(address)
 }
+
+/// Fail if token transfers are paused on the hub.
+pub fn assert_transfer_not_paused(deps: &DepsMut) -> Result<(), ContractError> {
+    let hub_address = deps.api.addr_humanize(&read_hub_contract(deps.storage)?)?;
+
+    let paused: Vec<PausableOperation> = match deps
+        .querier
+        .query_wasm_raw(hub_address, PAUSED_OPERATIONS_KEY.as_bytes())?
+    {
+        Some(paused) => from_slice(&paused)?,
+        None => vec![],
+    };
+
+    if paused.contains(&PausableOperation::Transfer) {
+        return Err(ContractError::OperationPaused {
+            operation: PausableOperation::Transfer,
+        });
+    }
+    Ok(())
+}
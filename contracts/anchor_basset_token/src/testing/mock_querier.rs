@@ -1,4 +1,4 @@
-use basset::hub::Config;
+use basset::hub::{Config, PausableOperation, PAUSED_OPERATIONS_KEY};
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
     from_slice, to_binary, Api, Coin, ContractResult, Empty, OwnedDeps, Querier, QuerierResult,
@@ -26,6 +26,7 @@ pub fn mock_dependencies(
 
 pub struct WasmMockQuerier {
     base: MockQuerier<Empty>,
+    paused_operations: Vec<PausableOperation>,
 }
 
 impl Querier for WasmMockQuerier {
@@ -67,6 +68,8 @@ impl WasmMockQuerier {
                             ),
                         };
                         SystemResult::Ok(ContractResult::from(to_binary(&config)))
+                    } else if key.as_slice() == PAUSED_OPERATIONS_KEY.as_bytes() {
+                        SystemResult::Ok(ContractResult::from(to_binary(&self.paused_operations)))
                     } else {
                         unimplemented!()
                     }
@@ -81,6 +84,14 @@ impl WasmMockQuerier {
 
 impl WasmMockQuerier {
     pub fn new(base: MockQuerier<Empty>) -> Self {
-        WasmMockQuerier { base }
+        WasmMockQuerier {
+            base,
+            paused_operations: vec![],
+        }
+    }
+
+    // configure the operations paused on the hub
+    pub fn with_paused_operations(&mut self, paused_operations: &[PausableOperation]) {
+        self.paused_operations = paused_operations.to_vec();
     }
 }
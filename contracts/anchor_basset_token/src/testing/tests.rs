@@ -4,13 +4,17 @@ use cosmwasm_std::{
     WasmMsg,
 };
 
+use basset::contract_error::ContractError;
+use basset::hub::PausableOperation;
 use basset::reward::ExecuteMsg::{DecreaseBalance, IncreaseBalance};
+use cw2::{get_contract_version, set_contract_version};
 use cw20::{Cw20ReceiveMsg, MinterResponse, TokenInfoResponse};
 use cw20_legacy::contract::{query_minter, query_token_info};
 use cw20_legacy::msg::ExecuteMsg;
 
-use crate::contract::{execute, instantiate};
-use crate::msg::TokenInitMsg;
+use crate::contract::{execute, instantiate, migrate};
+use crate::error::ContractError as TokenContractError;
+use crate::msg::{MigrateMsg, TokenInitMsg};
 use crate::state::read_hub_contract;
 use crate::testing::mock_querier::{
     mock_dependencies, MOCK_HUB_CONTRACT_ADDR, MOCK_REWARD_CONTRACT_ADDR,
@@ -113,6 +117,34 @@ fn proper_initialization() {
     );
 
     assert_eq!(read_hub_contract(&deps.storage).unwrap(), hub_contract_raw);
+
+    let version = get_contract_version(&deps.storage).unwrap();
+    assert_eq!(version.contract, "crates.io:anchor-basset-token");
+}
+
+#[test]
+fn migrate_from_cw20_legacy_version() {
+    let mut deps = mock_dependencies(&[]);
+    do_init_with_minter(deps.borrow_mut(), MOCK_HUB_CONTRACT_ADDR, None);
+
+    // tokens deployed before the version was recorded carry the cw20-legacy one
+    set_contract_version(&mut deps.storage, "crates.io:cw20-base", "0.2.0").unwrap();
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+    let version = get_contract_version(&deps.storage).unwrap();
+    assert_eq!(version.contract, "crates.io:anchor-basset-token");
+    assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+
+    // downgrades are refused
+    set_contract_version(&mut deps.storage, "crates.io:anchor-basset-token", "9.0.0").unwrap();
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {});
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::CannotDowngrade {
+            stored: "9.0.0".to_string(),
+            new: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    );
 }
 
 #[test]
@@ -159,6 +191,22 @@ fn transfer() {
             })),
         ]
     );
+
+    // transfers are rejected while paused on the hub
+    deps.querier
+        .with_paused_operations(&[PausableOperation::Transfer]);
+    let info = mock_info("addr0001", &[]);
+    let msg = ExecuteMsg::Transfer {
+        recipient: String::from("addr0002"),
+        amount: Uint128::new(1u128),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    assert_eq!(
+        res.unwrap_err(),
+        TokenContractError::Basset(ContractError::OperationPaused {
+            operation: PausableOperation::Transfer
+        })
+    );
 }
 
 #[test]
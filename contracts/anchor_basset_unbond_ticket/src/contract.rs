@@ -0,0 +1,136 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+
+use cosmwasm_std::{attr, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdResult};
+
+use basset::contract_error::ContractError as BassetContractError;
+use basset::unbond_ticket::{ExecuteMsg, InstantiateMsg, MigrateMsg, UnbondTicket};
+use basset::version::migrate_contract_version;
+use cw2::set_contract_version;
+use cw721_base::{ContractError, Cw721Contract, MintMsg, QueryMsg};
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:anchor-basset-unbond-ticket";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub type UnbondTicketContract<'a> = Cw721Contract<'a, UnbondTicket, Empty>;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> StdResult<Response> {
+    UnbondTicketContract::default().instantiate(
+        deps.branch(),
+        env,
+        info,
+        cw721_base::InstantiateMsg {
+            name: msg.name,
+            symbol: msg.symbol,
+            minter: msg.hub_contract,
+        },
+    )?;
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    let contract = UnbondTicketContract::default();
+    let msg = match msg {
+        ExecuteMsg::Burn { token_id } => return execute_burn(deps, env, info, token_id),
+        ExecuteMsg::Mint {
+            token_id,
+            owner,
+            ticket,
+        } => cw721_base::ExecuteMsg::Mint(MintMsg {
+            token_id,
+            owner,
+            token_uri: None,
+            extension: ticket,
+        }),
+        ExecuteMsg::TransferNft {
+            recipient,
+            token_id,
+        } => cw721_base::ExecuteMsg::TransferNft {
+            recipient,
+            token_id,
+        },
+        ExecuteMsg::SendNft {
+            contract,
+            token_id,
+            msg,
+        } => cw721_base::ExecuteMsg::SendNft {
+            contract,
+            token_id,
+            msg,
+        },
+        ExecuteMsg::Approve {
+            spender,
+            token_id,
+            expires,
+        } => cw721_base::ExecuteMsg::Approve {
+            spender,
+            token_id,
+            expires,
+        },
+        ExecuteMsg::Revoke { spender, token_id } => {
+            cw721_base::ExecuteMsg::Revoke { spender, token_id }
+        }
+        ExecuteMsg::ApproveAll { operator, expires } => {
+            cw721_base::ExecuteMsg::ApproveAll { operator, expires }
+        }
+        ExecuteMsg::RevokeAll { operator } => cw721_base::ExecuteMsg::RevokeAll { operator },
+    };
+    contract.execute(deps, env, info, msg)
+}
+
+/// Burn a ticket whose request has been withdrawn.
+/// Only the hub is allowed to execute
+pub fn execute_burn(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let contract = UnbondTicketContract::default();
+    if info.sender != contract.minter.load(deps.storage)? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // fails for unknown tickets
+    contract.tokens.load(deps.storage, &token_id)?;
+    contract.tokens.remove(deps.storage, &token_id)?;
+    let count = contract.token_count(deps.storage)?;
+    contract.token_count.save(deps.storage, &(count - 1))?;
+
+    Ok(Response::new().add_attributes(vec![attr("action", "burn"), attr("token_id", token_id)]))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    UnbondTicketContract::default().query(deps, env, msg)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(
+    deps: DepsMut,
+    _env: Env,
+    _msg: MigrateMsg,
+) -> Result<Response, BassetContractError> {
+    migrate_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "migrate"),
+        attr("version", CONTRACT_VERSION),
+    ]))
+}
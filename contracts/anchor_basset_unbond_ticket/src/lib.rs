@@ -0,0 +1,4 @@
+pub mod contract;
+
+#[cfg(test)]
+mod testing;
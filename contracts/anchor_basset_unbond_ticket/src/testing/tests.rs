@@ -0,0 +1,143 @@
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use cosmwasm_std::{from_binary, Decimal, DepsMut, Uint128};
+
+use basset::unbond_ticket::{ExecuteMsg, InstantiateMsg, UnbondTicket};
+use cw721::{NftInfoResponse, NumTokensResponse, OwnerOfResponse};
+use cw721_base::{ContractError, QueryMsg};
+
+use crate::contract::{execute, instantiate, query};
+
+const HUB: &str = "hub";
+
+fn do_init(deps: DepsMut) {
+    let msg = InstantiateMsg {
+        name: "bluna unbond ticket".to_string(),
+        symbol: "UBLUNA".to_string(),
+        hub_contract: HUB.to_string(),
+    };
+    instantiate(deps, mock_env(), mock_info("creator", &[]), msg).unwrap();
+}
+
+fn sample_ticket() -> UnbondTicket {
+    UnbondTicket {
+        batch_id: 3,
+        amount: Uint128::new(100),
+        applied_exchange_rate: Decimal::percent(98),
+    }
+}
+
+fn mint_msg(token_id: &str, owner: &str) -> ExecuteMsg {
+    ExecuteMsg::Mint {
+        token_id: token_id.to_string(),
+        owner: owner.to_string(),
+        ticket: sample_ticket(),
+    }
+}
+
+/// Covers minting by the hub and the ticket data kept as extension.
+#[test]
+fn proper_mint() {
+    let mut deps = mock_dependencies(&[]);
+    do_init(deps.as_mut());
+
+    // only the hub mints
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        mint_msg("1", "bob"),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(HUB, &[]),
+        mint_msg("1", "bob"),
+    )
+    .unwrap();
+
+    let info: NftInfoResponse<UnbondTicket> = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::NftInfo {
+                token_id: "1".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(info.extension, sample_ticket());
+
+    // the ticket moves like any cw721 token
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        ExecuteMsg::TransferNft {
+            recipient: "alice".to_string(),
+            token_id: "1".to_string(),
+        },
+    )
+    .unwrap();
+    let owner: OwnerOfResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::OwnerOf {
+                token_id: "1".to_string(),
+                include_expired: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(owner.owner, "alice");
+}
+
+/// Covers burning, which only the hub can do.
+#[test]
+fn proper_burn() {
+    let mut deps = mock_dependencies(&[]);
+    do_init(deps.as_mut());
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(HUB, &[]),
+        mint_msg("1", "bob"),
+    )
+    .unwrap();
+
+    let burn = ExecuteMsg::Burn {
+        token_id: "1".to_string(),
+    };
+    // not even the holder can burn the ticket
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bob", &[]),
+        burn.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    execute(deps.as_mut(), mock_env(), mock_info(HUB, &[]), burn.clone()).unwrap();
+
+    let count: NumTokensResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::NumTokens {}).unwrap()).unwrap();
+    assert_eq!(count.count, 0);
+    assert!(query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::OwnerOf {
+            token_id: "1".to_string(),
+            include_expired: None,
+        },
+    )
+    .is_err());
+
+    // unknown tickets can not be burnt
+    execute(deps.as_mut(), mock_env(), mock_info(HUB, &[]), burn).unwrap_err();
+}
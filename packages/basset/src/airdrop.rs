@@ -22,10 +22,14 @@ pub enum ExecuteMsg {
         proof: Vec<String>,
     },
     UpdateConfig {
-        owner: Option<String>,
         hub_contract: Option<String>,
         reward_contract: Option<String>,
     },
+    ProposeNewOwner {
+        new_owner: String,
+    },
+    AcceptOwnership {},
+    CancelOwnershipProposal {},
     AddAirdropInfo {
         airdrop_token: String,
         airdrop_info: AirdropInfo,
@@ -50,6 +54,9 @@ pub enum QueryMsg {
     },
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum MIRAirdropHandleMsg {
@@ -92,6 +99,7 @@ pub struct AirdropInfo {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ConfigResponse {
     pub owner: String,
+    pub pending_owner: Option<String>,
     pub hub_contract: String,
     pub reward_contract: String,
     pub airdrop_tokens: Vec<String>,
@@ -1,4 +1,5 @@
-use cosmwasm_std::{OverflowError, StdError};
+use crate::hub::PausableOperation;
+use cosmwasm_std::{OverflowError, StdError, Uint128};
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -23,4 +24,124 @@ pub enum ContractError {
 
     #[error("Asset mismatch")]
     AssetMismatch {},
+
+    #[error("No {denom} assets are provided to bond")]
+    NoAssetsProvided { denom: String },
+
+    #[error("More than one coin is sent; only one asset is supported")]
+    MultipleAssets {},
+
+    #[error("Validator {validator} is not whitelisted")]
+    ValidatorNotWhitelisted { validator: String },
+
+    #[error("The specified address {address} is not a validator")]
+    NotAValidator { address: String },
+
+    #[error("Cannot remove the last whitelisted validator")]
+    LastValidator {},
+
+    #[error("There is no whitelisted validator")]
+    NoWhitelistedValidator {},
+
+    #[error("A validator must be specified to bond")]
+    ValidatorRequired {},
+
+    #[error("No target weights are configured for the whitelisted validators")]
+    NoTargetWeights {},
+
+    #[error("Validator {validator} is given more than one weight")]
+    DuplicateValidatorWeight { validator: String },
+
+    #[error("The total weight of the validators must be positive")]
+    ZeroTotalWeight {},
+
+    #[error("Undelegation amount {amount} exceeds the delegated amount {delegated}")]
+    UndelegationExceedsDelegation { amount: Uint128, delegated: Uint128 },
+
+    #[error("Burn amount must be greater than 1 ubluna")]
+    BatchTooSmall {},
+
+    #[error("No withdrawable {denom} assets are available yet")]
+    NothingToWithdraw { denom: String },
+
+    #[error("There is no balance in airdrop token contract {token}")]
+    NoAirdropBalance { token: String },
+
+    #[error("No rewards have accrued yet")]
+    NoRewardsAccrued {},
+
+    #[error("Decrease amount cannot exceed user balance: {balance}")]
+    DecreaseExceedsBalance { balance: Uint128 },
+
+    #[error("No asset is bonded by Hub")]
+    NoAssetBonded {},
+
+    #[error("There is a token info with this {token}")]
+    AirdropTokenExists { token: String },
+
+    #[error("There is no token info with this {token}")]
+    AirdropTokenNotFound { token: String },
+
+    #[error("Cannot migrate contract {found} to {expected}")]
+    ContractNameMismatch { expected: String, found: String },
+
+    #[error("Cannot downgrade contract from {stored} to {new}")]
+    CannotDowngrade { stored: String, new: String },
+
+    #[error("Invalid contract version {version}")]
+    InvalidContractVersion { version: String },
+
+    #[error("The wait list is still being migrated, run MigrateWaitList first")]
+    WaitListMigrationPending {},
+
+    #[error("The {operation} operation is paused")]
+    OperationPaused { operation: PausableOperation },
+
+    #[error("No ownership transfer is pending")]
+    NoPendingOwner {},
+
+    #[error("The owner is already set, propose a new owner instead")]
+    OwnerAlreadySet {},
+
+    #[error("Unbond history of batch {batch_id} not found")]
+    UnbondHistoryNotFound { batch_id: u64 },
+
+    #[error("No exchange rate is recorded at or before {time}")]
+    NoRateCheckpoint { time: u64 },
+
+    #[error("The current batch can not be submitted before {ready_at}")]
+    BatchNotReady { ready_at: u64 },
+
+    #[error("The current batch has no unbond request")]
+    EmptyBatch {},
+
+    #[error("Cannot cancel more than the unbond request of the current batch: {requested}")]
+    CancelExceedsRequest { requested: Uint128 },
+
+    #[error("No unbond ticket contract is registered")]
+    UnbondTicketsDisabled {},
+
+    #[error("The unbond ticket contract can not change while tickets are outstanding")]
+    OutstandingUnbondTickets {},
+
+    #[error("The batch of unbond ticket {ticket_id} is already submitted")]
+    TicketBatchSubmitted { ticket_id: String },
+
+    #[error("Withdraw either batch ids or ticket ids, not both")]
+    WithdrawSelectionConflict {},
+
+    #[error("The {name} can not be more than one")]
+    FractionTooLarge { name: String },
+
+    #[error("Not enough liquidity in the buffer, available: {available}")]
+    InsufficientLiquidity { available: Uint128 },
+
+    #[error("{asset} is already distributed as a reward")]
+    RewardAssetAlreadyTracked { asset: String },
+
+    #[error("{asset} is not distributed as a reward")]
+    RewardAssetNotTracked { asset: String },
+
+    #[error("No more than {max} reward assets can be distributed")]
+    TooManyRewardAssets { max: usize },
 }
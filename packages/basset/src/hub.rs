@@ -2,9 +2,16 @@ use cosmwasm_std::{Binary, CanonicalAddr, Decimal, Uint128};
 use cw20::Cw20ReceiveMsg;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::reward::RewardAssetAmount;
 
 pub type UnbondRequest = Vec<(u64, Uint128)>;
 
+/// Raw storage key of the paused operations in the hub.
+/// The reward and token contracts read it to check their own operations.
+pub const PAUSED_OPERATIONS_KEY: &str = "paused_operations";
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
     pub epoch_period: u64,
@@ -14,6 +21,8 @@ pub struct InstantiateMsg {
     pub er_threshold: Decimal,
     pub reward_denom: String,
     pub validator: String,
+    #[serde(default)]
+    pub reward_mode: RewardMode,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
@@ -27,6 +36,51 @@ pub struct State {
     pub last_processed_batch: u64,
 }
 
+/// Strategy used by the hub to choose the validator of a bond
+/// when the bonder does not name one.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DelegationStrategy {
+    /// The bonder must name a whitelisted validator.
+    UserChosen,
+    /// Delegate to the whitelisted validator holding the least stake of the hub.
+    LeastDelegated,
+    /// Delegate to the whitelisted validator furthest below its target weight.
+    TargetWeights,
+}
+
+impl Default for DelegationStrategy {
+    fn default() -> Self {
+        DelegationStrategy::UserChosen
+    }
+}
+
+/// What the hub does with the staking rewards it withdraws.
+/// The mode is chosen when the hub is instantiated.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RewardMode {
+    /// Swap the rewards to the reward denom and distribute them
+    /// to the holders through the reward contract.
+    Distribute,
+    /// Swap the rewards to the underlying denom and delegate them again,
+    /// so that they accrue to the exchange rate. Only the reward assets are distributed
+    /// to the holders; the rewards the reward contract can not swap are left for the next round.
+    Compound,
+}
+
+impl Default for RewardMode {
+    fn default() -> Self {
+        RewardMode::Distribute
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ValidatorWeight {
+    pub validator: String,
+    pub weight: u64,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
     pub creator: CanonicalAddr,
@@ -46,6 +100,35 @@ impl State {
     }
 }
 
+/// Operations of the bAsset contracts that the guardian can pause.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PausableOperation {
+    /// `Bond` on the hub
+    Bond,
+    /// `Receive(Unbond)` on the hub
+    Unbond,
+    /// `WithdrawUnbonded` on the hub
+    WithdrawUnbonded,
+    /// `ClaimRewards` on the reward contract
+    ClaimRewards,
+    /// transfers and sends of the token contract
+    Transfer,
+}
+
+impl fmt::Display for PausableOperation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            PausableOperation::Bond => "bond",
+            PausableOperation::Unbond => "unbond",
+            PausableOperation::WithdrawUnbonded => "withdraw_unbonded",
+            PausableOperation::ClaimRewards => "claim_rewards",
+            PausableOperation::Transfer => "transfer",
+        };
+        write!(f, "{}", name)
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
@@ -53,14 +136,21 @@ pub enum ExecuteMsg {
     /// Owner's operations
     ////////////////////
 
-    /// Set the owener
+    /// Set the reward, token and airdrop registry contracts
     UpdateConfig {
-        owner: Option<String>,
         reward_contract: Option<String>,
         token_contract: Option<String>,
         airdrop_registry_contract: Option<String>,
     },
 
+    /// Propose a new owner, who has to accept the ownership
+    ProposeNewOwner {
+        new_owner: String,
+    },
+
+    /// Drop the pending ownership transfer
+    CancelOwnershipProposal {},
+
     /// Register receives the reward contract address
     RegisterValidator {
         validator: String,
@@ -77,17 +167,83 @@ pub enum ExecuteMsg {
         unbonding_period: Option<u64>,
         peg_recovery_fee: Option<Decimal>,
         er_threshold: Option<Decimal>,
+        delegation_strategy: Option<DelegationStrategy>,
+    },
+
+    /// Cap the amount moved by a rebalance; `None` removes the cap
+    UpdateMaxRebalanceAmount {
+        amount: Option<Uint128>,
+    },
+
+    /// Replace the target weights used by the target weights strategy
+    UpdateValidatorWeights {
+        weights: Vec<ValidatorWeight>,
+    },
+
+    /// Set or remove the guardian that can pause operations
+    UpdateGuardian {
+        guardian: Option<String>,
+    },
+
+    /// Set or remove the cw721 contract of unbond tickets.
+    /// While set, each unbond request mints a ticket instead of joining the wait list.
+    UpdateUnbondTicketContract {
+        contract: Option<String>,
+    },
+
+    /// Update the fee kept by the liquidity buffer on instant unbonds
+    /// and the share of the buffer that can be lent at once
+    UpdateLiquidityBuffer {
+        fee: Option<Decimal>,
+        max_utilisation: Option<Decimal>,
+    },
+
+    /// Add the sent underlying coin to the liquidity buffer
+    DepositLiquidityBuffer {},
+
+    /// Take underlying coin out of the liquidity buffer
+    WithdrawLiquidityBuffer {
+        amount: Uint128,
+    },
+
+    /// Resume paused operations
+    Unpause {
+        operations: Vec<PausableOperation>,
+    },
+
+    ////////////////////
+    /// Guardian's operations
+    ////////////////////
+
+    /// Pause operations of the hub, reward and token contracts.
+    /// The owner can pause as well.
+    Pause {
+        operations: Vec<PausableOperation>,
     },
 
+    ////////////////////
+    /// Pending owner's operations
+    ////////////////////
+
+    /// Take over the ownership proposed by the owner
+    AcceptOwnership {},
+
     ////////////////////
     /// User's operations
     ////////////////////
 
     /// Receives `amount` in underlying coin denom from sender.
-    /// Delegate `amount` to a specific `validator`.
+    /// Delegate `amount` to `validator`, or to the validator picked
+    /// by the delegation strategy if none is given.
     /// Issue `amount` / exchange_rate for the user.
     Bond {
-        validator: String,
+        validator: Option<String>,
+    },
+
+    /// Bond the sent underlying coin like `Bond` and issue the bAsset to `recipient`.
+    BondFor {
+        recipient: String,
+        validator: Option<String>,
     },
 
     /// Update global index
@@ -95,12 +251,47 @@ pub enum ExecuteMsg {
         airdrop_hooks: Option<Vec<Binary>>,
     },
 
-    /// Send back unbonded coin to the user
-    WithdrawUnbonded {},
+    /// Send back unbonded coin to the user, or to `recipient` if given.
+    /// With `batch_ids`, only the requests of those batches are withdrawn.
+    /// With `ticket_ids`, the requests of those unbond tickets held by the user
+    /// are withdrawn instead, and the tickets are burnt.
+    WithdrawUnbonded {
+        batch_ids: Option<Vec<u64>>,
+        recipient: Option<String>,
+        ticket_ids: Option<Vec<String>>,
+    },
+
+    /// Undelegate the current batch once the epoch period has passed
+    SubmitBatch {},
+
+    /// Compute the withdraw rate of up to `limit` matured batches
+    ProcessBatches {
+        limit: Option<u32>,
+    },
+
+    /// Remove `amount` from the unbond request of the sender in the current batch,
+    /// which has not been submitted yet, and mint the bAsset back to the sender.
+    CancelUnbond {
+        amount: Uint128,
+    },
+
+    /// Cancel the unbond request held by a ticket of the sender, which must be in the
+    /// current batch, burn the ticket and mint the bAsset back to the sender.
+    CancelUnbondTicket {
+        ticket_id: String,
+    },
 
     /// Check whether the slashing has happened or not
     CheckSlashing {},
 
+    /// Redelegate stake towards the target weights of the validators
+    Rebalance {},
+
+    /// Move up to `limit` requests of the legacy wait list to the current layout
+    MigrateWaitList {
+        limit: Option<u32>,
+    },
+
     ////////////////////
     /// bAsset's operations
     ///////////////////
@@ -127,6 +318,11 @@ pub enum ExecuteMsg {
         airdrop_swap_contract: String,  // E.g. Contract address of MIR <> UST Terraswap Pair
         swap_msg: Binary,               // E.g. Base64-encoded JSON of PairHandleMsg::Swap
     },
+
+    /// Delegates the rewards received since `prev_balance` in the compound reward mode
+    RestakeHook {
+        prev_balance: Uint128,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -140,19 +336,81 @@ pub enum QueryMsg {
         address: String,
     },
     Parameters {},
+    /// Unbond requests of a user in ascending batch order, starting after `start_from`
     UnbondRequests {
         address: String,
+        start_from: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Unbond request held by a ticket, with the coins its holder can withdraw
+    UnbondTicket {
+        ticket_id: String,
+    },
+    /// Unbond requests of all the users in a batch
+    BatchUnbondRequests {
+        batch_id: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
     },
     AllHistory {
         start_from: Option<u64>,
         limit: Option<u32>,
     },
+    /// Status and timing of the batches in ascending order, starting after `start_from`,
+    /// with the open batch last
+    UnbondBatches {
+        start_from: Option<u64>,
+        limit: Option<u32>,
+    },
+    ValidatorWeights {},
+    PauseInfo {},
+    /// Simulate bonding `amount` of the underlying coin at the current block
+    SimulateBond {
+        amount: Uint128,
+    },
+    /// Simulate unbonding `amount` of bAsset at the current block
+    SimulateUnbond {
+        amount: Uint128,
+    },
+    /// State of the liquidity buffer and the coins available to instant unbonds
+    LiquidityBuffer {},
+    /// bAsset balance, unbond requests, withdrawable coins and rewards of a user,
+    /// with the unbond requests in ascending batch order, starting after `start_after`
+    Position {
+        address: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Detected slashings in ascending order, starting after `start_after`
+    SlashingHistory {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Exchange rate in effect at `time`, from the recorded checkpoints
+    ExchangeRateAt {
+        time: u64,
+    },
+    /// Time-weighted average of the exchange rate over the last `window` seconds
+    ExchangeRateTwap {
+        window: u64,
+    },
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum Cw20HookMsg {
     Unbond {},
+    /// Unbond the sent bAsset for `recipient`,
+    /// who owns the request and withdraws the unbonded coins
+    UnbondFor {
+        recipient: String,
+    },
+    /// Unbond the sent bAsset and receive its value right away
+    /// from the liquidity buffer, minus the buffer fee
+    InstantUnbond {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -165,6 +423,37 @@ pub struct UnbondHistory {
     pub released: bool,
 }
 
+/// A drop of the bonded amount of the hub found by a slashing check.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SlashingEvent {
+    pub id: u64,
+    /// Block time of the check that found the slashing
+    pub time: u64,
+    /// Block height of the check that found the slashing
+    pub height: u64,
+    pub bonded_before: Uint128,
+    pub bonded_after: Uint128,
+    pub exchange_rate_before: Decimal,
+    pub exchange_rate_after: Decimal,
+    /// (validator, lost amount) pairs, from the delegations expected for each validator
+    pub validators: Vec<(String, Uint128)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExchangeRateAtResponse {
+    pub exchange_rate: Decimal,
+    /// Time the exchange rate was recorded
+    pub checkpoint_time: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExchangeRateTwapResponse {
+    /// Start of the averaged period, later than requested if the history is shorter
+    pub start_time: u64,
+    pub end_time: u64,
+    pub twap: Decimal,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct StateResponse {
     pub exchange_rate: Decimal,
@@ -179,9 +468,11 @@ pub struct StateResponse {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ConfigResponse {
     pub owner: String,
+    pub pending_owner: Option<String>,
     pub reward_contract: Option<String>,
     pub token_contract: Option<String>,
     pub airdrop_registry_contract: Option<String>,
+    pub unbond_ticket_contract: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -195,6 +486,32 @@ pub struct CurrentBatchResponse {
     pub requested_with_fee: Uint128,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateBondResponse {
+    /// bAsset minted to the user, after the peg recovery fee
+    pub minted: Uint128,
+    /// bAsset kept as peg recovery fee
+    pub peg_recovery_fee: Uint128,
+    /// Exchange rate of the hub after the bond
+    pub exchange_rate: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateUnbondResponse {
+    /// bAsset burned from the user
+    pub burned: Uint128,
+    /// bAsset added to the unbond batch, after the peg recovery fee
+    pub unbonded: Uint128,
+    /// bAsset kept as peg recovery fee
+    pub peg_recovery_fee: Uint128,
+    /// Exchange rate of the hub after the unbond
+    pub exchange_rate: Decimal,
+    /// Underlying coin expected for `unbonded` at the resulting exchange rate
+    pub estimated_amount: Uint128,
+    /// Time the batch holding the request is expected to be released
+    pub estimated_release_time: u64,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct WithdrawableUnbondedResponse {
     pub withdrawable: Uint128,
@@ -205,7 +522,121 @@ pub struct UnbondRequestsResponse {
     pub requests: UnbondRequest,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidityBufferResponse {
+    /// Underlying coin held by the buffer
+    pub balance: Uint128,
+    /// Underlying coin paid out for requests that are not released yet
+    pub lent: Uint128,
+    pub fee: Decimal,
+    pub max_utilisation: Decimal,
+    /// Underlying coin that can be paid out by instant unbonds now
+    pub available: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BatchUnbondRequestsResponse {
+    pub batch_id: u64,
+    /// (user's address, requested amount) pairs
+    pub requests: Vec<(String, Uint128)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SlashingHistoryResponse {
+    pub history: Vec<SlashingEvent>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct AllHistoryResponse {
     pub history: Vec<UnbondHistory>,
 }
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UnbondBatchStatus {
+    /// The current batch, collecting unbond requests until the epoch period has passed
+    Open,
+    /// The current batch once the epoch period has passed, waiting to be undelegated
+    PendingUndelegation,
+    /// Undelegated and waiting for the unbonded coins
+    Unbonding,
+    /// The unbonded coins can be withdrawn
+    Released,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UnbondBatch {
+    pub batch_id: u64,
+    pub status: UnbondBatchStatus,
+    /// bAsset requested in the batch, after the peg recovery fee
+    pub requested: Uint128,
+    /// Time the batch was undelegated, none for the current batch
+    pub submission_time: Option<u64>,
+    /// Time the current batch can be undelegated, none for the other batches
+    pub next_epoch_time: Option<u64>,
+    /// Time the unbonded coins are expected in the hub
+    pub expected_release_time: u64,
+    /// Underlying coin expected for the batch at its exchange rate
+    pub expected_amount: Uint128,
+    /// Underlying coin actually received for the batch, once released
+    pub received_amount: Option<Uint128>,
+    /// Underlying coin lost to slashing while unbonding, once released
+    pub slashed_amount: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UnbondBatchesResponse {
+    pub batches: Vec<UnbondBatch>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PositionUnbondRequest {
+    /// bAsset requested by the user in the batch
+    pub amount: Uint128,
+    pub batch: UnbondBatch,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PositionResponse {
+    pub address: String,
+    /// bAsset held by the user
+    pub balance: Uint128,
+    pub exchange_rate: Decimal,
+    /// Underlying coin value of `balance` at the exchange rate
+    pub balance_value: Uint128,
+    /// A page of the outstanding unbond requests of the user
+    pub unbond_requests: Vec<PositionUnbondRequest>,
+    /// Underlying coin of all the released requests the user can withdraw,
+    /// not only of the ones in `unbond_requests`
+    pub withdrawable: Uint128,
+    /// Rewards accrued by the user in the reward contract
+    pub accrued_rewards: Uint128,
+    /// Rewards accrued by the user in each of the reward assets
+    pub asset_rewards: Vec<RewardAssetAmount>,
+    /// Unbond tickets held by the user, at most 30;
+    /// `UnbondTicket` looks up the rest
+    pub unbond_tickets: Vec<UnbondTicketResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UnbondTicketResponse {
+    pub ticket_id: String,
+    /// bAsset requested, after the peg recovery fee
+    pub amount: Uint128,
+    /// Exchange rate of the hub when the request was made
+    pub applied_exchange_rate: Decimal,
+    pub batch: UnbondBatch,
+    /// Underlying coin the holder of the ticket can withdraw
+    pub withdrawable: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ValidatorWeightsResponse {
+    pub weights: Vec<ValidatorWeight>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PauseInfoResponse {
+    pub guardian: Option<String>,
+    pub paused_operations: Vec<PausableOperation>,
+}
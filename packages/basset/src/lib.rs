@@ -4,7 +4,10 @@ pub use tax_querier::deduct_tax;
 pub mod airdrop;
 pub mod contract_error;
 pub mod hub;
+pub mod ownership;
 pub mod reward;
+pub mod unbond_ticket;
+pub mod version;
 
 #[cfg(test)]
 mod mock_querier;
@@ -0,0 +1,69 @@
+use crate::contract_error::ContractError;
+use cosmwasm_std::{Addr, CanonicalAddr, Deps, DepsMut, StdResult};
+use cw_storage_plus::Item;
+
+/// The address an ownership transfer has been proposed to.
+/// Ownership only moves once this address accepts it.
+pub const PENDING_OWNER: Item<CanonicalAddr> = Item::new("pending_owner");
+
+/// Propose `new_owner` as the next owner of the contract.
+/// Only the current `owner` is allowed to propose; a new proposal replaces the previous one.
+pub fn propose_new_owner(
+    deps: DepsMut,
+    sender: &Addr,
+    owner: &CanonicalAddr,
+    new_owner: &str,
+) -> Result<(), ContractError> {
+    let sender_raw = deps.api.addr_canonicalize(sender.as_str())?;
+    if &sender_raw != owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let new_owner_raw = deps.api.addr_canonicalize(new_owner)?;
+    PENDING_OWNER.save(deps.storage, &new_owner_raw)?;
+    Ok(())
+}
+
+/// Drop the pending ownership transfer.
+/// Only the current `owner` is allowed to cancel.
+pub fn cancel_ownership_proposal(
+    deps: DepsMut,
+    sender: &Addr,
+    owner: &CanonicalAddr,
+) -> Result<(), ContractError> {
+    let sender_raw = deps.api.addr_canonicalize(sender.as_str())?;
+    if &sender_raw != owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if PENDING_OWNER.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::NoPendingOwner {});
+    }
+    PENDING_OWNER.remove(deps.storage);
+    Ok(())
+}
+
+/// Accept the pending ownership transfer and return the new owner,
+/// the caller is responsible for storing it in its config.
+/// Only the pending owner is allowed to accept.
+pub fn accept_ownership(deps: DepsMut, sender: &Addr) -> Result<CanonicalAddr, ContractError> {
+    let pending_owner = PENDING_OWNER
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingOwner {})?;
+
+    let sender_raw = deps.api.addr_canonicalize(sender.as_str())?;
+    if sender_raw != pending_owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    PENDING_OWNER.remove(deps.storage);
+    Ok(pending_owner)
+}
+
+/// Return the address ownership has been proposed to, if any.
+pub fn query_pending_owner(deps: Deps) -> StdResult<Option<String>> {
+    PENDING_OWNER
+        .may_load(deps.storage)?
+        .map(|owner| deps.api.addr_humanize(&owner).map(|addr| addr.to_string()))
+        .transpose()
+}
@@ -2,6 +2,7 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_std::{Decimal, Uint128};
+use std::fmt;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
@@ -9,6 +10,24 @@ pub struct InstantiateMsg {
     pub reward_denom: String,
 }
 
+/// A reward distributed as it is received, with its own global index,
+/// instead of being swapped to the reward denom.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RewardAsset {
+    Native { denom: String },
+    Token { contract_addr: String },
+}
+
+impl fmt::Display for RewardAsset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RewardAsset::Native { denom } => write!(f, "{}", denom),
+            RewardAsset::Token { contract_addr } => write!(f, "{}", contract_addr),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
@@ -22,6 +41,54 @@ pub enum ExecuteMsg {
     /// Update the global index
     UpdateGlobalIndex {},
 
+    /// Swap all of the balances, except the rewards owed to the holders,
+    /// to the underlying denom of the hub.
+    SwapToUnderlying { underlying_denom: String },
+
+    /// Send the underlying denom back to the hub to be restaked.
+    SendUnderlying { underlying_denom: String },
+
+    /// Update the global index of the reward assets only, the reward denom being compounded
+    UpdateRewardAssetIndexes {},
+
+    /// Propose a new owner, who has to accept the ownership
+    ProposeNewOwner { new_owner: String },
+
+    /// Drop the pending ownership transfer
+    CancelOwnershipProposal {},
+
+    /// Update the protocol fee taken from the rewards and the address receiving it
+    UpdateConfig {
+        protocol_fee: Option<Decimal>,
+        fee_collector: Option<String>,
+        /// Stop taking the protocol fee until a new fee collector is set
+        remove_fee_collector: Option<bool>,
+    },
+
+    /// Track `asset` with its own global index instead of swapping it to the reward denom
+    AddRewardAsset { asset: RewardAsset },
+
+    /// Stop distributing `asset` and send the balance of the contract in it not distributed yet
+    /// to `recipient`, the sender by default; the holders keep claiming what they accrued
+    RemoveRewardAsset {
+        asset: RewardAsset,
+        recipient: Option<String>,
+    },
+
+    /// Skip the reward swaps of `denom` whose spread against the oracle rate exceeds
+    /// `max_spread`; `None` removes the limit
+    UpdateMaxSpread {
+        denom: String,
+        max_spread: Option<Decimal>,
+    },
+
+    ////////////////////
+    /// Pending owner's operations
+    ///////////////////
+
+    /// Take over the ownership proposed by the owner
+    AcceptOwnership {},
+
     ////////////////////
     /// bAsset's operations
     ///////////////////
@@ -41,6 +108,25 @@ pub enum ExecuteMsg {
 
     /// return the accrued reward in uusd to the user.
     ClaimRewards { recipient: Option<String> },
+
+    /// Swap the accrued reward to the underlying denom and bond it through the hub,
+    /// which issues the bAsset to the user.
+    /// Fail if the swap spread is above `max_spread`, or the max spread of the reward denom.
+    ClaimAndCompound {
+        validator: Option<String>,
+        max_spread: Option<Decimal>,
+    },
+
+    ////////////////////
+    /// internal operations
+    ///////////////////
+
+    /// Bond the underlying coins received since `prev_balance` for `holder`
+    CompoundHook {
+        holder: String,
+        prev_balance: Uint128,
+        validator: Option<String>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -62,8 +148,15 @@ pub enum QueryMsg {
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ConfigResponse {
+    pub owner: Option<String>,
+    pub pending_owner: Option<String>,
     pub hub_contract: String,
     pub reward_denom: String,
+    pub protocol_fee: Decimal,
+    pub fee_collector: Option<String>,
+    pub reward_assets: Vec<RewardAsset>,
+    pub retired_reward_assets: Vec<RewardAsset>,
+    pub max_spreads: Vec<(String, Decimal)>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -71,11 +164,29 @@ pub struct StateResponse {
     pub global_index: Decimal,
     pub total_balance: Uint128,
     pub prev_reward_balance: Uint128,
+    pub total_protocol_fee: Uint128,
+    pub reward_indexes: Vec<RewardIndexResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardIndexResponse {
+    pub asset: RewardAsset,
+    pub global_index: Decimal,
+    pub prev_balance: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct AccruedRewardsResponse {
     pub rewards: Uint128,
+    /// Rewards accrued in each of the reward assets
+    #[serde(default)]
+    pub asset_rewards: Vec<RewardAssetAmount>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardAssetAmount {
+    pub asset: RewardAsset,
+    pub amount: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -84,6 +195,14 @@ pub struct HolderResponse {
     pub balance: Uint128,
     pub index: Decimal,
     pub pending_rewards: Decimal,
+    pub asset_rewards: Vec<HolderAssetRewards>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HolderAssetRewards {
+    pub asset: RewardAsset,
+    pub index: Decimal,
+    pub pending_rewards: Decimal,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -92,4 +211,8 @@ pub struct HoldersResponse {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct MigrateMsg {}
+pub struct MigrateMsg {
+    /// Owner to assign to a reward contract instantiated without one
+    #[serde(default)]
+    pub owner: Option<String>,
+}
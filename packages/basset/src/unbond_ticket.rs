@@ -0,0 +1,70 @@
+use cosmwasm_std::{Binary, Decimal, Uint128};
+use cw721::Expiration;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstantiateMsg {
+    pub name: String,
+    pub symbol: String,
+    /// The hub mints and burns the tickets
+    pub hub_contract: String,
+}
+
+/// The unbond request a ticket stands for, kept as the cw721 extension of the ticket.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UnbondTicket {
+    pub batch_id: u64,
+    /// bAsset requested, after the peg recovery fee
+    pub amount: Uint128,
+    /// Exchange rate of the hub when the request was made
+    pub applied_exchange_rate: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    ////////////////////
+    /// cw721 operations
+    ////////////////////
+
+    /// Move a ticket to another account without triggering actions
+    TransferNft { recipient: String, token_id: String },
+    /// Move a ticket to a contract and trigger an action on it
+    SendNft {
+        contract: String,
+        token_id: String,
+        msg: Binary,
+    },
+    /// Allow `spender` to transfer or send the ticket
+    Approve {
+        spender: String,
+        token_id: String,
+        expires: Option<Expiration>,
+    },
+    /// Remove a previously granted approval
+    Revoke { spender: String, token_id: String },
+    /// Allow `operator` to transfer or send any ticket of the owner
+    ApproveAll {
+        operator: String,
+        expires: Option<Expiration>,
+    },
+    /// Remove a previously granted operator
+    RevokeAll { operator: String },
+
+    ////////////////////
+    /// Hub's operations
+    ////////////////////
+
+    /// Mint a ticket for an unbond request
+    Mint {
+        token_id: String,
+        owner: String,
+        ticket: UnbondTicket,
+    },
+    /// Burn a ticket once its request is withdrawn
+    Burn { token_id: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
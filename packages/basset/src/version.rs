@@ -0,0 +1,98 @@
+use crate::contract_error::ContractError;
+use cosmwasm_std::Storage;
+use cw2::{get_contract_version, set_contract_version, CONTRACT};
+use semver::Version;
+
+/// Verify the cw2 version stored by the running code and record the new one.
+/// Returns the previous version, or `None` if the contract was instantiated
+/// before it recorded a cw2 version.
+pub fn migrate_contract_version(
+    storage: &mut dyn Storage,
+    name: &str,
+    version: &str,
+) -> Result<Option<Version>, ContractError> {
+    let new_version = parse_version(version)?;
+
+    let stored_version = match CONTRACT.may_load(storage)? {
+        Some(_) => {
+            let stored = get_contract_version(storage)?;
+            if stored.contract != name {
+                return Err(ContractError::ContractNameMismatch {
+                    expected: name.to_string(),
+                    found: stored.contract,
+                });
+            }
+
+            let stored_version = parse_version(&stored.version)?;
+            if stored_version > new_version {
+                return Err(ContractError::CannotDowngrade {
+                    stored: stored.version,
+                    new: version.to_string(),
+                });
+            }
+            Some(stored_version)
+        }
+        None => None,
+    };
+
+    set_contract_version(storage, name, version)?;
+    Ok(stored_version)
+}
+
+/// Whether a migration from `stored` must run the state migration introduced in `version`.
+pub fn needs_migration(stored: &Option<Version>, version: &str) -> Result<bool, ContractError> {
+    let target = parse_version(version)?;
+    Ok(stored.as_ref().map_or(true, |stored| *stored < target))
+}
+
+fn parse_version(version: &str) -> Result<Version, ContractError> {
+    Version::parse(version).map_err(|_| ContractError::InvalidContractVersion {
+        version: version.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    #[test]
+    fn legacy_contract_without_version() {
+        let mut storage = MockStorage::new();
+        let stored = migrate_contract_version(&mut storage, "crates.io:hub", "1.1.0").unwrap();
+        assert_eq!(stored, None);
+        assert!(needs_migration(&stored, "1.1.0").unwrap());
+
+        let version = get_contract_version(&storage).unwrap();
+        assert_eq!(version.contract, "crates.io:hub");
+        assert_eq!(version.version, "1.1.0");
+    }
+
+    #[test]
+    fn refuse_downgrade_and_foreign_contract() {
+        let mut storage = MockStorage::new();
+        set_contract_version(&mut storage, "crates.io:hub", "1.2.0").unwrap();
+
+        let err = migrate_contract_version(&mut storage, "crates.io:hub", "1.1.0").unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::CannotDowngrade {
+                stored: "1.2.0".to_string(),
+                new: "1.1.0".to_string(),
+            }
+        );
+
+        let err = migrate_contract_version(&mut storage, "crates.io:token", "1.3.0").unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ContractNameMismatch {
+                expected: "crates.io:token".to_string(),
+                found: "crates.io:hub".to_string(),
+            }
+        );
+
+        let stored = migrate_contract_version(&mut storage, "crates.io:hub", "1.3.0").unwrap();
+        assert_eq!(stored, Some(Version::new(1, 2, 0)));
+        assert!(!needs_migration(&stored, "1.1.0").unwrap());
+    }
+}